@@ -89,6 +89,13 @@ fn generate(f: &mut dyn Write) {
     emit(f, "SIEVE_INFO", "(u16, u16)", &sieve_info[..]);
 }
 
+/// Write the primes below 65536, for trial division, to `f`.
+fn generate_primes(f: &mut dyn Write) {
+    let mut primes = vec![2];
+    primes.extend(odd_primes_below_65536());
+    emit(f, "PRIMES", "u16", &primes[..]);
+}
+
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
     let manifest_path = env::var("OUT_DIR").expect("cargo should have set this");
@@ -96,4 +103,7 @@ fn main() {
     path.push("constants.rs");
     let mut f = File::create(path).expect("cannot create constants.rs");
     generate(&mut f);
+    let path = PathBuf::from(&manifest_path).join("primes.rs");
+    let mut f = File::create(path).expect("cannot create primes.rs");
+    generate_primes(&mut f);
 }
@@ -0,0 +1,188 @@
+//! Merging accumulator updates computed independently from the same state.
+//!
+//! Each operator turns its share of the deletions and additions into an
+//! `UpdateBatch` against the current accumulator. Batches that delete and add
+//! disjoint elements merge into one, which is applied as a single transition:
+//! all deletions, then all additions, with a proof for each half.
+
+use super::{prime_product, shamir_trick, Accumulator, Member, Poe, Witness};
+use crate::group::{ClassGroup, UnknownOrderGroup};
+use anyhow::{bail, ensure, Result};
+use rug::Integer;
+
+/// Deletions and additions against one accumulator, not yet applied.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UpdateBatch<G: UnknownOrderGroup = ClassGroup> {
+    base: Accumulator<G>,
+    /// `base` without the deleted members.
+    deleted: Accumulator<G>,
+    deletions: Integer,
+    additions: Integer,
+}
+
+/// Evidence that an accumulator was turned into another by deleting some
+/// members and then adding some elements.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UpdateProof<G: UnknownOrderGroup = ClassGroup> {
+    /// The accumulator after the deletions but before the additions.
+    pub deleted: Accumulator<G>,
+    pub delete_proof: Poe<G>,
+    pub add_proof: Poe<G>,
+}
+
+impl<G: UnknownOrderGroup> UpdateBatch<G> {
+    /// A batch that deletes members from `accumulator`, given their
+    /// witnesses, and adds `additions`.
+    ///
+    /// Fails if a witness is invalid or a member is listed twice.
+    pub fn new<T: Member>(
+        accumulator: &Accumulator<G>,
+        deletions: &[(T, Witness<G>)],
+        additions: &[T],
+    ) -> Result<Self> {
+        let (value, product) = accumulator.aggregate(deletions)?;
+        Ok(Self {
+            base: accumulator.clone(),
+            deleted: Accumulator { value },
+            deletions: product,
+            additions: prime_product(additions),
+        })
+    }
+
+    /// The accumulator the batch applies to.
+    pub fn base(&self) -> &Accumulator<G> {
+        &self.base
+    }
+
+    /// Combines two batches against the same accumulator into one that makes
+    /// the changes of both.
+    ///
+    /// Fails if the batches have different bases, or both delete or both add
+    /// the same element.
+    pub fn merge(&self, other: &Self) -> Result<Self> {
+        ensure!(
+            self.base == other.base,
+            "batches apply to different accumulators"
+        );
+        let value = match shamir_trick::<G>(
+            &self.deleted.value,
+            &self.deletions,
+            &other.deleted.value,
+            &other.deletions,
+        ) {
+            Some(value) => value,
+            None => bail!("a member is deleted by both batches"),
+        };
+        ensure!(
+            Integer::from(self.additions.gcd_ref(&other.additions)) == 1,
+            "an element is added by both batches"
+        );
+        Ok(Self {
+            base: self.base.clone(),
+            deleted: Accumulator { value },
+            deletions: Integer::from(&self.deletions * &other.deletions),
+            additions: Integer::from(&self.additions * &other.additions),
+        })
+    }
+
+    /// The accumulator after the batch, and the proof of the transition.
+    pub fn apply(&self) -> (Accumulator<G>, UpdateProof<G>) {
+        let deleted = &self.deleted.value;
+        let value = G::pow(deleted, &self.additions);
+        let proof = UpdateProof {
+            delete_proof: Poe::<G>::prove(deleted, &self.deletions, &self.base.value),
+            add_proof: Poe::<G>::prove(deleted, &self.additions, &value),
+            deleted: self.deleted.clone(),
+        };
+        (Accumulator { value }, proof)
+    }
+}
+
+impl<G: UnknownOrderGroup> Accumulator<G> {
+    /// Checks a proof from `UpdateBatch::apply` that deleting `deletions`
+    /// from `self` and then adding `additions` gives `new`.
+    pub fn verify_update<T: Member>(
+        &self,
+        deletions: &[T],
+        additions: &[T],
+        new: &Self,
+        proof: &UpdateProof<G>,
+    ) -> bool {
+        G::is_elem(&proof.deleted.value)
+            && self.verify_delete(deletions, &proof.deleted, &proof.delete_proof)
+            && proof.deleted.verify_add(additions, new, &proof.add_proof)
+    }
+}
+
+#[cfg(feature = "proto")]
+mod proto {
+    use super::*;
+    use crate::proto::{message, read_fields, write_field, ProtoMessage};
+
+    impl ProtoMessage for UpdateProof<ClassGroup> {
+        fn encode_proto(&self) -> Vec<u8> {
+            let mut buf = Vec::new();
+            write_field(&mut buf, 1, &self.deleted.encode_proto());
+            write_field(&mut buf, 2, &self.delete_proof.encode_proto());
+            write_field(&mut buf, 3, &self.add_proof.encode_proto());
+            buf
+        }
+
+        fn decode_proto(bytes: &[u8]) -> Result<Self> {
+            let fields = read_fields(bytes)?;
+            Ok(Self {
+                deleted: message(&fields, 1)?,
+                delete_proof: message(&fields, 2)?,
+                add_proof: message(&fields, 3)?,
+            })
+        }
+    }
+
+    /// The `AccumulatorUpdate` message: the accumulator and proof returned
+    /// by `UpdateBatch::apply`.
+    impl ProtoMessage for (Accumulator<ClassGroup>, UpdateProof<ClassGroup>) {
+        fn encode_proto(&self) -> Vec<u8> {
+            let mut buf = Vec::new();
+            write_field(&mut buf, 1, &self.0.encode_proto());
+            write_field(&mut buf, 2, &self.1.encode_proto());
+            buf
+        }
+
+        fn decode_proto(bytes: &[u8]) -> Result<Self> {
+            let fields = read_fields(bytes)?;
+            Ok((message(&fields, 1)?, message(&fields, 2)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let (acc, w) = Accumulator::<ClassGroup>::empty().add(&[b"a", b"b", b"c"]);
+        let first = UpdateBatch::new(&acc, &[(b"a", w[0].clone())], &[b"x"]).unwrap();
+        let second = UpdateBatch::new(&acc, &[(b"b", w[1].clone())], &[b"y", b"z"]).unwrap();
+
+        let merged = first.merge(&second).unwrap();
+        let (new, proof) = merged.apply();
+        assert_eq!(
+            new,
+            Accumulator::<ClassGroup>::empty()
+                .add(&[b"c", b"x", b"y", b"z"])
+                .0
+        );
+        assert_eq!(merged, second.merge(&first).unwrap());
+        assert!(acc.verify_update(&[b"b", b"a"], &[b"x", b"y", b"z"], &new, &proof));
+        assert!(!acc.verify_update(&[b"a"], &[b"x", b"y", b"z"], &new, &proof));
+        assert!(!acc.verify_update(&[b"a", b"b"], &[b"x"], &new, &proof));
+
+        assert!(first.merge(&first).is_err());
+        let overlap = UpdateBatch::new(&acc, &[], &[b"x"]).unwrap();
+        assert!(first.merge(&overlap).is_err());
+        let (new, _) = first.apply();
+        let later = UpdateBatch::new(&new, &[], &[b"y"]).unwrap();
+        assert!(first.merge(&later).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! Key-value map commitments from a pair of accumulators.
+//!
+//! One accumulator holds the keys and the other holds every `(key, value)`
+//! entry. A key maps to a value if the entry is a member of the second, and
+//! is absent if it is not a member of the first. The committer keeps keys
+//! unique; `CommittedMap` enforces this.
+
+use super::{Accumulator, MembershipProof, NonMembershipProof, Witness};
+use crate::group::{ClassGroup, UnknownOrderGroup};
+use anyhow::{ensure, Result};
+use std::collections::BTreeMap;
+
+/// Encodes an entry so that distinct `(key, value)` pairs never collide.
+fn entry(key: &[u8], value: &[u8]) -> Vec<u8> {
+    let mut buf = (key.len() as u64).to_be_bytes().to_vec();
+    buf.extend_from_slice(key);
+    buf.extend_from_slice(value);
+    buf
+}
+
+/// The published value of a committed map.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MapCommitment<G: UnknownOrderGroup = ClassGroup> {
+    keys: Accumulator<G>,
+    entries: Accumulator<G>,
+}
+
+/// A committed map, held by the party that proves its contents.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommittedMap<G: UnknownOrderGroup = ClassGroup> {
+    map: BTreeMap<Vec<u8>, Vec<u8>>,
+    commitment: MapCommitment<G>,
+}
+
+/// Evidence for what a key maps to, if anything.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MapProof<G: UnknownOrderGroup = ClassGroup> {
+    Present(MembershipProof<G>),
+    Absent(NonMembershipProof<G>),
+}
+
+impl<G: UnknownOrderGroup> CommittedMap<G> {
+    /// Commits to `entries`, whose keys must be distinct.
+    pub fn commit<K: AsRef<[u8]>, V: AsRef<[u8]>>(entries: &[(K, V)]) -> Result<Self> {
+        let mut map = BTreeMap::new();
+        for (k, v) in entries {
+            let k = k.as_ref();
+            ensure!(
+                map.insert(k.to_vec(), v.as_ref().to_vec()).is_none(),
+                "key {:?} is given twice",
+                k
+            );
+        }
+        let keys: Vec<_> = map.keys().collect();
+        let encoded: Vec<_> = map.iter().map(|(k, v)| entry(k, v)).collect();
+        let commitment = MapCommitment {
+            keys: Accumulator::<G>::empty().add(&keys).0,
+            entries: Accumulator::<G>::empty().add(&encoded).0,
+        };
+        Ok(Self { map, commitment })
+    }
+
+    pub fn commitment(&self) -> &MapCommitment<G> {
+        &self.commitment
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.map.get(key).map(Vec::as_slice)
+    }
+
+    /// Proves what `key` maps to, or that it is absent.
+    pub fn prove(&self, key: &[u8]) -> Result<MapProof<G>> {
+        match self.map.get(key) {
+            Some(value) => {
+                let rest: Vec<_> = self
+                    .map
+                    .iter()
+                    .filter(|(k, _)| k.as_slice() != key)
+                    .map(|(k, v)| entry(k, v))
+                    .collect();
+                let witness = Witness {
+                    value: Accumulator::<G>::empty().add(&rest).0.value,
+                };
+                let proof = self
+                    .commitment
+                    .entries
+                    .prove_membership(&[entry(key, value)], &witness)?;
+                Ok(MapProof::Present(proof))
+            }
+            None => {
+                let keys: Vec<_> = self.map.keys().map(Vec::as_slice).collect();
+                let proof = self.commitment.keys.prove_nonmembership(&keys, &[key])?;
+                Ok(MapProof::Absent(proof))
+            }
+        }
+    }
+}
+
+impl<G: UnknownOrderGroup> MapCommitment<G> {
+    /// Checks that `proof` shows `key` maps to `value`, or is absent if
+    /// `value` is `None`.
+    pub fn verify(&self, key: &[u8], value: Option<&[u8]>, proof: &MapProof<G>) -> bool {
+        match (value, proof) {
+            (Some(value), MapProof::Present(proof)) => self
+                .entries
+                .verify_membership_proof(&[entry(key, value)], proof),
+            (None, MapProof::Absent(proof)) => self.keys.verify_nonmembership(&[key], proof),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prove_verify() {
+        let map =
+            CommittedMap::<ClassGroup>::commit(&[("alice", "10"), ("bob", "20"), ("carol", "")])
+                .unwrap();
+        let c = map.commitment();
+
+        let bob = map.prove(b"bob").unwrap();
+        assert!(c.verify(b"bob", Some(b"20"), &bob));
+        assert!(!c.verify(b"bob", Some(b"21"), &bob));
+        assert!(!c.verify(b"bob", None, &bob));
+        assert!(!c.verify(b"alice", Some(b"20"), &bob));
+
+        let dave = map.prove(b"dave").unwrap();
+        assert!(c.verify(b"dave", None, &dave));
+        assert!(!c.verify(b"bob", None, &dave));
+
+        assert!(c.verify(b"carol", Some(b""), &map.prove(b"carol").unwrap()));
+        assert!(CommittedMap::<ClassGroup>::commit(&[("alice", "1"), ("alice", "2")]).is_err());
+    }
+}
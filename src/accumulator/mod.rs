@@ -0,0 +1,801 @@
+//! A dynamic accumulator over the class group, after Boneh, Bünz and Fisch,
+//! "Batching techniques for accumulators with applications to IOPs and
+//! stateless blockchains". https://eprint.iacr.org/2018/1188.pdf
+//!
+//! Members are byte strings, each mapped to a prime `p` by `elem_to_prime`,
+//! or other `Member`s with a mapping of their own.
+//! The accumulator of a set is `g^(p_1 * ... * p_n)` for the group's
+//! generator `g`, and a member's witness is the accumulator of everything
+//! else, so that `witness^p = accumulator`.
+//!
+//! Everything is generic over the group, which defaults to the class group.
+//! There, all operations run in the calling thread's current class group.
+
+use crate::group::{ClassGroup, UnknownOrderGroup};
+use crate::hash::ElementMapper;
+use crate::vdf::codec::{read_elem, read_mpz, write_elem, write_mpz};
+use anyhow::{bail, ensure, Result};
+use bacteria::Transcript;
+use rug::Integer;
+use std::io::{Read, Write};
+
+mod batch;
+pub use batch::{UpdateBatch, UpdateProof};
+mod map;
+pub use crate::proofs::Poe;
+use crate::proofs::Poke2;
+pub use map::{CommittedMap, MapCommitment, MapProof};
+pub mod revocation;
+pub mod utxo;
+mod vector;
+pub use vector::{Opening, VectorCommitment};
+
+const CHECKPOINT_MAGIC: &[u8; 4] = b"CGAC";
+const CHECKPOINT_VERSION: u8 = 1;
+
+/// Maps a member to the prime it is accumulated as, with the untagged
+/// `ElementMapper`.
+pub fn elem_to_prime(elem: &[u8]) -> Integer {
+    ElementMapper::default().prime(elem)
+}
+
+/// A value that can be accumulated, as the prime it maps to.
+///
+/// Byte strings map with `elem_to_prime`. Helpers such as `utxo` give their
+/// own types a tagged `ElementMapper`, so that their members cannot collide
+/// with plain byte strings or with each other.
+pub trait Member {
+    fn to_prime(&self) -> Integer;
+}
+
+impl<T: AsRef<[u8]>> Member for T {
+    fn to_prime(&self) -> Integer {
+        elem_to_prime(self.as_ref())
+    }
+}
+
+fn prime_product<T: Member>(elems: &[T]) -> Integer {
+    elems.iter().map(|e| e.to_prime()).product()
+}
+
+/// Given `w1^x = w2^y = a` for coprime `x` and `y`, returns `w` with
+/// `w^(x * y) = a`, or `None` if `x` and `y` share a factor.
+pub fn shamir_trick<G: UnknownOrderGroup>(
+    w1: &G::Elem,
+    x: &Integer,
+    w2: &G::Elem,
+    y: &Integer,
+) -> Option<G::Elem> {
+    // With `s * x + t * y = 1`, `(w1^t * w2^s)^(x * y) = a^(t * y + s * x)`.
+    let (g, s, t) = x.clone().extended_gcd(y.clone(), Integer::new());
+    if g != 1 {
+        return None;
+    }
+    Some(G::op(&G::pow(w1, &t), &G::pow(w2, &s)))
+}
+
+/// For each `i`, `g` raised to every prime but `primes[i]`.
+///
+/// This is the root factor algorithm: split the primes in half and raise `g`
+/// to each half's product to get the base for the other half. Exponents add
+/// up to `O(n log n)` primes, against `O(n^2)` for raising `g` separately for
+/// each member, and the halves run in parallel in the caller's group.
+fn root_factor<G: UnknownOrderGroup>(
+    params: &G::Params,
+    g: &G::Elem,
+    primes: &[Integer],
+) -> Vec<Witness<G>> {
+    match primes.len() {
+        0 => return Vec::new(),
+        1 => return vec![Witness { value: g.clone() }],
+        _ => {}
+    }
+    let (left, right) = primes.split_at(primes.len() / 2);
+    let half = |base: &[Integer], rest: &[Integer]| {
+        G::with_params(params, || {
+            let g = G::pow(g, &base.iter().product());
+            root_factor::<G>(params, &g, rest)
+        })
+    };
+    let (mut witnesses, right) = rayon::join(|| half(right, left), || half(left, right));
+    witnesses.extend(right);
+    witnesses
+}
+
+/// The value of an accumulated set.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Accumulator<G: UnknownOrderGroup = ClassGroup> {
+    value: G::Elem,
+}
+
+/// Evidence that one or more members are in an accumulator: the accumulator of
+/// every other member.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Witness<G: UnknownOrderGroup = ClassGroup> {
+    value: G::Elem,
+}
+
+/// A witness for a set of members with a proof that raising it to their
+/// primes gives the accumulator, so that checking it takes two small
+/// exponentiations however many members it covers.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MembershipProof<G: UnknownOrderGroup = ClassGroup> {
+    witness: Witness<G>,
+    proof: Poe<G>,
+}
+
+/// Evidence that none of a set of elements is a member: `d` and `b` with
+/// `d^x * accumulator^b = g` for the product `x` of the elements' primes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NonMembershipProof<G: UnknownOrderGroup = ClassGroup> {
+    d: G::Elem,
+    b: Integer,
+}
+
+/// A non-membership proof for any number of elements that verifies in
+/// constant time: `v = accumulator^b` with a proof of knowledge of `b`, and
+/// a proof that `d^x = g / v`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BatchNonMembershipProof<G: UnknownOrderGroup = ClassGroup> {
+    d: G::Elem,
+    v: G::Elem,
+    poke: Poke2<G>,
+    poe: Poe<G>,
+}
+
+impl<G: UnknownOrderGroup> Default for Accumulator<G> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<G: UnknownOrderGroup> Accumulator<G> {
+    /// The accumulator of the empty set.
+    pub fn empty() -> Self {
+        Self {
+            value: G::unknown_order_elem(),
+        }
+    }
+
+    pub fn value(&self) -> &G::Elem {
+        &self.value
+    }
+
+    /// Adds `elems` and returns the new accumulator with a witness for each
+    /// of them, in order.
+    pub fn add<T: Member>(&self, elems: &[T]) -> (Self, Vec<Witness<G>>) {
+        let primes: Vec<Integer> = elems.iter().map(|e| e.to_prime()).collect();
+        let product: Integer = primes.iter().product();
+        let acc = Self {
+            value: G::pow(&self.value, &product),
+        };
+        (acc, root_factor::<G>(&G::params(), &self.value, &primes))
+    }
+
+    /// The witnesses of all of `members`, which must be the whole accumulated
+    /// set, in order. This is what an operator hands out to every member.
+    pub fn witnesses<T: Member>(&self, members: &[T]) -> Result<Vec<Witness<G>>> {
+        let (acc, witnesses) = Self::empty().add(members);
+        ensure!(acc == *self, "members are not the accumulated set");
+        Ok(witnesses)
+    }
+
+    /// Like `add`, but also proves that the new value is the old one raised to
+    /// the product of the added primes.
+    pub fn add_with_proof<T: Member>(&self, elems: &[T]) -> (Self, Vec<Witness<G>>, Poe<G>) {
+        let (acc, witnesses) = self.add(elems);
+        let proof = Poe::<G>::prove(&self.value, &prime_product(elems), &acc.value);
+        (acc, witnesses, proof)
+    }
+
+    /// Checks a proof from `add_with_proof` that adding `elems` to `self`
+    /// gives `new`.
+    pub fn verify_add<T: Member>(&self, elems: &[T], new: &Self, proof: &Poe<G>) -> bool {
+        proof.verify(&self.value, &prime_product(elems), &new.value)
+    }
+
+    /// Removes members given their witnesses, without needing the rest of the
+    /// set, and proves that the old value is the new one raised to the
+    /// product of the removed primes.
+    ///
+    /// Fails if a witness is invalid or a member is listed twice.
+    pub fn delete<T: Member>(&self, members: &[(T, Witness<G>)]) -> Result<(Self, Poe<G>)> {
+        let (value, product) = self.aggregate(members)?;
+        let proof = Poe::<G>::prove(&value, &product, &self.value);
+        Ok((Self { value }, proof))
+    }
+
+    /// Combines the witnesses of distinct members into the accumulator of
+    /// everything else, returned with the product of the members' primes.
+    fn aggregate<T: Member>(&self, members: &[(T, Witness<G>)]) -> Result<(G::Elem, Integer)> {
+        let mut value = self.value.clone();
+        let mut product = Integer::from(1);
+        for (i, (elem, witness)) in members.iter().enumerate() {
+            if !self.verify_membership(elem, witness) {
+                bail!("witness {} is not valid for this accumulator", i);
+            }
+            // `value` raised to `product` gives the current accumulator; fold
+            // in the next member's witness.
+            let p = elem.to_prime();
+            value = match shamir_trick::<G>(&value, &product, &witness.value, &p) {
+                Some(value) => value,
+                None => bail!("member {} is listed more than once", i),
+            };
+            product *= p;
+        }
+        Ok((value, product))
+    }
+
+    /// Checks a proof from `delete` that removing `elems` from `self` gives
+    /// `new`.
+    pub fn verify_delete<T: Member>(&self, elems: &[T], new: &Self, proof: &Poe<G>) -> bool {
+        proof.verify(&new.value, &prime_product(elems), &self.value)
+    }
+
+    /// Checks that `witness` proves `elem` is a member.
+    pub fn verify_membership<T: Member>(&self, elem: &T, witness: &Witness<G>) -> bool {
+        let p = elem.to_prime();
+        G::pow(&witness.value, &p) == self.value
+    }
+
+    /// Proves that `elems` are members, given `witness`, the accumulator of
+    /// every other member.
+    pub fn prove_membership<T: Member>(
+        &self,
+        elems: &[T],
+        witness: &Witness<G>,
+    ) -> Result<MembershipProof<G>> {
+        let product = prime_product(elems);
+        ensure!(
+            G::pow(&witness.value, &product) == self.value,
+            "witness is not valid for these members"
+        );
+        Ok(MembershipProof {
+            proof: Poe::<G>::prove(&witness.value, &product, &self.value),
+            witness: witness.clone(),
+        })
+    }
+
+    /// Proves that none of `elems` is in the accumulator of `members`, which
+    /// must be the whole accumulated set.
+    pub fn prove_nonmembership<T: Member>(
+        &self,
+        members: &[T],
+        elems: &[T],
+    ) -> Result<NonMembershipProof<G>> {
+        let s = prime_product(members);
+        let g = Self::empty().value;
+        ensure!(
+            G::pow(&g, &s) == self.value,
+            "members are not the accumulated set"
+        );
+        // With `a * x + b * s = 1`, `(g^a)^x * (g^s)^b = g`.
+        let (gcd, a, b) = prime_product(elems).extended_gcd(s, Integer::new());
+        ensure!(gcd == 1, "an element is a member");
+        Ok(NonMembershipProof {
+            d: G::pow(&g, &a),
+            b,
+        })
+    }
+
+    /// Checks a proof from `prove_nonmembership` that none of `elems` is a
+    /// member.
+    pub fn verify_nonmembership<T: Member>(
+        &self,
+        elems: &[T],
+        proof: &NonMembershipProof<G>,
+    ) -> bool {
+        if !G::is_elem(&proof.d) {
+            return false;
+        }
+        let lhs = G::op(
+            &G::pow(&proof.d, &prime_product(elems)),
+            &G::pow(&self.value, &proof.b),
+        );
+        lhs == Self::empty().value
+    }
+
+    /// Like `prove_nonmembership`, but the proof is checked with two proofs of
+    /// exponentiation instead of exponentiations by the elements' primes.
+    pub fn prove_nonmembership_batch<T: Member>(
+        &self,
+        members: &[T],
+        elems: &[T],
+    ) -> Result<BatchNonMembershipProof<G>> {
+        let NonMembershipProof { d, b } = self.prove_nonmembership(members, elems)?;
+        let v = G::pow(&self.value, &b);
+        let g_over_v = G::op(&Self::empty().value, &G::inv(&v));
+        // Both proofs run on one transcript, so the PoE is bound to the PoKE.
+        let mut transcript = Transcript::new(b"Classygroup.accumulator.nonmembership");
+        let poke = Poke2::<G>::prove_with_transcript(&mut transcript, &self.value, &v, &b);
+        let poe =
+            Poe::<G>::prove_with_transcript(&mut transcript, &d, &prime_product(elems), &g_over_v);
+        Ok(BatchNonMembershipProof { d, v, poke, poe })
+    }
+
+    /// Checks a proof from `prove_nonmembership_batch` that none of `elems`
+    /// is a member.
+    pub fn verify_nonmembership_batch<T: Member>(
+        &self,
+        elems: &[T],
+        proof: &BatchNonMembershipProof<G>,
+    ) -> bool {
+        let (d, v) = (&proof.d, &proof.v);
+        if !G::is_elem(d) || !G::is_elem(v) {
+            return false;
+        }
+        let g_over_v = G::op(&Self::empty().value, &G::inv(v));
+        let mut transcript = Transcript::new(b"Classygroup.accumulator.nonmembership");
+        proof
+            .poke
+            .verify_with_transcript(&mut transcript, &self.value, v)
+            && proof.poe.verify_with_transcript(
+                &mut transcript,
+                d,
+                &prime_product(elems),
+                &g_over_v,
+            )
+    }
+
+    /// Aggregates the witnesses of several distinct members into one
+    /// constant-size proof that they are all in the accumulator.
+    ///
+    /// The proof verifies with `verify_membership_proof` over the members.
+    pub fn prove_membership_aggregated<T: Member>(
+        &self,
+        members: &[(T, Witness<G>)],
+    ) -> Result<MembershipProof<G>> {
+        let (value, product) = self.aggregate(members)?;
+        Ok(MembershipProof {
+            proof: Poe::<G>::prove(&value, &product, &self.value),
+            witness: Witness { value },
+        })
+    }
+
+    /// Checks a proof from `prove_membership` or
+    /// `prove_membership_aggregated` that `elems` are members.
+    pub fn verify_membership_proof<T: Member>(
+        &self,
+        elems: &[T],
+        proof: &MembershipProof<G>,
+    ) -> bool {
+        proof
+            .proof
+            .verify(&proof.witness.value, &prime_product(elems), &self.value)
+    }
+}
+
+impl<G: UnknownOrderGroup> MembershipProof<G> {
+    pub fn witness(&self) -> &Witness<G> {
+        &self.witness
+    }
+
+    pub fn proof(&self) -> &Poe<G> {
+        &self.proof
+    }
+}
+
+impl<G: UnknownOrderGroup> Witness<G> {
+    pub fn value(&self) -> &G::Elem {
+        &self.value
+    }
+
+    /// Brings the witness for `elem` up to date after `additions` were added
+    /// and `deletions` removed, in any order, resulting in `accumulator`.
+    ///
+    /// Only the changes and the new value are needed, not the rest of the set.
+    /// Fails if `elem` itself was deleted or the inputs do not match.
+    pub fn update<E: Member, T: Member>(
+        &self,
+        elem: &E,
+        additions: &[T],
+        deletions: &[T],
+        accumulator: &Accumulator<G>,
+    ) -> Result<Self> {
+        let x = elem.to_prime();
+        let d = prime_product(deletions);
+        // After the additions alone, `w^x = a^d` for the new value `a`.
+        let w = G::pow(&self.value, &prime_product(additions));
+        let value = match shamir_trick::<G>(&w, &x, &accumulator.value, &d) {
+            Some(value) => value,
+            None => bail!("the member was deleted"),
+        };
+        let updated = Self { value };
+        ensure!(
+            accumulator.verify_membership(elem, &updated),
+            "updates do not lead to the given accumulator"
+        );
+        Ok(updated)
+    }
+
+    /// Merges a witness for `elems` with one for the disjoint `other_elems`
+    /// of the same accumulator into a witness for both.
+    pub fn combine<T: Member>(&self, elems: &[T], other: &Self, other_elems: &[T]) -> Result<Self> {
+        let x = prime_product(elems);
+        let y = prime_product(other_elems);
+        ensure!(
+            G::pow(&self.value, &x) == G::pow(&other.value, &y),
+            "witnesses are for different accumulators"
+        );
+        match shamir_trick::<G>(&self.value, &x, &other.value, &y) {
+            Some(value) => Ok(Self { value }),
+            None => bail!("witnesses share a member"),
+        }
+    }
+
+    /// Splits a witness for `elems` into one witness per member, in order.
+    pub fn split<T: Member>(&self, elems: &[T]) -> Vec<Self> {
+        // Splitting is adding the members back on top of the accumulator of
+        // everything else.
+        Accumulator::<G> {
+            value: self.value.clone(),
+        }
+        .add(elems)
+        .1
+    }
+}
+
+impl Accumulator<ClassGroup> {
+    /// Writes a checkpoint of the accumulator and the group it lives in.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(CHECKPOINT_MAGIC);
+        buf.push(CHECKPOINT_VERSION);
+        write_mpz(&mut buf, &ClassGroup::context_discriminant());
+        write_elem(&mut buf, &self.value);
+        w.write_all(&buf)?;
+        Ok(())
+    }
+
+    /// Reads a checkpoint from `write_to`, reading no further than its end.
+    ///
+    /// Fails unless the checkpoint is for the current group.
+    pub fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        let mut head = [0u8; 5];
+        r.read_exact(&mut head)?;
+        ensure!(
+            head[..4] == CHECKPOINT_MAGIC[..],
+            "not an accumulator checkpoint"
+        );
+        ensure!(
+            head[4] == CHECKPOINT_VERSION,
+            "unsupported checkpoint version {}",
+            head[4]
+        );
+        let discriminant = read_mpz(r)?;
+        ensure!(
+            discriminant == ClassGroup::context_discriminant(),
+            "checkpoint is for another group"
+        );
+        let value = read_elem(r)?;
+        ensure!(
+            value.a.sgn() > 0 && ClassGroup::validate(&value.a, &value.b, &value.c),
+            "checkpoint value is not in the group"
+        );
+        Ok(Self { value })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.write_to(&mut buf)
+            .expect("writing to a Vec cannot fail");
+        buf
+    }
+
+    /// Parses the encoding of `to_bytes`, rejecting trailing bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut rest = bytes;
+        let acc = Self::read_from(&mut rest)?;
+        ensure!(rest.is_empty(), "trailing bytes after checkpoint");
+        Ok(acc)
+    }
+}
+
+#[cfg(feature = "proto")]
+impl crate::proto::ProtoMessage for Accumulator<ClassGroup> {
+    fn encode_proto(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let value = crate::proto::ProtoMessage::encode_proto(&self.value);
+        crate::proto::write_field(&mut buf, 1, &value);
+        buf
+    }
+
+    fn decode_proto(bytes: &[u8]) -> Result<Self> {
+        let fields = crate::proto::read_fields(bytes)?;
+        Ok(Self {
+            value: crate::proto::message(&fields, 1)?,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+crate::serialize::impl_serde_bytes!(
+    Accumulator<ClassGroup>,
+    Accumulator::to_bytes,
+    Accumulator::from_bytes
+);
+
+#[cfg(feature = "serde")]
+crate::serialize::impl_serde_bytes!(
+    Witness<ClassGroup>,
+    |x| {
+        let mut buf = Vec::new();
+        write_elem(&mut buf, &x.value);
+        buf
+    },
+    |bytes| {
+        let value = crate::vdf::codec::read_all(bytes, |r| read_elem(r))?;
+        Ok(Witness { value })
+    }
+);
+
+#[cfg(feature = "serde")]
+crate::serialize::impl_serde_bytes!(
+    MembershipProof<ClassGroup>,
+    |x| {
+        let mut buf = Vec::new();
+        write_elem(&mut buf, &x.witness.value);
+        write_elem(&mut buf, &x.proof.q);
+        buf
+    },
+    |bytes| {
+        let (value, q) =
+            crate::vdf::codec::read_all(bytes, |r| Ok((read_elem(r)?, read_elem(r)?)))?;
+        Ok(MembershipProof {
+            witness: Witness { value },
+            proof: Poe { q },
+        })
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_with_proof() {
+        let (acc, _) = Accumulator::<ClassGroup>::empty().add(&[b"a"]);
+        let (new, witnesses, proof) = acc.add_with_proof(&[b"b", b"c"]);
+        assert_eq!((new.clone(), witnesses), acc.add(&[b"b", b"c"]));
+        assert!(acc.verify_add(&[b"b", b"c"], &new, &proof));
+        assert!(acc.verify_add(&[b"c", b"b"], &new, &proof));
+        assert!(!acc.verify_add(&[b"b"], &new, &proof));
+        assert!(!new.verify_add(&[b"b", b"c"], &acc, &proof));
+    }
+
+    #[test]
+    fn test_delete() {
+        let (acc, w) = Accumulator::<ClassGroup>::empty().add(&[b"a", b"b", b"c"]);
+        let (ac, proof) = acc
+            .delete(&[(b"b", w[1].clone()), (b"c", w[2].clone())])
+            .unwrap();
+        assert_eq!(ac, Accumulator::<ClassGroup>::empty().add(&[b"a"]).0);
+        assert!(acc.verify_delete(&[b"b", b"c"], &ac, &proof));
+        assert!(!acc.verify_delete(&[b"b"], &ac, &proof));
+
+        let (empty, _) = ac
+            .delete(&[(
+                b"a",
+                Witness {
+                    value: ClassGroup::unknown_order_elem(),
+                },
+            )])
+            .unwrap();
+        assert_eq!(empty, Accumulator::<ClassGroup>::empty());
+
+        assert!(acc.delete(&[(b"a", w[1].clone())]).is_err());
+        assert!(acc
+            .delete(&[(b"a", w[0].clone()), (b"a", w[0].clone())])
+            .is_err());
+    }
+
+    #[test]
+    fn test_membership_proof() {
+        let (acc, w) = Accumulator::<ClassGroup>::empty().add(&[b"a", b"b", b"c"]);
+        let proof = acc.prove_membership(&[b"b"], &w[1]).unwrap();
+        assert!(acc.verify_membership_proof(&[b"b"], &proof));
+        assert!(!acc.verify_membership_proof(&[b"a"], &proof));
+        assert!(acc.prove_membership(&[b"a"], &w[1]).is_err());
+
+        // The initial accumulator is a witness for every member at once.
+        let all = Witness {
+            value: Accumulator::<ClassGroup>::empty().value,
+        };
+        let proof = acc.prove_membership(&[b"a", b"b", b"c"], &all).unwrap();
+        assert!(acc.verify_membership_proof(&[b"c", b"a", b"b"], &proof));
+        assert!(!acc.verify_membership_proof(&[b"a", b"b"], &proof));
+    }
+
+    #[test]
+    fn test_aggregated_membership() {
+        let (acc, w) = Accumulator::<ClassGroup>::empty().add(&[b"a", b"b", b"c", b"d"]);
+        let members = [(b"a", w[0].clone()), (b"c", w[2].clone())];
+        let proof = acc.prove_membership_aggregated(&members).unwrap();
+        assert_eq!(
+            proof.witness().value,
+            Accumulator::<ClassGroup>::empty()
+                .add(&[b"b", b"d"])
+                .0
+                .value
+        );
+        assert!(acc.verify_membership_proof(&[b"a", b"c"], &proof));
+        assert!(!acc.verify_membership_proof(&[b"a", b"b"], &proof));
+
+        assert!(acc
+            .prove_membership_aggregated(&[(b"a", w[0].clone()), (b"a", w[0].clone())])
+            .is_err());
+        assert!(acc
+            .prove_membership_aggregated(&[(b"a", w[1].clone())])
+            .is_err());
+    }
+
+    #[test]
+    fn test_combine_and_split() {
+        let (acc, w) = Accumulator::<ClassGroup>::empty().add(&[b"a", b"b", b"c"]);
+        let ab = w[0].combine(&[b"a"], &w[1], &[b"b"]).unwrap();
+        assert_eq!(
+            ab.value,
+            Accumulator::<ClassGroup>::empty().add(&[b"c"]).0.value
+        );
+        assert!(acc.prove_membership(&[b"a", b"b"], &ab).is_ok());
+
+        let abc = ab.combine(&[b"a", b"b"], &w[2], &[b"c"]).unwrap();
+        assert_eq!(abc.split(&[b"a", b"b", b"c"]), w);
+        assert_eq!(ab.split(&[b"a", b"b"]), w[..2].to_vec());
+
+        assert!(ab.combine(&[b"a", b"b"], &w[1], &[b"b"]).is_err());
+        assert!(w[0].combine(&[b"a"], &w[1], &[b"c"]).is_err());
+    }
+
+    #[test]
+    fn test_nonmembership() {
+        let members = [b"a", b"b", b"c"];
+        let (acc, _) = Accumulator::<ClassGroup>::empty().add(&members);
+        let proof = acc.prove_nonmembership(&members, &[b"d", b"e"]).unwrap();
+        assert!(acc.verify_nonmembership(&[b"d", b"e"], &proof));
+        assert!(!acc.verify_nonmembership(&[b"d"], &proof));
+
+        assert!(acc.prove_nonmembership(&members, &[b"d", b"b"]).is_err());
+        assert!(acc.prove_nonmembership(&members[..2], &[b"d"]).is_err());
+    }
+
+    #[test]
+    fn test_batch_nonmembership() {
+        let members = [b"a", b"b", b"c"];
+        let (acc, _) = Accumulator::<ClassGroup>::empty().add(&members);
+        let absent = [b"d", b"e", b"f", b"g"];
+        let proof = acc.prove_nonmembership_batch(&members, &absent).unwrap();
+        assert!(acc.verify_nonmembership_batch(&absent, &proof));
+        assert!(!acc.verify_nonmembership_batch(&absent[..3], &proof));
+        assert!(!Accumulator::<ClassGroup>::empty().verify_nonmembership_batch(&absent, &proof));
+
+        assert!(acc
+            .prove_nonmembership_batch(&members, &[b"d", b"a"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_witness_update() {
+        let (acc, w) = Accumulator::<ClassGroup>::empty().add(&[b"a", b"b", b"c"]);
+        let (acc, _) = acc.add(&[b"d", b"e"]);
+        let (acc, _) = acc
+            .delete(&[(
+                b"b",
+                Witness {
+                    value: Accumulator::<ClassGroup>::empty()
+                        .add(&[b"a", b"c", b"d", b"e"])
+                        .0
+                        .value,
+                },
+            )])
+            .unwrap();
+
+        let updated = w[0].update(b"a", &[b"d", b"e"], &[b"b"], &acc).unwrap();
+        assert!(acc.verify_membership(b"a", &updated));
+        assert_eq!(
+            updated,
+            w[0].update(b"a", &[b"e", b"d"], &[b"b"], &acc).unwrap()
+        );
+
+        assert!(w[1].update(b"b", &[b"d", b"e"], &[b"b"], &acc).is_err());
+        assert!(w[0].update(b"a", &[b"d"], &[b"b"], &acc).is_err());
+    }
+
+    #[test]
+    fn test_witnesses() {
+        let members: Vec<_> = (0..9u8).map(|i| [i]).collect();
+        let (acc, w) = Accumulator::<ClassGroup>::empty().add(&members);
+        let witnesses = acc.witnesses(&members).unwrap();
+        assert_eq!(witnesses, w);
+        for (elem, w) in members.iter().zip(&witnesses) {
+            assert!(acc.verify_membership(elem, w));
+        }
+        assert!(acc.witnesses(&members[1..]).is_err());
+
+        // Worker threads follow the caller's discriminant.
+        #[cfg(feature = "prover")]
+        {
+            let disc = crate::group::create_discriminant(b"witnesses", 256);
+            ClassGroup::with_discriminant(&disc, || {
+                let g = Accumulator::<ClassGroup> {
+                    value: ClassGroup::unknown_order_elem_disc(&disc),
+                };
+                let (acc, w) = g.add(&members);
+                for (elem, w) in members.iter().zip(&w) {
+                    assert!(acc.verify_membership(elem, w));
+                }
+            });
+        }
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let (acc, _) = Accumulator::<ClassGroup>::empty().add(&[b"a", b"b"]);
+        let bytes = acc.to_bytes();
+        assert_eq!(Accumulator::from_bytes(&bytes).unwrap(), acc);
+
+        // Checkpoints stream back to back.
+        let mut file = bytes.clone();
+        Accumulator::<ClassGroup>::empty()
+            .write_to(&mut file)
+            .unwrap();
+        let mut reader = &file[..];
+        assert_eq!(Accumulator::read_from(&mut reader).unwrap(), acc);
+        assert_eq!(
+            Accumulator::read_from(&mut reader).unwrap(),
+            Accumulator::<ClassGroup>::empty()
+        );
+
+        assert!(Accumulator::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 2;
+        assert!(Accumulator::from_bytes(&bad_version).is_err());
+        let mut corrupt = bytes;
+        *corrupt.last_mut().unwrap() ^= 1;
+        assert!(Accumulator::from_bytes(&corrupt).is_err());
+    }
+
+    #[test]
+    fn test_add_and_verify() {
+        let acc = Accumulator::<ClassGroup>::empty();
+        let (acc, witnesses) = acc.add(&[b"a", b"b", b"c"]);
+        for (elem, w) in [b"a", b"b", b"c"].iter().zip(&witnesses) {
+            assert!(acc.verify_membership(elem, w));
+        }
+        assert!(!acc.verify_membership(b"d", &witnesses[0]));
+        assert!(!acc.verify_membership(b"a", &witnesses[1]));
+
+        // Adding in two batches gives the same value as adding at once.
+        let (ab, _) = Accumulator::<ClassGroup>::empty().add(&[b"a", b"b"]);
+        let (abc, w) = ab.add(&[b"c"]);
+        assert_eq!(abc, acc);
+        assert_eq!(w[0], witnesses[2]);
+
+        let (same, none) = acc.add::<&[u8]>(&[]);
+        assert_eq!(same, acc);
+        assert!(none.is_empty());
+    }
+
+    #[cfg(feature = "rsa")]
+    #[test]
+    fn test_rsa() {
+        use crate::group::Rsa2048;
+        let (acc, w) = Accumulator::<Rsa2048>::empty().add(&[b"a", b"b", b"c"]);
+        assert!(acc.verify_membership(b"b", &w[1]));
+        let proof = acc
+            .prove_membership(
+                &[b"a", b"b"],
+                &w[0].combine(&[b"a"], &w[1], &[b"b"]).unwrap(),
+            )
+            .unwrap();
+        assert!(acc.verify_membership_proof(&[b"a", b"b"], &proof));
+
+        let (ac, proof) = acc.delete(&[(b"b", w[1].clone())]).unwrap();
+        assert!(acc.verify_delete(&[b"b"], &ac, &proof));
+        let absent = ac
+            .prove_nonmembership(&[b"a", b"c"], &[b"b", b"d"])
+            .unwrap();
+        assert!(ac.verify_nonmembership(&[b"b", b"d"], &absent));
+        assert!(!acc.verify_nonmembership(&[b"b", b"d"], &absent));
+    }
+}
@@ -0,0 +1,237 @@
+//! Epoch-based revocation for anonymous credentials.
+//!
+//! The issuer accumulates the ids of valid credentials. Issuing and revoking
+//! take effect at the end of an epoch, when the issuer publishes an
+//! `EpochDelta` naming the ids added and revoked and the new accumulator.
+//! Holders keep their witnesses current by folding in every delta since
+//! their last refresh, and show a valid credential by proving membership
+//! against the accumulator of the current epoch.
+
+use super::{Accumulator, Member, Witness};
+use crate::group::{ClassGroup, UnknownOrderGroup};
+use crate::hash::ElementMapper;
+use anyhow::{bail, ensure, Result};
+use rug::Integer;
+use std::collections::BTreeSet;
+
+/// The `ElementMapper` tag of credential ids, so that their primes are
+/// distinct from other uses of the accumulator.
+const DOMAIN: &[u8] = b"Classygroup.revocation";
+
+/// A credential id as it is accumulated, mapped under the `revocation` tag.
+struct Id<'a>(&'a [u8]);
+
+impl Member for Id<'_> {
+    fn to_prime(&self) -> Integer {
+        ElementMapper::new(DOMAIN).prime(self.0)
+    }
+}
+
+fn ids<'a, I: IntoIterator<Item = &'a Vec<u8>>>(ids: I) -> Vec<Id<'a>> {
+    ids.into_iter().map(|id| Id(id)).collect()
+}
+
+/// The changes published at the end of an epoch.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EpochDelta<G: UnknownOrderGroup = ClassGroup> {
+    /// The epoch that starts with this delta.
+    pub epoch: u64,
+    pub issued: Vec<Vec<u8>>,
+    pub revoked: Vec<Vec<u8>>,
+    pub accumulator: Accumulator<G>,
+}
+
+/// A holder's credential id with its witness as of some epoch.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Credential<G: UnknownOrderGroup = ClassGroup> {
+    id: Vec<u8>,
+    epoch: u64,
+    witness: Witness<G>,
+}
+
+impl<G: UnknownOrderGroup> Credential<G> {
+    pub fn id(&self) -> &[u8] {
+        &self.id
+    }
+
+    /// The epoch the witness is valid in.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn witness(&self) -> &Witness<G> {
+        &self.witness
+    }
+
+    /// Brings the witness up to date with `deltas`, which must include every
+    /// epoch after the credential's, in order. Deltas from earlier epochs are
+    /// skipped.
+    ///
+    /// Fails, leaving the credential unchanged, if an epoch is missing or the
+    /// credential was revoked.
+    pub fn refresh_witness(&mut self, deltas: &[EpochDelta<G>]) -> Result<()> {
+        let elem = Id(&self.id);
+        let (mut epoch, mut witness) = (self.epoch, self.witness.clone());
+        for delta in deltas.iter().filter(|d| d.epoch > self.epoch) {
+            ensure!(delta.epoch == epoch + 1, "epoch {} is missing", epoch + 1);
+            if delta.revoked.contains(&self.id) {
+                bail!("credential was revoked in epoch {}", delta.epoch);
+            }
+            witness = witness.update(
+                &elem,
+                &ids(&delta.issued),
+                &ids(&delta.revoked),
+                &delta.accumulator,
+            )?;
+            epoch = delta.epoch;
+        }
+        self.epoch = epoch;
+        self.witness = witness;
+        Ok(())
+    }
+}
+
+/// The issuer's state: the valid ids, every id ever issued and the changes
+/// queued for the end of the epoch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RevocationList<G: UnknownOrderGroup = ClassGroup> {
+    epoch: u64,
+    acc: Accumulator<G>,
+    valid: BTreeSet<Vec<u8>>,
+    ever_issued: BTreeSet<Vec<u8>>,
+    issued: Vec<Vec<u8>>,
+    revoked: BTreeSet<Vec<u8>>,
+}
+
+impl<G: UnknownOrderGroup> Default for RevocationList<G> {
+    fn default() -> Self {
+        Self {
+            epoch: 0,
+            acc: Accumulator::default(),
+            valid: BTreeSet::new(),
+            ever_issued: BTreeSet::new(),
+            issued: Vec::new(),
+            revoked: BTreeSet::new(),
+        }
+    }
+}
+
+impl<G: UnknownOrderGroup> RevocationList<G> {
+    /// An empty list in epoch 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// The accumulator of the ids valid in the current epoch.
+    pub fn accumulator(&self) -> &Accumulator<G> {
+        &self.acc
+    }
+
+    /// Whether `credential` is valid in the current epoch.
+    pub fn verify(&self, credential: &Credential<G>) -> bool {
+        credential.epoch == self.epoch
+            && self
+                .acc
+                .verify_membership(&Id(&credential.id), &credential.witness)
+    }
+
+    /// Queues a new credential id for the next epoch. Fails if the id was
+    /// ever issued before, even if it has since been revoked.
+    pub fn issue(&mut self, id: &[u8]) -> Result<()> {
+        ensure!(
+            self.ever_issued.insert(id.to_vec()),
+            "id {:?} is already issued",
+            id
+        );
+        self.issued.push(id.to_vec());
+        Ok(())
+    }
+
+    /// Queues the revocation of a valid id for the next epoch.
+    pub fn revoke(&mut self, id: &[u8]) -> Result<()> {
+        ensure!(self.valid.contains(id), "id {:?} is not valid", id);
+        self.revoked.insert(id.to_vec());
+        Ok(())
+    }
+
+    /// Ends the epoch. Returns the delta to publish and a credential for each
+    /// id issued in it, in order.
+    pub fn publish(&mut self) -> (EpochDelta<G>, Vec<Credential<G>>) {
+        for id in &self.revoked {
+            self.valid.remove(id);
+        }
+        let (acc, witnesses) = Accumulator::<G>::empty()
+            .add(&ids(&self.valid))
+            .0
+            .add(&ids(&self.issued));
+
+        self.epoch += 1;
+        self.acc = acc.clone();
+        let issued = std::mem::take(&mut self.issued);
+        self.valid.extend(issued.iter().cloned());
+        let credentials = issued
+            .iter()
+            .zip(witnesses)
+            .map(|(id, witness)| Credential {
+                id: id.clone(),
+                epoch: self.epoch,
+                witness,
+            })
+            .collect();
+        let delta = EpochDelta {
+            epoch: self.epoch,
+            issued,
+            revoked: std::mem::take(&mut self.revoked).into_iter().collect(),
+            accumulator: acc,
+        };
+        (delta, credentials)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_epochs() {
+        let mut list = RevocationList::<ClassGroup>::new();
+        list.issue(b"alice").unwrap();
+        list.issue(b"bob").unwrap();
+        list.issue(b"carol").unwrap();
+        assert!(list.issue(b"bob").is_err());
+        let (first, creds) = list.publish();
+        let (mut alice, mut bob) = (creds[0].clone(), creds[1].clone());
+        assert!(list.verify(&alice));
+
+        list.revoke(b"bob").unwrap();
+        assert!(list.revoke(b"dave").is_err());
+        let (second, _) = list.publish();
+        assert!(list.issue(b"bob").is_err());
+        list.issue(b"dave").unwrap();
+        let (third, dave) = list.publish();
+        assert!(!list.verify(&alice));
+
+        let deltas = [first, second.clone(), third.clone()];
+        alice.refresh_witness(&deltas).unwrap();
+        assert_eq!(alice.epoch(), 3);
+        assert!(list.verify(&alice));
+        assert!(list.verify(&dave[0]));
+
+        // Bob is revoked, and a gap in the deltas is an error.
+        assert!(bob.refresh_witness(&deltas).is_err());
+        assert_eq!(bob.epoch(), 1);
+        let mut carol = creds[2].clone();
+        assert!(carol.refresh_witness(&[third]).is_err());
+        carol.refresh_witness(&deltas[1..]).unwrap();
+        assert!(list.verify(&carol));
+
+        // Ids are not members as untagged byte strings.
+        assert!(!list
+            .accumulator()
+            .verify_membership(&b"alice", alice.witness()));
+    }
+}
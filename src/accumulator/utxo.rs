@@ -0,0 +1,173 @@
+//! Accumulator bookkeeping for a stateless UTXO chain.
+//!
+//! The chain commits to its unspent outputs with one accumulator. Every
+//! block spends outputs, whose owners supply their witnesses, and creates
+//! new ones. The resulting `BlockUpdate` lets anyone check the transition
+//! and lets wallets bring their witnesses up to date without the UTXO set.
+
+use super::{Accumulator, Member, Poe, Witness};
+use crate::group::{ClassGroup, UnknownOrderGroup};
+use crate::hash::ElementMapper;
+use anyhow::{ensure, Result};
+use rug::Integer;
+
+/// The `ElementMapper` tag of outpoints, so that their primes are distinct
+/// from other uses of the accumulator.
+const DOMAIN: &[u8] = b"Classygroup.utxo";
+
+/// A transaction output: the creating transaction's id and the output's
+/// index in it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct OutPoint {
+    pub txid: [u8; 32],
+    pub vout: u32,
+}
+
+impl OutPoint {
+    pub fn new(txid: [u8; 32], vout: u32) -> Self {
+        Self { txid, vout }
+    }
+
+    /// The transaction id followed by the index as a big-endian `u32`.
+    pub fn to_bytes(&self) -> [u8; 36] {
+        let mut buf = [0u8; 36];
+        buf[..32].copy_from_slice(&self.txid);
+        buf[32..].copy_from_slice(&self.vout.to_be_bytes());
+        buf
+    }
+}
+
+/// Outputs are accumulated as their bytes mapped under the `utxo` tag.
+impl Member for OutPoint {
+    fn to_prime(&self) -> Integer {
+        ElementMapper::new(DOMAIN).prime(&self.to_bytes())
+    }
+}
+
+/// Everything published about one block's effect on the accumulator.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BlockUpdate<G: UnknownOrderGroup = ClassGroup> {
+    pub spent: Vec<OutPoint>,
+    pub created: Vec<OutPoint>,
+    /// The accumulator after the spends but before the new outputs.
+    pub spent_state: Accumulator<G>,
+    pub accumulator: Accumulator<G>,
+    pub delete_proof: Poe<G>,
+    pub add_proof: Poe<G>,
+}
+
+impl<G: UnknownOrderGroup> BlockUpdate<G> {
+    /// Checks that the update turns `prev` into `self.accumulator`.
+    pub fn verify(&self, prev: &Accumulator<G>) -> bool {
+        prev.verify_delete(&self.spent, &self.spent_state, &self.delete_proof)
+            && self
+                .spent_state
+                .verify_add(&self.created, &self.accumulator, &self.add_proof)
+    }
+
+    /// Brings the witness for an unspent `output` from before the block up to
+    /// date. Fails if the block spent it.
+    pub fn update_witness(&self, output: &OutPoint, witness: &Witness<G>) -> Result<Witness<G>> {
+        witness.update(output, &self.created, &self.spent, &self.accumulator)
+    }
+}
+
+/// The accumulator of the unspent outputs, as kept by a block producer.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UtxoSet<G: UnknownOrderGroup = ClassGroup> {
+    acc: Accumulator<G>,
+}
+
+impl<G: UnknownOrderGroup> Default for UtxoSet<G> {
+    fn default() -> Self {
+        Self::from_accumulator(Accumulator::default())
+    }
+}
+
+impl<G: UnknownOrderGroup> UtxoSet<G> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Continues from an accumulator, e.g. one loaded from a checkpoint.
+    pub fn from_accumulator(acc: Accumulator<G>) -> Self {
+        Self { acc }
+    }
+
+    pub fn accumulator(&self) -> &Accumulator<G> {
+        &self.acc
+    }
+
+    /// Applies a block that spends `spent`, given the owners' witnesses, and
+    /// creates `created`. Returns the update to publish and a witness for
+    /// each new output, in order.
+    ///
+    /// Nothing changes if a witness is invalid or an output is spent or
+    /// created twice.
+    pub fn apply_block(
+        &mut self,
+        spent: &[(OutPoint, Witness<G>)],
+        created: &[OutPoint],
+    ) -> Result<(BlockUpdate<G>, Vec<Witness<G>>)> {
+        let mut sorted = created.to_vec();
+        sorted.sort();
+        sorted.dedup();
+        ensure!(sorted.len() == created.len(), "an output is created twice");
+
+        let (spent_state, delete_proof) = self.acc.delete(spent)?;
+        let (acc, witnesses, add_proof) = spent_state.add_with_proof(created);
+
+        self.acc = acc.clone();
+        let update = BlockUpdate {
+            spent: spent.iter().map(|(o, _)| *o).collect(),
+            created: created.to_vec(),
+            spent_state,
+            accumulator: acc,
+            delete_proof,
+            add_proof,
+        };
+        Ok((update, witnesses))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks() {
+        let outs: Vec<_> = (0..4).map(|i| OutPoint::new([i as u8; 32], i)).collect();
+        let mut set = UtxoSet::<ClassGroup>::new();
+
+        let genesis = set.accumulator().clone();
+        let (first, w) = set.apply_block(&[], &outs[..3]).unwrap();
+        assert!(first.verify(&genesis));
+
+        let (second, new) = set
+            .apply_block(&[(outs[1], w[1].clone())], &outs[3..])
+            .unwrap();
+        assert!(second.verify(&first.accumulator));
+        assert!(!second.verify(&genesis));
+
+        let acc = set.accumulator().clone();
+        let w0 = second.update_witness(&outs[0], &w[0]).unwrap();
+        assert!(acc.verify_membership(&outs[0], &w0));
+        assert!(acc.verify_membership(&outs[3], &new[0]));
+
+        // Outputs do not share primes with their bytes accumulated untagged.
+        let untagged = Accumulator::<ClassGroup>::empty()
+            .add(&[outs[0].to_bytes()])
+            .0;
+        assert!(!untagged.verify_membership(&outs[0], &w0));
+        assert_ne!(
+            outs[0].to_prime(),
+            super::super::elem_to_prime(&outs[0].to_bytes())
+        );
+        assert!(second.update_witness(&outs[1], &w[1]).is_err());
+
+        // A double spend is rejected and leaves the set untouched.
+        assert!(set.apply_block(&[(outs[1], w[1].clone())], &[]).is_err());
+        assert!(set.apply_block(&[], &[outs[0], outs[0]]).is_err());
+        assert_eq!(*set.accumulator(), acc);
+    }
+}
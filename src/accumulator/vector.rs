@@ -0,0 +1,132 @@
+//! Binary vector commitments on top of the accumulator (BBF18, section 5.2).
+//!
+//! Position `i` is mapped to the prime of its index, and the commitment is
+//! the accumulator of the positions holding a one. A one is opened with a
+//! membership proof and a zero with a non-membership proof; any number of
+//! positions opens with one of each.
+
+use super::{Accumulator, MembershipProof, NonMembershipProof, Witness};
+use crate::group::{ClassGroup, UnknownOrderGroup};
+use anyhow::{bail, ensure, Result};
+
+fn key(index: usize) -> [u8; 8] {
+    (index as u64).to_be_bytes()
+}
+
+/// A committed bit vector, held by the party that opens it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VectorCommitment<G: UnknownOrderGroup = ClassGroup> {
+    bits: Vec<bool>,
+    commitment: Accumulator<G>,
+}
+
+/// Evidence for the values at several positions of a committed vector.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Opening<G: UnknownOrderGroup = ClassGroup> {
+    ones: MembershipProof<G>,
+    zeros: NonMembershipProof<G>,
+}
+
+impl<G: UnknownOrderGroup> VectorCommitment<G> {
+    pub fn commit(bits: &[bool]) -> Self {
+        let ones: Vec<_> = (0..bits.len()).filter(|&i| bits[i]).map(key).collect();
+        Self {
+            bits: bits.to_vec(),
+            commitment: Accumulator::<G>::empty().add(&ones).0,
+        }
+    }
+
+    /// The value that is published and verified against.
+    pub fn commitment(&self) -> &Accumulator<G> {
+        &self.commitment
+    }
+
+    pub fn bits(&self) -> &[bool] {
+        &self.bits
+    }
+
+    /// Opens the positions `indices`, which must be distinct and in range.
+    pub fn open(&self, indices: &[usize]) -> Result<Opening<G>> {
+        let mut opened = vec![false; self.bits.len()];
+        for &i in indices {
+            ensure!(i < self.bits.len(), "position {} is out of range", i);
+            if opened[i] {
+                bail!("position {} is opened twice", i);
+            }
+            opened[i] = true;
+        }
+
+        let select = |one: bool| -> Vec<_> {
+            indices
+                .iter()
+                .filter(|&&i| self.bits[i] == one)
+                .map(|&i| key(i))
+                .collect()
+        };
+        let all: Vec<_> = (0..self.bits.len())
+            .filter(|&i| self.bits[i])
+            .map(key)
+            .collect();
+        // The ones are opened with the accumulator of the ones left closed.
+        let rest: Vec<_> = (0..self.bits.len())
+            .filter(|&i| self.bits[i] && !opened[i])
+            .map(key)
+            .collect();
+        let witness = Witness {
+            value: Accumulator::<G>::empty().add(&rest).0.value,
+        };
+        Ok(Opening {
+            ones: self.commitment.prove_membership(&select(true), &witness)?,
+            zeros: self.commitment.prove_nonmembership(&all, &select(false))?,
+        })
+    }
+
+    /// Checks that `opening` shows `commitment` holds `bit` at `index` for
+    /// every pair in `values`.
+    pub fn verify(
+        commitment: &Accumulator<G>,
+        values: &[(usize, bool)],
+        opening: &Opening<G>,
+    ) -> bool {
+        let ones: Vec<_> = values.iter().filter(|v| v.1).map(|v| key(v.0)).collect();
+        let zeros: Vec<_> = values.iter().filter(|v| !v.1).map(|v| key(v.0)).collect();
+        commitment.verify_membership_proof(&ones, &opening.ones)
+            && commitment.verify_nonmembership(&zeros, &opening.zeros)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_verify() {
+        let bits = [true, false, false, true, true, false];
+        let vc = VectorCommitment::<ClassGroup>::commit(&bits);
+        let c = vc.commitment();
+
+        let opening = vc.open(&[0, 1, 4]).unwrap();
+        assert!(VectorCommitment::verify(
+            c,
+            &[(0, true), (1, false), (4, true)],
+            &opening
+        ));
+        assert!(!VectorCommitment::verify(
+            c,
+            &[(0, true), (1, true), (4, true)],
+            &opening
+        ));
+        assert!(!VectorCommitment::verify(
+            c,
+            &[(0, true), (1, false)],
+            &opening
+        ));
+
+        let single = vc.open(&[5]).unwrap();
+        assert!(VectorCommitment::verify(c, &[(5, false)], &single));
+        assert!(!VectorCommitment::verify(c, &[(3, false)], &single));
+
+        assert!(vc.open(&[6]).is_err());
+        assert!(vc.open(&[1, 1]).is_err());
+    }
+}
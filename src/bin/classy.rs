@@ -0,0 +1,91 @@
+//! Command-line front end in the style of the `pot` tool:
+//!
+//! ```text
+//! classy discriminant <seed> <bits>
+//! classy prove <seed> <bits> <iterations>
+//! classy verify <seed> <bits> <iterations> <proof>
+//! ```
+//!
+//! Seeds and proofs are hex, and bit lengths must be within
+//! `SeededDiscriminant::LENGTHS`. `prove` prints the output and proof as one
+//! hex string, which `verify` takes back. `verify` exits with 0 for a valid
+//! proof and 1 for an invalid one; usage and parse errors exit with 2.
+
+use anyhow::{bail, ensure, Context, Result};
+use classygroup::encoding::{from_hex, to_hex};
+use classygroup::group::SeededDiscriminant;
+use classygroup::{create_discriminant, vdf};
+use std::process;
+
+const USAGE: &str = "usage:
+    classy discriminant <seed> <bits>
+    classy prove <seed> <bits> <iterations>
+    classy verify <seed> <bits> <iterations> <proof>";
+
+fn parse_u64(s: &str, what: &str) -> Result<u64> {
+    s.parse()
+        .with_context(|| format!("invalid {} {:?}", what, s))
+}
+
+fn parse_bits(s: &str) -> Result<u64> {
+    let bits = parse_u64(s, "bit length")?;
+    let lengths = SeededDiscriminant::LENGTHS;
+    ensure!(
+        lengths.contains(&bits),
+        "bit length {} is not between {} and {}",
+        bits,
+        lengths.start(),
+        lengths.end()
+    );
+    Ok(bits)
+}
+
+/// Runs a command and returns the process exit code.
+fn run(args: &[String]) -> Result<i32> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["discriminant", seed, bits] => {
+            let disc = create_discriminant(&from_hex(seed)?, parse_bits(bits)?);
+            println!("{}", disc);
+            Ok(0)
+        }
+        ["prove", seed, bits, iterations] => {
+            let (y, proof) = vdf::solve(
+                &from_hex(seed)?,
+                parse_bits(bits)?,
+                parse_u64(iterations, "iteration count")?,
+            );
+            println!("{}", to_hex(&vdf::output_to_bytes(&y, &proof)));
+            Ok(0)
+        }
+        ["verify", seed, bits, iterations, proof] => {
+            let (y, proof) = vdf::output_from_bytes(&from_hex(proof)?)?;
+            let valid = vdf::verify(
+                &from_hex(seed)?,
+                parse_bits(bits)?,
+                parse_u64(iterations, "iteration count")?,
+                &y,
+                &proof,
+            );
+            println!(
+                "{}",
+                if valid {
+                    "Proof is valid"
+                } else {
+                    "Proof is invalid"
+                }
+            );
+            Ok(if valid { 0 } else { 1 })
+        }
+        _ => bail!("{}", USAGE),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let code = run(&args).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        2
+    });
+    process::exit(code);
+}
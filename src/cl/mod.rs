@@ -0,0 +1,305 @@
+//! Linearly homomorphic encryption over class groups, after Castagnos and
+//! Laguillaumie, "Linearly homomorphic encryption from DDH".
+//! https://eprint.iacr.org/2015/047.pdf
+//!
+//! Messages live in `Z/qZ` for a prime `q`. The group is the class group of
+//! the non-maximal order of discriminant `-p * q^3`, where `-p * q` is a
+//! fundamental discriminant. It has a subgroup `F` of order `q` generated by
+//! `f = (q^2, q, .)`, in which discrete logs are easy, and a ciphertext hides
+//! `f^m` behind a power of the public key. Adding ciphertexts adds the
+//! messages mod `q`, and raising one to `k` multiplies its message by `k`.
+//!
+//! Each `ClParams` carries its discriminant; every operation switches the
+//! calling thread to it for its duration.
+
+use crate::commitment::{random_bits, STATISTICAL_BITS};
+use crate::group::{ClassElem, ClassGroup};
+use crate::num::Mpz;
+use anyhow::{bail, ensure, Result};
+use rand::{CryptoRng, RngCore};
+use rug::integer::IsPrime;
+use rug::ops::RemRounding;
+use rug::Integer;
+
+mod proofs;
+pub use proofs::{curve_order, DlogEncryptionProof, EncryptionProof};
+
+/// The public parameters of a CL instance for messages mod `q`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ClParams {
+    q: Integer,
+    p: Integer,
+    discriminant: Mpz,
+    f: ClassElem,
+    g: ClassElem,
+    exponent_bits: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SecretKey(Integer);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PublicKey(ClassElem);
+
+/// An encryption `(g^r, f^m * h^r)` of `m` under the public key `h`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Ciphertext {
+    pub c1: ClassElem,
+    pub c2: ClassElem,
+}
+
+impl PublicKey {
+    pub fn value(&self) -> &ClassElem {
+        &self.0
+    }
+}
+
+impl SecretKey {
+    pub fn value(&self) -> &Integer {
+        &self.0
+    }
+}
+
+impl ClParams {
+    /// Parameters for messages mod the prime `q`, with a fundamental
+    /// discriminant `-p * q` of `bits` bits.
+    ///
+    /// Fails unless `q` is an odd prime and `bits` is at least twice the
+    /// bits of `q` plus three, so that `p > 4 * q`.
+    pub fn generate<R: RngCore + CryptoRng>(q: &Integer, bits: u32, rng: &mut R) -> Result<Self> {
+        ensure!(
+            *q > 2 && q.is_probably_prime(30) != IsPrime::No,
+            "q must be an odd prime"
+        );
+        let q_bits = q.significant_bits();
+        // `p` gets `bits - q_bits >= q_bits + 3` bits, so `p >= 2^(q_bits + 2) > 4 * q`.
+        ensure!(bits >= 2 * q_bits + 3, "discriminant is too small for q");
+        let p_bits = bits - q_bits;
+
+        // `p * q = 3 mod 4` makes `-p * q` a discriminant, and `(p / q) = -1`
+        // keeps `q` inert so that `F` has order exactly `q`.
+        let p = loop {
+            let mut p = random_bits(rng, p_bits);
+            p.set_bit(p_bits - 1, true);
+            p.next_prime_mut();
+            if p.significant_bits() == p_bits
+                && Integer::from(&p * q).mod_u(4) == 3
+                && p.legendre(q) == -1
+            {
+                break p;
+            }
+        };
+
+        let disc_k = -Integer::from(&p * q);
+        let q2 = Integer::from(q.square_ref());
+        let disc = Integer::from(&disc_k * &q2);
+        let discriminant = Mpz::from(disc.clone());
+        let (f, g) = ClassGroup::with_discriminant(&discriminant, || {
+            // `f = (q^2, q, (1 + p * q) / 4)`, reduced since `p > 4 * q`.
+            let f = ClassGroup::elem((
+                Mpz::from(q2.clone()),
+                Mpz::from(q.clone()),
+                Mpz::from((Integer::from(&p * q) + 1) >> 2),
+            ));
+            // Lift a form of small prime norm `l` from `-p * q` and raise it
+            // to `q` to land in the subgroup of `q`-th powers.
+            let mut l = Integer::from(3);
+            let lifted = loop {
+                if l.mod_u(4) == 3 && *q != l && disc_k.legendre(&l) == 1 {
+                    let e = Integer::from(&l + 1) >> 2;
+                    let b = disc_k.clone().pow_mod(&e, &l).unwrap();
+                    let b = if b.is_even() {
+                        Integer::from(&l - &b)
+                    } else {
+                        b
+                    };
+                    let b_q = Integer::from(&b * q) % Integer::from(&l << 1);
+                    let c = (Integer::from(b_q.square_ref()) - &disc) / Integer::from(&l << 2);
+                    break ClassGroup::elem((Mpz::from(l), Mpz::from(b_q), Mpz::from(c)));
+                }
+                l.next_prime_mut();
+            };
+            (f, ClassGroup::pow(&lifted, q))
+        });
+
+        // Exponents exceed the class number of `-p * q`, which is below
+        // `log|disc| * sqrt|disc|`, by `STATISTICAL_BITS`.
+        let exponent_bits = bits / 2 + (32 - bits.leading_zeros()) + STATISTICAL_BITS;
+        Ok(Self {
+            q: q.clone(),
+            p,
+            discriminant,
+            f,
+            g,
+            exponent_bits,
+        })
+    }
+
+    /// The message modulus.
+    pub fn q(&self) -> &Integer {
+        &self.q
+    }
+
+    /// The discriminant `-p * q^3` of the group.
+    pub fn discriminant(&self) -> &Mpz {
+        &self.discriminant
+    }
+
+    /// The generator of the subgroup of order `q`.
+    pub fn f(&self) -> &ClassElem {
+        &self.f
+    }
+
+    /// The base of keys and randomness, a `q`-th power.
+    pub fn g(&self) -> &ClassElem {
+        &self.g
+    }
+
+    /// Bits of secret keys and encryption randomness.
+    pub fn exponent_bits(&self) -> u32 {
+        self.exponent_bits
+    }
+
+    /// Runs `f` in the group of these parameters.
+    pub fn with_group<T, F: FnOnce() -> T>(&self, f: F) -> T {
+        ClassGroup::with_discriminant(&self.discriminant, f)
+    }
+
+    pub fn keygen<R: RngCore + CryptoRng>(&self, rng: &mut R) -> (SecretKey, PublicKey) {
+        let x = random_bits(rng, self.exponent_bits);
+        let h = self.with_group(|| ClassGroup::pow(&self.g, &x));
+        (SecretKey(x), PublicKey(h))
+    }
+
+    /// `f^m`, which encodes `m mod q`.
+    pub fn encode(&self, m: &Integer) -> ClassElem {
+        self.with_group(|| ClassGroup::pow(&self.f, &Integer::from(m.rem_euc(&self.q))))
+    }
+
+    /// Encrypts `m mod q` with fresh randomness.
+    pub fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        pk: &PublicKey,
+        m: &Integer,
+        rng: &mut R,
+    ) -> Ciphertext {
+        self.encrypt_with(pk, m, &random_bits(rng, self.exponent_bits))
+    }
+
+    /// Encrypts `m mod q` with the randomness `r`.
+    pub fn encrypt_with(&self, pk: &PublicKey, m: &Integer, r: &Integer) -> Ciphertext {
+        let fm = self.encode(m);
+        self.with_group(|| Ciphertext {
+            c1: ClassGroup::pow(&self.g, r),
+            c2: ClassGroup::op(&fm, &ClassGroup::pow(&pk.0, r)),
+        })
+    }
+
+    /// Recovers the message in `[0, q)`. Fails if `c` is not a ciphertext
+    /// under `sk`.
+    pub fn decrypt(&self, sk: &SecretKey, c: &Ciphertext) -> Result<Integer> {
+        let fm = self.with_group(|| {
+            ensure!(
+                self.is_elem(&c.c1) && self.is_elem(&c.c2),
+                "ciphertext is not in the group"
+            );
+            Ok(ClassGroup::op(
+                &c.c2,
+                &ClassGroup::pow(&c.c1, &-sk.0.clone()),
+            ))
+        })?;
+        self.discrete_log_f(&fm)
+    }
+
+    fn is_elem(&self, x: &ClassElem) -> bool {
+        x.a.sgn() > 0 && ClassGroup::validate(&x.a, &x.b, &x.c)
+    }
+
+    /// The `m` in `[0, q)` with `f^m = x`. A reduced `f^m` is the identity or
+    /// `(q^2, L * q, .)` with `L = 1 / m mod q`.
+    fn discrete_log_f(&self, x: &ClassElem) -> Result<Integer> {
+        let a = Integer::from(&x.a);
+        if a == 1 {
+            return Ok(Integer::new());
+        }
+        let (l, rem) = Integer::from(&x.b).div_rem(self.q.clone());
+        if a != Integer::from(self.q.square_ref()) || rem != 0 {
+            bail!("decryption is not in the subgroup of order q");
+        }
+        match l.invert(&self.q) {
+            Ok(m) => Ok(m),
+            Err(_) => bail!("decryption is not in the subgroup of order q"),
+        }
+    }
+
+    /// An encryption of the sum of the messages of `a` and `b`.
+    pub fn add(&self, a: &Ciphertext, b: &Ciphertext) -> Ciphertext {
+        self.with_group(|| Ciphertext {
+            c1: ClassGroup::op(&a.c1, &b.c1),
+            c2: ClassGroup::op(&a.c2, &b.c2),
+        })
+    }
+
+    /// An encryption of `k` times the message of `c`.
+    pub fn scale(&self, c: &Ciphertext, k: &Integer) -> Ciphertext {
+        self.with_group(|| Ciphertext {
+            c1: ClassGroup::pow(&c.c1, k),
+            c2: ClassGroup::pow(&c.c2, k),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let rng = &mut thread_rng();
+        let q = Integer::from(u64::MAX).next_prime();
+        let params = ClParams::generate(&q, 320, rng).unwrap();
+        let (sk, pk) = params.keygen(rng);
+
+        for m in &[
+            Integer::new(),
+            Integer::from(1),
+            Integer::from(&q - 1),
+            Integer::from(-5),
+        ] {
+            let c = params.encrypt(&pk, m, rng);
+            assert_eq!(
+                params.decrypt(&sk, &c).unwrap(),
+                Integer::from(m.rem_euc(&q))
+            );
+        }
+
+        let (m1, m2) = (Integer::from(123_456_789), Integer::from(&q - 1000));
+        let (c1, c2) = (params.encrypt(&pk, &m1, rng), params.encrypt(&pk, &m2, rng));
+        let sum = params.decrypt(&sk, &params.add(&c1, &c2)).unwrap();
+        assert_eq!(sum, Integer::from(&m1 + &m2) % &q);
+        let scaled = params
+            .decrypt(&sk, &params.scale(&c1, &Integer::from(7)))
+            .unwrap();
+        assert_eq!(scaled, Integer::from(&m1 * 7));
+
+        let (other, _) = params.keygen(rng);
+        assert!(params.decrypt(&other, &c1).is_err());
+        assert!(ClParams::generate(&Integer::from(100), 320, rng).is_err());
+    }
+
+    #[test]
+    fn test_smallest_discriminant() {
+        let rng = &mut thread_rng();
+        let q = Integer::from(u64::MAX).next_prime();
+        let bits = 2 * q.significant_bits() + 3;
+        assert!(ClParams::generate(&q, bits - 1, rng).is_err());
+        let params = ClParams::generate(&q, bits, rng).unwrap();
+        assert!(params.p > Integer::from(&q << 2));
+        let (sk, pk) = params.keygen(rng);
+        for m in &[Integer::from(5), Integer::from(&q - 1)] {
+            let c = params.encrypt(&pk, m, rng);
+            assert_eq!(&params.decrypt(&sk, &c).unwrap(), m);
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! Sigma protocols for CL ciphertexts, made non-interactive with Fiat-Shamir.
+//!
+//! `EncryptionProof` shows that a ciphertext is a well-formed encryption
+//! `(g^r, f^m * h^r)` whose plaintext and randomness the prover knows.
+//! `DlogEncryptionProof` additionally shows that the plaintext is the
+//! discrete log of a Ristretto point `Q = m * G`, which is what two-party
+//! and threshold ECDSA-style protocols need from their CL encryptions; it
+//! requires parameters for `q` equal to the curve order.
+//!
+//! The randomness `r` lives in a group of unknown order, so its response is
+//! an integer masked by `STATISTICAL_BITS`; the plaintext's is taken mod `q`.
+
+use super::{Ciphertext, ClParams, PublicKey};
+use crate::commitment::{random_bits, CHALLENGE_BITS, STATISTICAL_BITS};
+use crate::group::{ClassElem, ClassGroup, UnknownOrderGroup};
+use crate::transcript::TranscriptProtocol;
+use anyhow::{ensure, Result};
+use bacteria::Transcript;
+use mohan::dalek::constants::{BASEPOINT_ORDER, RISTRETTO_BASEPOINT_POINT};
+use mohan::dalek::ristretto::RistrettoPoint;
+use mohan::dalek::scalar::Scalar;
+use rand::{CryptoRng, RngCore};
+use rug::integer::Order;
+use rug::ops::RemRounding;
+use rug::Integer;
+
+/// The order of the Ristretto group, the `q` for `DlogEncryptionProof`.
+pub fn curve_order() -> Integer {
+    Integer::from_digits(BASEPOINT_ORDER.as_bytes(), Order::Lsf)
+}
+
+fn to_scalar(x: &Integer) -> Scalar {
+    let mut bytes = [0u8; 32];
+    let digits = Integer::from(x.rem_euc(&curve_order())).to_digits::<u8>(Order::Lsf);
+    bytes[..digits.len()].copy_from_slice(&digits);
+    Scalar::from_bytes_mod_order(bytes)
+}
+
+/// A proof of knowledge of `(m, r)` with `c = (g^r, f^m * h^r)`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EncryptionProof {
+    t1: ClassElem,
+    t2: ClassElem,
+    z_m: Integer,
+    z_r: Integer,
+}
+
+/// A proof of knowledge of `(m, r)` with `c = (g^r, f^m * h^r)` and
+/// `Q = m * G`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DlogEncryptionProof {
+    encryption: EncryptionProof,
+    t: RistrettoPoint,
+}
+
+fn mask_bits(params: &ClParams) -> u32 {
+    params.exponent_bits() + CHALLENGE_BITS + STATISTICAL_BITS
+}
+
+/// Binds the statement and the first message, and draws the challenge.
+fn challenge(
+    params: &ClParams,
+    pk: &PublicKey,
+    c: &Ciphertext,
+    t: [&ClassElem; 2],
+    point: Option<(&RistrettoPoint, &RistrettoPoint)>,
+) -> Integer {
+    let mut transcript = Transcript::new(b"Classygroup.cl");
+    transcript.domain_sep::<ClassGroup>(b"cl.encryption");
+    transcript.append_integer(b"q", params.q());
+    transcript.append_elem::<ClassGroup>(b"f", params.f());
+    transcript.append_elem::<ClassGroup>(b"g", params.g());
+    transcript.append_elem::<ClassGroup>(b"h", pk.value());
+    transcript.append_elem::<ClassGroup>(b"c1", &c.c1);
+    transcript.append_elem::<ClassGroup>(b"c2", &c.c2);
+    transcript.append_elem::<ClassGroup>(b"t1", t[0]);
+    transcript.append_elem::<ClassGroup>(b"t2", t[1]);
+    if let Some((q, t)) = point {
+        transcript.append_message(b"Q", q.compress().as_bytes());
+        transcript.append_message(b"T", t.compress().as_bytes());
+    }
+    transcript.challenge_integer(b"e", CHALLENGE_BITS)
+}
+
+impl EncryptionProof {
+    /// Proves that `c` encrypts `m` under `pk` with randomness `r`. Fails
+    /// unless `r` is in `[0, 2^exponent_bits)`, the range `encrypt` draws it
+    /// from, since the verifier bounds the response by that size.
+    pub fn prove<R: RngCore + CryptoRng>(
+        params: &ClParams,
+        pk: &PublicKey,
+        c: &Ciphertext,
+        m: &Integer,
+        r: &Integer,
+        rng: &mut R,
+    ) -> Result<Self> {
+        Ok(Self::prove_inner(params, pk, c, m, r, None, rng)?.0)
+    }
+
+    fn prove_inner<R: RngCore + CryptoRng>(
+        params: &ClParams,
+        pk: &PublicKey,
+        c: &Ciphertext,
+        m: &Integer,
+        r: &Integer,
+        point: Option<&RistrettoPoint>,
+        rng: &mut R,
+    ) -> Result<(Self, RistrettoPoint)> {
+        ensure!(
+            *r >= 0 && r.significant_bits() <= params.exponent_bits(),
+            "randomness is not in [0, 2^exponent_bits)"
+        );
+        let s_r = random_bits(rng, mask_bits(params));
+        let s_m =
+            random_bits(rng, params.q().significant_bits() + STATISTICAL_BITS).rem_euc(params.q());
+        let t = RISTRETTO_BASEPOINT_POINT * to_scalar(&s_m);
+        params.with_group(|| {
+            let t1 = ClassGroup::pow(params.g(), &s_r);
+            let t2 = ClassGroup::op(&params.encode(&s_m), &ClassGroup::pow(pk.value(), &s_r));
+            let e = challenge(params, pk, c, [&t1, &t2], point.map(|q| (q, &t)));
+            let proof = Self {
+                z_m: Integer::from(&e * m + &s_m).rem_euc(params.q()),
+                z_r: s_r + e * r,
+                t1,
+                t2,
+            };
+            Ok((proof, t))
+        })
+    }
+
+    /// Checks a proof from `prove` that `c` is an encryption under `pk`.
+    pub fn verify(&self, params: &ClParams, pk: &PublicKey, c: &Ciphertext) -> bool {
+        self.verify_inner(params, pk, c, None)
+    }
+
+    fn verify_inner(
+        &self,
+        params: &ClParams,
+        pk: &PublicKey,
+        c: &Ciphertext,
+        point: Option<(&RistrettoPoint, &RistrettoPoint)>,
+    ) -> bool {
+        if self.z_m < 0
+            || self.z_m >= *params.q()
+            || self.z_r < 0
+            || self.z_r.significant_bits() > mask_bits(params) + 1
+        {
+            return false;
+        }
+        params.with_group(|| {
+            if ![&self.t1, &self.t2, &c.c1, &c.c2, pk.value()]
+                .iter()
+                .all(|x| x.a.sgn() > 0 && ClassGroup::is_elem(x))
+            {
+                return false;
+            }
+            let e = challenge(params, pk, c, [&self.t1, &self.t2], point);
+            let lhs1 = ClassGroup::pow(params.g(), &self.z_r);
+            let lhs2 = ClassGroup::op(
+                &params.encode(&self.z_m),
+                &ClassGroup::pow(pk.value(), &self.z_r),
+            );
+            lhs1 == ClassGroup::op(&self.t1, &ClassGroup::pow(&c.c1, &e))
+                && lhs2 == ClassGroup::op(&self.t2, &ClassGroup::pow(&c.c2, &e))
+        })
+    }
+}
+
+impl DlogEncryptionProof {
+    /// Proves that `c` encrypts `m` under `pk` with randomness `r`, and that
+    /// `point = m * G`. Fails unless `params` are for the curve order and `r`
+    /// is in `[0, 2^exponent_bits)`.
+    pub fn prove<R: RngCore + CryptoRng>(
+        params: &ClParams,
+        pk: &PublicKey,
+        c: &Ciphertext,
+        point: &RistrettoPoint,
+        m: &Integer,
+        r: &Integer,
+        rng: &mut R,
+    ) -> Result<Self> {
+        ensure!(
+            *params.q() == curve_order(),
+            "parameters are not for the curve order"
+        );
+        let (encryption, t) = EncryptionProof::prove_inner(params, pk, c, m, r, Some(point), rng)?;
+        Ok(Self { encryption, t })
+    }
+
+    /// Checks a proof from `prove` that `c` is an encryption under `pk` of
+    /// the discrete log of `point`.
+    pub fn verify(
+        &self,
+        params: &ClParams,
+        pk: &PublicKey,
+        c: &Ciphertext,
+        point: &RistrettoPoint,
+    ) -> bool {
+        if *params.q() != curve_order() {
+            return false;
+        }
+        let e = params.with_group(|| {
+            challenge(
+                params,
+                pk,
+                c,
+                [&self.encryption.t1, &self.encryption.t2],
+                Some((point, &self.t)),
+            )
+        });
+        RISTRETTO_BASEPOINT_POINT * to_scalar(&self.encryption.z_m)
+            == self.t + point * to_scalar(&e)
+            && self
+                .encryption
+                .verify_inner(params, pk, c, Some((point, &self.t)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_proofs() {
+        let rng = &mut thread_rng();
+        let params = ClParams::generate(&curve_order(), 640, rng).unwrap();
+        let (_, pk) = params.keygen(rng);
+        let m = random_bits(rng, 200);
+        let r = random_bits(rng, params.exponent_bits());
+        let c = params.encrypt_with(&pk, &m, &r);
+
+        let proof = EncryptionProof::prove(&params, &pk, &c, &m, &r, rng).unwrap();
+        assert!(proof.verify(&params, &pk, &c));
+        let other = params.encrypt(&pk, &m, rng);
+        assert!(!proof.verify(&params, &pk, &other));
+        let wrong =
+            EncryptionProof::prove(&params, &pk, &c, &Integer::from(&m + 1), &r, rng).unwrap();
+        assert!(!wrong.verify(&params, &pk, &c));
+
+        let point = RISTRETTO_BASEPOINT_POINT * to_scalar(&m);
+        let proof = DlogEncryptionProof::prove(&params, &pk, &c, &point, &m, &r, rng).unwrap();
+        assert!(proof.verify(&params, &pk, &c, &point));
+        let elsewhere = point + RISTRETTO_BASEPOINT_POINT;
+        assert!(!proof.verify(&params, &pk, &c, &elsewhere));
+        assert!(!proof.verify(&params, &pk, &other, &point));
+
+        // Randomness wider than `encrypt` draws would give a response the
+        // verifier rejects, so it is refused up front.
+        let wide = Integer::from(1) << params.exponent_bits();
+        let c = params.encrypt_with(&pk, &m, &wide);
+        assert!(EncryptionProof::prove(&params, &pk, &c, &m, &wide, rng).is_err());
+        assert!(DlogEncryptionProof::prove(&params, &pk, &c, &point, &m, &wide, rng).is_err());
+        assert!(EncryptionProof::prove(&params, &pk, &c, &m, &Integer::from(-1), rng).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+//! Building blocks of DARK polynomial commitments, after Bünz, Fisch and
+//! Szepieniec, "Transparent SNARKs from DARK compilers".
+//! https://eprint.iacr.org/2019/1229.pdf
+//!
+//! A polynomial over `Z_p` with coefficients `f_i` is encoded as the integer
+//! `f(q) = f_0 + f_1 * q + ...` for a large base `q` and committed as
+//! `g^f(q)`. An evaluation `f(z) = y mod p` is proven by splitting the
+//! polynomial into halves `f = f_l + X^k * f_r` and folding them into
+//! `f_l + alpha * f_r` for a challenge `alpha`, until a single coefficient
+//! is left to open.
+
+use super::CHALLENGE_BITS;
+use crate::group::{ClassElem, ClassGroup, UnknownOrderGroup};
+use crate::proofs::Poe;
+use crate::transcript::TranscriptProtocol;
+use anyhow::{ensure, Result};
+use bacteria::Transcript;
+use rug::ops::{Pow, RemRounding};
+use rug::Integer;
+
+/// The field and encoding of committed polynomials.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DarkParams {
+    p: Integer,
+    q: Integer,
+    len: usize,
+}
+
+/// The prover's message for one halving round.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FoldMessage {
+    pub c_l: ClassElem,
+    pub c_r: ClassElem,
+    pub y_l: Integer,
+    pub y_r: Integer,
+    /// Shows `c_r^(q^k) = c / c_l` for the half length `k`.
+    pub poe: Poe,
+}
+
+/// A proof of `f(z) = y mod p` for a committed polynomial `f`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EvalProof {
+    rounds: Vec<FoldMessage>,
+    constant: Integer,
+}
+
+/// Writes `n` in base `q` with balanced digits in `(-q/2, q/2]`, least
+/// significant first. This inverts `encode` for coefficients below `q/2` in
+/// absolute value.
+pub fn decompose(n: &Integer, q: &Integer) -> Vec<Integer> {
+    let half = Integer::from(q >> 1);
+    let mut n = n.clone();
+    let mut digits = Vec::new();
+    while n != 0 {
+        let (mut quot, mut rem) = n.div_rem_euc(q.clone());
+        if rem > half {
+            rem -= q;
+            quot += 1;
+        }
+        digits.push(rem);
+        n = quot;
+    }
+    digits
+}
+
+/// The integer `f(q)`.
+pub fn encode(coeffs: &[Integer], q: &Integer) -> Integer {
+    coeffs
+        .iter()
+        .rev()
+        .fold(Integer::new(), |acc, f| acc * q + f)
+}
+
+/// Folds the halves of a polynomial into `f_l + alpha * f_r`.
+pub fn fold(f_l: &[Integer], f_r: &[Integer], alpha: &Integer) -> Vec<Integer> {
+    let mut folded = f_l.to_vec();
+    for (f, r) in folded.iter_mut().zip(f_r) {
+        *f += Integer::from(alpha * r);
+    }
+    folded
+}
+
+fn evaluate(coeffs: &[Integer], z: &Integer, p: &Integer) -> Integer {
+    coeffs
+        .iter()
+        .rev()
+        .fold(Integer::new(), |acc, f| (acc * z + f).rem_euc(p))
+}
+
+impl DarkParams {
+    /// Parameters for polynomials with `len` coefficients over `Z_p`, with `q`
+    /// large enough that folded coefficients never overflow a digit.
+    pub fn new(p: &Integer, len: usize) -> Result<Self> {
+        ensure!(*p > 1, "the modulus must be at least 2");
+        ensure!(len > 0, "a polynomial has at least one coefficient");
+        let mut params = Self {
+            p: p.clone(),
+            q: Integer::new(),
+            len,
+        };
+        params.q = Integer::from(1) << (params.bound().significant_bits() + 1);
+        Ok(params)
+    }
+
+    pub fn p(&self) -> &Integer {
+        &self.p
+    }
+
+    pub fn q(&self) -> &Integer {
+        &self.q
+    }
+
+    /// The number of coefficients of committed polynomials.
+    pub fn coefficients(&self) -> usize {
+        self.len
+    }
+
+    /// Coefficient counts of the polynomial before each round.
+    fn lengths(&self) -> Vec<usize> {
+        let mut lengths = Vec::new();
+        let mut n = self.len;
+        while n > 1 {
+            lengths.push(n);
+            n = n.div_ceil(2);
+        }
+        lengths
+    }
+
+    /// Exclusive bound on the coefficients after every round, each of which
+    /// grows them by at most `2^CHALLENGE_BITS`.
+    fn bound(&self) -> Integer {
+        Integer::from(&self.p) << (CHALLENGE_BITS * self.lengths().len() as u32)
+    }
+
+    /// Commits to a polynomial with `len` coefficients in `[0, p)`.
+    pub fn commit(&self, coeffs: &[Integer]) -> ClassElem {
+        ClassGroup::pow(&ClassGroup::unknown_order_elem(), &encode(coeffs, &self.q))
+    }
+
+    fn transcript(&self, c: &ClassElem, z: &Integer, y: &Integer) -> Transcript {
+        let mut transcript = Transcript::new(b"Classygroup.dark");
+        ClassGroup::append_params(&mut transcript);
+        transcript.append_integer(b"p", &self.p);
+        transcript.append_u64(b"len", self.len as u64);
+        transcript.append_elem::<ClassGroup>(b"commitment", c);
+        transcript.append_integer(b"z", z);
+        transcript.append_integer(b"y", y);
+        transcript
+    }
+
+    fn round_challenge(transcript: &mut Transcript, msg: &FoldMessage) -> Integer {
+        transcript.append_elem::<ClassGroup>(b"c_l", &msg.c_l);
+        transcript.append_elem::<ClassGroup>(b"c_r", &msg.c_r);
+        transcript.append_integer(b"y_l", &msg.y_l);
+        transcript.append_integer(b"y_r", &msg.y_r);
+        transcript.challenge_integer(b"c", CHALLENGE_BITS)
+    }
+
+    /// Evaluates the committed polynomial at `z` and proves the result.
+    pub fn prove(&self, coeffs: &[Integer], z: &Integer) -> Result<(Integer, EvalProof)> {
+        ensure!(
+            coeffs.len() == self.len,
+            "expected {} coefficients",
+            self.len
+        );
+        ensure!(
+            coeffs.iter().all(|f| *f >= 0 && *f < self.p),
+            "coefficients must be reduced mod p"
+        );
+        let mut f = coeffs.to_vec();
+        let mut c = self.commit(&f);
+        let y = evaluate(&f, z, &self.p);
+        let mut transcript = self.transcript(&c, z, &y);
+
+        let mut rounds = Vec::new();
+        while f.len() > 1 {
+            let k = f.len().div_ceil(2);
+            let (f_l, f_r) = f.split_at(k);
+            let c_l = self.commit(f_l);
+            let c_r = self.commit(f_r);
+            let shift = Integer::from((&self.q).pow(k as u32));
+            let msg = FoldMessage {
+                poe: Poe::prove(&c_r, &shift, &ClassGroup::op(&c, &ClassGroup::inv(&c_l))),
+                y_l: evaluate(f_l, z, &self.p),
+                y_r: evaluate(f_r, z, &self.p),
+                c_l,
+                c_r,
+            };
+            let alpha = Self::round_challenge(&mut transcript, &msg);
+            f = fold(f_l, f_r, &alpha);
+            c = ClassGroup::op(&msg.c_l, &ClassGroup::pow(&msg.c_r, &alpha));
+            rounds.push(msg);
+        }
+
+        let constant = f.pop().unwrap();
+        Ok((y, EvalProof { rounds, constant }))
+    }
+
+    /// Checks a proof from `prove` that the polynomial committed in `c`
+    /// evaluates to `y` at `z`.
+    pub fn verify(&self, c: &ClassElem, z: &Integer, y: &Integer, proof: &EvalProof) -> bool {
+        let lengths = self.lengths();
+        if proof.rounds.len() != lengths.len() || *y < 0 || *y >= self.p {
+            return false;
+        }
+        let mut transcript = self.transcript(c, z, y);
+        let (mut c, mut y) = (c.clone(), y.clone());
+        for (msg, n) in proof.rounds.iter().zip(lengths) {
+            let k = n.div_ceil(2);
+            let z_k = Integer::from(z.pow_mod_ref(&Integer::from(k), &self.p).unwrap());
+            let y_split = Integer::from(&msg.y_l + &z_k * &msg.y_r).rem_euc(&self.p);
+            let shift = Integer::from((&self.q).pow(k as u32));
+            let quotient = ClassGroup::op(&c, &ClassGroup::inv(&msg.c_l));
+            if y_split != y
+                || !ClassGroup::validate(&msg.c_l.a, &msg.c_l.b, &msg.c_l.c)
+                || !msg.poe.verify(&msg.c_r, &shift, &quotient)
+            {
+                return false;
+            }
+            let alpha = Self::round_challenge(&mut transcript, msg);
+            c = ClassGroup::op(&msg.c_l, &ClassGroup::pow(&msg.c_r, &alpha));
+            y = Integer::from(&msg.y_l + &alpha * &msg.y_r).rem_euc(&self.p);
+        }
+
+        let f = &proof.constant;
+        *f >= 0
+            && *f < self.bound()
+            && Integer::from(f % &self.p) == y
+            && self.commit(std::slice::from_ref(f)) == c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompose() {
+        let q = Integer::from(1000);
+        let digits: Vec<Integer> = [7, -499, 500, 0, -1]
+            .iter()
+            .map(|&d| Integer::from(d))
+            .collect();
+        let n = encode(&digits, &q);
+        assert_eq!(decompose(&n, &q), digits);
+        assert!(decompose(&Integer::new(), &q).is_empty());
+    }
+
+    #[test]
+    fn test_prove_verify() {
+        let p = Integer::from(Integer::u_pow_u(2, 61)) - 1;
+        let params = DarkParams::new(&p, 5).unwrap();
+        let coeffs: Vec<Integer> = [3, 1, 4, 1, 5].iter().map(|&f| Integer::from(f)).collect();
+        let c = params.commit(&coeffs);
+        let z = Integer::from(10);
+
+        let (y, proof) = params.prove(&coeffs, &z).unwrap();
+        assert_eq!(y, 51413);
+        assert!(params.verify(&c, &z, &y, &proof));
+        assert!(!params.verify(&c, &z, &Integer::from(&y + 1), &proof));
+        assert!(!params.verify(&c, &Integer::from(11), &y, &proof));
+
+        let other = params.commit(&coeffs[..4]);
+        assert!(!params.verify(&other, &z, &y, &proof));
+        assert!(params.prove(&coeffs[..4], &z).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! Damgård-Fujisaki integer commitments `g^m * h^r` in the class group.
+//!
+//! `g` is the group's generator and `h` is derived with `hash_to_group`, so
+//! nobody knows the discrete log of one to the other. Commitments hide `m`
+//! statistically when `r` is drawn `STATISTICAL_BITS` beyond the size of the
+//! group, and bind under the strong root assumption.
+//!
+//! All operations run in the calling thread's current class group.
+
+use crate::group::{ClassElem, ClassGroup, UnknownOrderGroup};
+use crate::transcript::TranscriptProtocol;
+use bacteria::Transcript;
+use rand::{CryptoRng, RngCore};
+use rug::integer::Order;
+use rug::Integer;
+
+pub mod dark;
+mod pedersen;
+pub use pedersen::{PedersenCommitment, PedersenKey};
+mod range;
+pub use range::RangeProof;
+
+/// Slack, in bits, for statistical hiding of randomness and responses.
+pub const STATISTICAL_BITS: u32 = 128;
+/// Bit length of Fiat-Shamir challenges.
+pub const CHALLENGE_BITS: u32 = 128;
+
+/// A uniformly random non-negative integer below `2^bits`.
+pub(crate) fn random_bits<R: RngCore + CryptoRng>(rng: &mut R, bits: u32) -> Integer {
+    let mut bytes = vec![0u8; bits.div_ceil(8) as usize];
+    rng.fill_bytes(&mut bytes);
+    let mut x = Integer::from_digits(&bytes, Order::Lsf);
+    x.keep_bits_mut(bits);
+    x
+}
+
+pub(crate) fn is_elem(x: &ClassElem) -> bool {
+    <ClassGroup as UnknownOrderGroup>::is_elem(x)
+}
+
+/// The public bases `g` and `h` of a commitment scheme.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CommitmentKey {
+    g: ClassElem,
+    h: ClassElem,
+}
+
+/// A commitment `g^m * h^r` to an integer `m`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Commitment {
+    value: ClassElem,
+}
+
+/// A proof of knowledge of an opening `(m, r)` of a commitment that reveals
+/// neither.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OpeningProof {
+    t: ClassElem,
+    s_m: Integer,
+    s_r: Integer,
+}
+
+impl Commitment {
+    pub fn value(&self) -> &ClassElem {
+        &self.value
+    }
+}
+
+impl CommitmentKey {
+    /// The key for the current group, with `h` hashed from `domain`.
+    pub fn setup(domain: &[u8]) -> Self {
+        Self {
+            g: ClassGroup::unknown_order_elem(),
+            h: ClassGroup::hash_to_group(domain),
+        }
+    }
+
+    pub fn g(&self) -> &ClassElem {
+        &self.g
+    }
+
+    pub fn h(&self) -> &ClassElem {
+        &self.h
+    }
+
+    /// Bits of randomness that hide a commitment: half the discriminant's size,
+    /// which bounds the group order's, plus `STATISTICAL_BITS`.
+    pub fn randomness_bits() -> u32 {
+        ClassGroup::context_discriminant().bit_length() as u32 / 2 + STATISTICAL_BITS
+    }
+
+    pub fn commit(&self, m: &Integer, r: &Integer) -> Commitment {
+        Commitment {
+            value: ClassGroup::op(&ClassGroup::pow(&self.g, m), &ClassGroup::pow(&self.h, r)),
+        }
+    }
+
+    /// Commits to `m` with fresh randomness, which is returned for opening.
+    pub fn commit_random<R: RngCore + CryptoRng>(
+        &self,
+        m: &Integer,
+        rng: &mut R,
+    ) -> (Commitment, Integer) {
+        let r = random_bits(rng, Self::randomness_bits());
+        (self.commit(m, &r), r)
+    }
+
+    /// Checks that `(m, r)` opens `c`.
+    pub fn verify_opening(&self, c: &Commitment, m: &Integer, r: &Integer) -> bool {
+        self.commit(m, r) == *c
+    }
+
+    fn opening_challenge(&self, c: &Commitment, t: &ClassElem) -> Integer {
+        let mut transcript = Transcript::new(b"Classygroup.commitment.opening");
+        ClassGroup::append_params(&mut transcript);
+        transcript.append_elem::<ClassGroup>(b"g", &self.g);
+        transcript.append_elem::<ClassGroup>(b"h", &self.h);
+        transcript.append_elem::<ClassGroup>(b"commitment", &c.value);
+        transcript.append_elem::<ClassGroup>(b"t", t);
+        transcript.challenge_integer(b"c", CHALLENGE_BITS)
+    }
+
+    /// Proves knowledge of the opening `(m, r)` of `c` in zero knowledge.
+    pub fn prove_opening<R: RngCore + CryptoRng>(
+        &self,
+        c: &Commitment,
+        m: &Integer,
+        r: &Integer,
+        rng: &mut R,
+    ) -> OpeningProof {
+        // The masks exceed `c * m` and `c * r` by `STATISTICAL_BITS`.
+        let slack = CHALLENGE_BITS + STATISTICAL_BITS;
+        let k_m = random_bits(rng, m.significant_bits() + slack);
+        let k_r = random_bits(rng, Self::randomness_bits() + slack);
+        let t = self.commit(&k_m, &k_r).value;
+
+        let e = self.opening_challenge(c, &t);
+        OpeningProof {
+            s_m: k_m + Integer::from(&e * m),
+            s_r: k_r + Integer::from(&e * r),
+            t,
+        }
+    }
+
+    /// Checks a proof from `prove_opening`.
+    pub fn verify_opening_proof(&self, c: &Commitment, proof: &OpeningProof) -> bool {
+        if !is_elem(&proof.t) {
+            return false;
+        }
+        let e = self.opening_challenge(c, &proof.t);
+        let lhs = self.commit(&proof.s_m, &proof.s_r).value;
+        lhs == ClassGroup::op(&proof.t, &ClassGroup::pow(&c.value, &e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_commit_open() {
+        let key = CommitmentKey::setup(b"test");
+        let m = Integer::from(-1234567);
+        let (c, r) = key.commit_random(&m, &mut thread_rng());
+        assert!(key.verify_opening(&c, &m, &r));
+        assert!(!key.verify_opening(&c, &Integer::from(&m + 1), &r));
+        assert_ne!(c, key.commit_random(&m, &mut thread_rng()).0);
+
+        let proof = key.prove_opening(&c, &m, &r, &mut thread_rng());
+        assert!(key.verify_opening_proof(&c, &proof));
+        assert!(!key.verify_opening_proof(&key.commit(&m, &Integer::from(&r + 1)), &proof));
+
+        let mut forged = proof;
+        forged.s_m += 1;
+        assert!(!key.verify_opening_proof(&c, &forged));
+    }
+}
@@ -0,0 +1,107 @@
+//! Pedersen-style commitments `g^m * h^r` with both bases hashed.
+//!
+//! Unlike `CommitmentKey`, neither base is the group's generator: both come
+//! from `hash_to_group` under one domain, so nobody knows a discrete log
+//! relation between them or to any other base. There are no proofs attached,
+//! only commitment, opening and the homomorphism `c(m1, r1) * c(m2, r2) =
+//! c(m1 + m2, r1 + r2)`.
+
+use super::{random_bits, CommitmentKey};
+use crate::group::{ClassElem, ClassGroup};
+use rand::{CryptoRng, RngCore};
+use rug::Integer;
+use std::ops::Add;
+
+/// Two bases with unknown relative discrete log.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PedersenKey {
+    g: ClassElem,
+    h: ClassElem,
+}
+
+/// A commitment `g^m * h^r` to an integer `m`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PedersenCommitment {
+    value: ClassElem,
+}
+
+impl PedersenCommitment {
+    pub fn value(&self) -> &ClassElem {
+        &self.value
+    }
+}
+
+/// Commits to the sum of the messages under the sum of the randomness.
+impl Add for &PedersenCommitment {
+    type Output = PedersenCommitment;
+
+    fn add(self, other: &PedersenCommitment) -> PedersenCommitment {
+        PedersenCommitment {
+            value: ClassGroup::op(&self.value, &other.value),
+        }
+    }
+}
+
+impl PedersenKey {
+    /// The key for the current group, with both bases hashed from `domain`.
+    pub fn setup(domain: &[u8]) -> Self {
+        Self {
+            g: ClassGroup::hash_to_group(&[domain, b"g"].concat()),
+            h: ClassGroup::hash_to_group(&[domain, b"h"].concat()),
+        }
+    }
+
+    pub fn g(&self) -> &ClassElem {
+        &self.g
+    }
+
+    pub fn h(&self) -> &ClassElem {
+        &self.h
+    }
+
+    pub fn commit(&self, m: &Integer, r: &Integer) -> PedersenCommitment {
+        PedersenCommitment {
+            value: ClassGroup::op(&ClassGroup::pow(&self.g, m), &ClassGroup::pow(&self.h, r)),
+        }
+    }
+
+    /// Commits to `m` with fresh randomness of `CommitmentKey::randomness_bits`,
+    /// which is returned for opening.
+    pub fn commit_random<R: RngCore + CryptoRng>(
+        &self,
+        m: &Integer,
+        rng: &mut R,
+    ) -> (PedersenCommitment, Integer) {
+        let r = random_bits(rng, CommitmentKey::randomness_bits());
+        (self.commit(m, &r), r)
+    }
+
+    /// Checks that `(m, r)` opens `c`.
+    pub fn verify_opening(&self, c: &PedersenCommitment, m: &Integer, r: &Integer) -> bool {
+        self.commit(m, r) == *c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_commit_add() {
+        let key = PedersenKey::setup(b"test");
+        assert_eq!(key, PedersenKey::setup(b"test"));
+        assert_ne!(key.g(), key.h());
+        assert_ne!(key, PedersenKey::setup(b"other"));
+
+        let (m1, m2) = (Integer::from(1000), Integer::from(-42));
+        let (c1, r1) = key.commit_random(&m1, &mut thread_rng());
+        let (c2, r2) = key.commit_random(&m2, &mut thread_rng());
+        assert!(key.verify_opening(&c1, &m1, &r1));
+        assert!(!key.verify_opening(&c1, &m2, &r1));
+
+        let sum = &c1 + &c2;
+        assert!(key.verify_opening(&sum, &Integer::from(&m1 + &m2), &Integer::from(&r1 + &r2)));
+        assert!(!key.verify_opening(&sum, &m1, &Integer::from(&r1 + &r2)));
+    }
+}
@@ -0,0 +1,212 @@
+//! Zero-knowledge proofs that a committed integer lies in `[0, 2^n)`.
+//!
+//! The prover commits to each bit `b_i` of `m` as `c_i = g^b_i * h^r_i`,
+//! choosing the `r_i` so that the `c_i^(2^i)` multiply to the original
+//! commitment, and shows with an OR proof that each `c_i` opens to 0 or 1.
+
+use super::{is_elem, random_bits, Commitment, CommitmentKey};
+use super::{CHALLENGE_BITS, STATISTICAL_BITS};
+use crate::group::{ClassElem, ClassGroup, UnknownOrderGroup};
+use crate::transcript::TranscriptProtocol;
+use anyhow::{ensure, Result};
+use bacteria::Transcript;
+use rand::{CryptoRng, RngCore};
+use rug::Integer;
+
+/// Shows that a bit commitment `c` is `h^r` or `g * h^r` for a known `r`,
+/// without saying which. The challenge for the second branch is the round's
+/// challenge XOR `e0`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct BitProof {
+    t0: ClassElem,
+    t1: ClassElem,
+    e0: Integer,
+    s0: Integer,
+    s1: Integer,
+}
+
+/// A proof that a commitment opens to some `m` with `0 <= m < 2^n`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RangeProof {
+    bits: Vec<ClassElem>,
+    proofs: Vec<BitProof>,
+}
+
+impl CommitmentKey {
+    fn range_transcript(&self, c: &Commitment, bits: &[ClassElem]) -> Transcript {
+        let mut transcript = Transcript::new(b"Classygroup.commitment.range");
+        ClassGroup::append_params(&mut transcript);
+        transcript.append_elem::<ClassGroup>(b"g", &self.g);
+        transcript.append_elem::<ClassGroup>(b"h", &self.h);
+        transcript.append_elem::<ClassGroup>(b"commitment", &c.value);
+        for bit in bits {
+            transcript.append_elem::<ClassGroup>(b"bit", bit);
+        }
+        transcript
+    }
+
+    /// Bits of the masks in bit proofs, enough to hide `e * r_i` for every
+    /// bit's randomness.
+    fn range_mask_bits(n: u32) -> u32 {
+        Self::randomness_bits() + n + CHALLENGE_BITS + 2 * STATISTICAL_BITS
+    }
+
+    /// Proves in zero knowledge that `(m, r)`, which must open `c`, has
+    /// `0 <= m < 2^n`.
+    pub fn prove_range<R: RngCore + CryptoRng>(
+        &self,
+        c: &Commitment,
+        m: &Integer,
+        r: &Integer,
+        n: u32,
+        rng: &mut R,
+    ) -> Result<RangeProof> {
+        ensure!(n > 0, "the range must have at least one bit");
+        ensure!(
+            *m >= 0 && m.significant_bits() <= n,
+            "the value is out of range"
+        );
+        ensure!(self.verify_opening(c, m, r), "the opening does not match");
+
+        // Bits above the first get fresh randomness, and the first absorbs
+        // the difference so that the product of the `c_i^(2^i)` is `c`.
+        let mut rs: Vec<Integer> = (1..n)
+            .map(|_| random_bits(rng, Self::randomness_bits() + n + STATISTICAL_BITS))
+            .collect();
+        let rest = rs.iter().enumerate().fold(Integer::new(), |acc, (i, r_i)| {
+            acc + (Integer::from(r_i) << (i as u32 + 1))
+        });
+        rs.insert(0, r - rest);
+
+        let bits: Vec<ClassElem> = rs
+            .iter()
+            .enumerate()
+            .map(|(i, r_i)| self.commit(&Integer::from(m.get_bit(i as u32)), r_i).value)
+            .collect();
+        let mut transcript = self.range_transcript(c, &bits);
+
+        let mask_bits = Self::range_mask_bits(n);
+        let g_inv = ClassGroup::inv(&self.g);
+        let mut proofs = Vec::with_capacity(bits.len());
+        for (i, (bit, r_i)) in bits.iter().zip(&rs).enumerate() {
+            // The branch for the real bit is proven honestly and the other
+            // one is simulated with a challenge chosen in advance.
+            let one = m.get_bit(i as u32);
+            let fake = if one {
+                bit.clone()
+            } else {
+                ClassGroup::op(bit, &g_inv)
+            };
+
+            let e_fake = random_bits(rng, CHALLENGE_BITS);
+            let s_fake = random_bits(rng, mask_bits);
+            let t_fake = ClassGroup::op(
+                &ClassGroup::pow(&self.h, &s_fake),
+                &ClassGroup::pow(&fake, &Integer::from(-&e_fake)),
+            );
+            let k = random_bits(rng, mask_bits);
+            let t_real = ClassGroup::pow(&self.h, &k);
+
+            let (t0, t1) = if one {
+                (t_fake, t_real)
+            } else {
+                (t_real, t_fake)
+            };
+            transcript.append_elem::<ClassGroup>(b"t0", &t0);
+            transcript.append_elem::<ClassGroup>(b"t1", &t1);
+            let e = transcript.challenge_integer(b"c", CHALLENGE_BITS);
+            let e_real = Integer::from(&e ^ &e_fake);
+            let s_real = k + Integer::from(&e_real * r_i);
+
+            proofs.push(if one {
+                BitProof {
+                    t0,
+                    t1,
+                    e0: e_fake,
+                    s0: s_fake,
+                    s1: s_real,
+                }
+            } else {
+                BitProof {
+                    t0,
+                    t1,
+                    e0: e_real,
+                    s0: s_real,
+                    s1: s_fake,
+                }
+            });
+        }
+        Ok(RangeProof { bits, proofs })
+    }
+
+    /// Checks a proof from `prove_range` that `c` opens to a value in
+    /// `[0, 2^n)`.
+    pub fn verify_range(&self, c: &Commitment, n: u32, proof: &RangeProof) -> bool {
+        let n = n as usize;
+        if n == 0 || proof.bits.len() != n || proof.proofs.len() != n {
+            return false;
+        }
+        if !proof.bits.iter().all(is_elem) {
+            return false;
+        }
+        let recombined = proof.bits.iter().rev().fold(ClassGroup::id(), |acc, bit| {
+            let mut acc = acc;
+            ClassGroup::square(&mut acc);
+            ClassGroup::op(&acc, bit)
+        });
+        if recombined != c.value {
+            return false;
+        }
+
+        let mut transcript = self.range_transcript(c, &proof.bits);
+        let g_inv = ClassGroup::inv(&self.g);
+        for (bit, p) in proof.bits.iter().zip(&proof.proofs) {
+            if !is_elem(&p.t0)
+                || !is_elem(&p.t1)
+                || p.e0 < 0
+                || p.e0.significant_bits() > CHALLENGE_BITS
+            {
+                return false;
+            }
+            transcript.append_elem::<ClassGroup>(b"t0", &p.t0);
+            transcript.append_elem::<ClassGroup>(b"t1", &p.t1);
+            let e = transcript.challenge_integer(b"c", CHALLENGE_BITS);
+            let e1 = Integer::from(&e ^ &p.e0);
+
+            let shifted = ClassGroup::op(bit, &g_inv);
+            let zero = ClassGroup::pow(&self.h, &p.s0)
+                == ClassGroup::op(&p.t0, &ClassGroup::pow(bit, &p.e0));
+            let one = ClassGroup::pow(&self.h, &p.s1)
+                == ClassGroup::op(&p.t1, &ClassGroup::pow(&shifted, &e1));
+            if !zero || !one {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_range_proof() {
+        let key = CommitmentKey::setup(b"test");
+        let m = Integer::from(0b1011);
+        let (c, r) = key.commit_random(&m, &mut thread_rng());
+
+        let proof = key.prove_range(&c, &m, &r, 4, &mut thread_rng()).unwrap();
+        assert!(key.verify_range(&c, 4, &proof));
+        assert!(!key.verify_range(&c, 5, &proof));
+        assert!(!key.verify_range(&key.commit(&m, &Integer::from(&r + 1)), 4, &proof));
+
+        assert!(key.prove_range(&c, &m, &r, 3, &mut thread_rng()).is_err());
+        let negative = Integer::from(-1);
+        let (c, r) = key.commit_random(&negative, &mut thread_rng());
+        assert!(key
+            .prove_range(&c, &negative, &r, 4, &mut thread_rng())
+            .is_err());
+    }
+}
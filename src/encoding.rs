@@ -0,0 +1,162 @@
+//! Text encodings of byte strings, for putting elements, discriminants and
+//! proofs into configs, logs and JSON.
+//!
+//! `ClassElem` and the proofs implement `Display` and `FromStr` as lowercase
+//! hex of the same canonical bytes their serde impls carry. Discriminants,
+//! being `Mpz`s, keep their decimal `Display` and format as hex with `{:x}`,
+//! which `Mpz::from_str` reads back after a `0x` prefix. `to_base64` and
+//! `from_base64` are there for APIs that want something shorter.
+//!
+//! For people rather than parsers, elements and proofs also have a
+//! `to_json_debug` that spells out every coefficient in decimal.
+
+use anyhow::{anyhow, bail, ensure, Result};
+
+/// Lowercase hex, two digits per byte.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Parses hex in either case, with or without a `0x` prefix.
+pub fn from_hex(s: &str) -> Result<Vec<u8>> {
+    s.trim_start_matches("0x")
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .filter(|digits| digits.len() == 2)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| anyhow!("invalid hex string {:?}", s))
+        })
+        .collect()
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard base64 with padding, as in RFC 4648.
+pub fn to_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Parses the output of `to_base64`. Padding is required.
+pub fn from_base64(s: &str) -> Result<Vec<u8>> {
+    let s = s.as_bytes();
+    ensure!(s.len() % 4 == 0, "base64 length is not a multiple of 4");
+    let mut out = Vec::with_capacity(s.len() / 4 * 3);
+    for (i, chunk) in s.chunks(4).enumerate() {
+        let last = i == s.len() / 4 - 1;
+        let pad = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        ensure!(pad <= 2 && (pad == 0 || last), "invalid base64 padding");
+        let mut n = 0u32;
+        for &c in &chunk[..4 - pad] {
+            let v = match BASE64.iter().position(|&d| d == c) {
+                Some(v) => v as u32,
+                None => bail!("invalid base64 character {:?}", c as char),
+            };
+            n = n << 6 | v;
+        }
+        n <<= 6 * pad as u32;
+        out.extend_from_slice(&n.to_be_bytes()[1..4 - pad]);
+    }
+    Ok(out)
+}
+
+/// A JSON object of `fields`, whose values must already be JSON.
+pub(crate) fn json_object(fields: &[(&str, String)]) -> String {
+    let fields: Vec<_> = fields
+        .iter()
+        .map(|(name, value)| format!("\"{}\":{}", name, value))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// `x` as a JSON string of its decimal digits.
+pub(crate) fn json_decimal<T: std::fmt::Display>(x: &T) -> String {
+    format!("\"{}\"", x)
+}
+
+/// Implements `Display` and `FromStr` for `$ty` as the hex of
+/// `$to_bytes(&x)`, parsed back with `$from_bytes`.
+macro_rules! impl_hex {
+    ($ty:ty, $to_bytes:expr, $from_bytes:expr) => {
+        impl ::std::fmt::Display for $ty {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                let to_bytes: fn(&$ty) -> Vec<u8> = $to_bytes;
+                f.write_str(&$crate::encoding::to_hex(&to_bytes(self)))
+            }
+        }
+
+        impl ::std::str::FromStr for $ty {
+            type Err = ::anyhow::Error;
+
+            fn from_str(s: &str) -> ::anyhow::Result<Self> {
+                let from_bytes: fn(&[u8]) -> ::anyhow::Result<$ty> = $from_bytes;
+                from_bytes(&$crate::encoding::from_hex(s)?)
+            }
+        }
+    };
+}
+pub(crate) use impl_hex;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::{ClassElem, ClassGroup, CLASS_GROUP_DISCRIMINANT};
+    use crate::num::Mpz;
+    use crate::proofs::Poe;
+    use rug::Integer;
+
+    #[test]
+    fn test_strings() {
+        for (bytes, b64) in &[
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"fooba", "Zm9vYmE="),
+            (b"foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(to_base64(bytes), *b64);
+            assert_eq!(from_base64(b64).unwrap(), *bytes);
+        }
+        assert!(from_base64("Zg=").is_err());
+        assert!(from_base64("Zg==Zm8=").is_err());
+        assert!(from_base64("Z!==").is_err());
+        assert_eq!(from_hex("0x00fF").unwrap(), vec![0, 255]);
+        assert!(from_hex("abc").is_err());
+
+        let disc = &*CLASS_GROUP_DISCRIMINANT;
+        let hex = format!("{:x}", disc);
+        assert!(hex.starts_with("-0x"));
+        assert_eq!(hex.parse::<Mpz>().unwrap(), *disc);
+
+        let g = ClassGroup::unknown_order_elem();
+        let w = ClassGroup::pow(&g, &Integer::from(99));
+        assert_eq!(w.to_string().parse::<ClassElem>().unwrap(), w);
+        let proof = Poe::<ClassGroup>::prove(&g, &Integer::from(99), &w);
+        assert_eq!(proof.to_string().parse::<Poe>().unwrap(), proof);
+        assert!("zz".parse::<ClassElem>().is_err());
+
+        let json = ClassGroup::id().to_json_debug();
+        assert!(json.starts_with(r#"{"a":"1","b":"1","c":""#));
+        assert!(json.ends_with(&format!(r#""discriminant":"{}"}}"#, disc)));
+        assert!(proof.to_json_debug().starts_with(r#"{"q":{"a":""#));
+        assert!(serde_json::from_str::<serde_json::Value>(&proof.to_json_debug()).is_ok());
+    }
+}
@@ -2,7 +2,7 @@
 
 use super::lin_congruence_ctx::LinCongruenceCtx;
 use crate::group::CLASS_GROUP_DISCRIMINANT;
-use crate::num::{partial, Mpz};
+use crate::num::{partial, Mpz, MpzPool};
 
 #[allow(clippy::type_complexity)]
 pub struct OpCtx {
@@ -27,32 +27,41 @@ pub struct OpCtx {
     ),
 }
 
-impl Default for OpCtx {
-    fn default() -> Self {
+impl OpCtx {
+    /// Temporaries with room for `bits`-bit values, so the group operations
+    /// do not reallocate them as they run.
+    pub fn with_capacity_bits(bits: usize) -> Self {
+        let m = || Mpz::with_capacity_bits(bits);
         Self {
             inner: (
-                Mpz::default(),
-                Mpz::default(),
-                Mpz::default(),
-                Mpz::default(),
-                Mpz::default(),
-                Mpz::default(),
-                Mpz::default(),
-                Mpz::default(),
-                Mpz::default(),
-                Mpz::default(),
-                Mpz::default(),
-                Mpz::default(),
-                Mpz::default(),
-                Mpz::default(),
-                Mpz::default(),
-                Mpz::default(),
-                Mpz::default(),
+                m(),
+                m(),
+                m(),
+                m(),
+                m(),
+                m(),
+                m(),
+                m(),
+                m(),
+                m(),
+                m(),
+                m(),
+                m(),
+                m(),
+                m(),
+                m(),
+                m(),
             ),
         }
     }
 }
 
+impl Default for OpCtx {
+    fn default() -> Self {
+        Self::with_capacity_bits(0)
+    }
+}
+
 #[allow(non_snake_case)]
 #[allow(clippy::type_complexity)]
 pub struct ClassCtx {
@@ -69,16 +78,22 @@ pub struct ClassCtx {
 
     // Context that handles partial extended GCD.
     pub partial_context: partial::PartialGCDContext,
+
+    // Integers of recycled elements, for the results of group operations.
+    pub pool: MpzPool,
 }
 
 impl ClassCtx {
-    fn from_discriminant(disc: &Mpz) -> Self {
+    pub fn from_discriminant(disc: &Mpz) -> Self {
         let mut s = Self {
             L: Mpz::default(),
             D: disc.clone(),
-            op_ctx: OpCtx::default(),
+            // Products of coefficients reach the size of the discriminant;
+            // leave room for the intermediate sums as well.
+            op_ctx: OpCtx::with_capacity_bits(disc.bit_length() + 64),
             lin_cong_ctx: LinCongruenceCtx::default(),
             partial_context: Default::default(),
+            pool: MpzPool::with_capacity_bits(disc.bit_length() + 64),
         };
 
         // Precomputation needed for NUDULP.
@@ -93,9 +108,10 @@ impl Default for ClassCtx {
         let mut s = Self {
             L: Mpz::default(),
             D: CLASS_GROUP_DISCRIMINANT.clone(),
-            op_ctx: OpCtx::default(),
+            op_ctx: OpCtx::with_capacity_bits(CLASS_GROUP_DISCRIMINANT.bit_length() + 64),
             lin_cong_ctx: LinCongruenceCtx::default(),
             partial_context: Default::default(),
+            pool: MpzPool::with_capacity_bits(CLASS_GROUP_DISCRIMINANT.bit_length() + 64),
         };
 
         // Precomputation needed for NUDULP.
@@ -2,8 +2,9 @@
 
 use super::CLASS_GROUP_DISCRIMINANT;
 use crate::group::{ClassCtx, ClassElem};
+use crate::hash::hash_to_prime;
 use crate::mut_tuple_elems;
-use crate::num::Mpz;
+use crate::num::{Mpz, MpzPool};
 use rug::Integer;
 use std::cell::RefCell;
 
@@ -31,6 +32,26 @@ macro_rules! with_ctx {
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ClassGroup {}
 
+/// Puts a saved context back when dropped, for `with_discriminant`.
+struct RestoreCtx(Option<ClassCtx>);
+
+impl Drop for RestoreCtx {
+    fn drop(&mut self) {
+        if let Some(ctx) = self.0.take() {
+            CTX.with(|refcell| *refcell.borrow_mut() = ctx);
+        }
+    }
+}
+
+/// An element to write a result into, made of recycled integers.
+fn pooled_elem(pool: &mut MpzPool) -> ClassElem {
+    ClassElem {
+        a: pool.take(),
+        b: pool.take(),
+        c: pool.take(),
+    }
+}
+
 #[inline]
 fn log2(x: u64) -> u32 {
     63 - x.leading_zeros()
@@ -85,7 +106,38 @@ pub fn test_reduction(x: &mut ClassElem) -> bool {
 }
 
 impl ClassGroup {
-    fn discriminant(a: &Mpz, b: &Mpz, c: &Mpz) -> Mpz {
+    /// Switches the calling thread's class group context over to `disc`.
+    ///
+    /// Every thread starts out on `CLASS_GROUP_DISCRIMINANT`. Elements of a
+    /// different group (e.g. one built with `create_discriminant`) may only be
+    /// operated on once the context has been switched.
+    pub fn set_discriminant(disc: &Mpz) {
+        CTX.with(|refcell| *refcell.borrow_mut() = ClassCtx::from_discriminant(disc));
+    }
+
+    /// The discriminant of the calling thread's class group context.
+    pub fn context_discriminant() -> Mpz {
+        with_ctx!(|ctx: &mut ClassCtx| ctx.D.clone())
+    }
+
+    /// The length of `ClassElem::to_bytes` for the calling thread's group.
+    pub fn element_size_bytes() -> usize {
+        ClassElem::serialized_size(Self::context_discriminant().bit_length())
+    }
+
+    /// Runs `f` with the thread's context switched to `disc`, restoring the
+    /// previous context afterwards, even if `f` panics. If the thread is
+    /// already on `disc`, its context is used as it is.
+    pub fn with_discriminant<T, F: FnOnce() -> T>(disc: &Mpz, f: F) -> T {
+        if with_ctx!(|ctx: &mut ClassCtx| ctx.D == *disc) {
+            return f();
+        }
+        let previous = CTX.with(|refcell| refcell.replace(ClassCtx::from_discriminant(disc)));
+        let _restore = RestoreCtx(Some(previous));
+        f()
+    }
+
+    pub(crate) fn discriminant(a: &Mpz, b: &Mpz, c: &Mpz) -> Mpz {
         with_ctx!(|ctx: &mut ClassCtx| {
             let (scratch,) = mut_tuple_elems!(ctx.op_ctx, 0);
 
@@ -174,8 +226,7 @@ impl ClassGroup {
             ay_sq_op.mul(&G_sq_op, &y_sq_op);
 
             // Step 5 in Alg 2.
-            t_sq_op.mul(&Dy_sq_op, &bx_sq_op);
-            t_sq_op.submul(&x.c, &x_sq_op);
+            t_sq_op.fmms(&Dy_sq_op, &bx_sq_op, &x.c, &x_sq_op);
             dx_sq_op.divexact(&t_sq_op, &By_sq_op);
             Q1_sq_op.mul(&y_sq_op, &dx_sq_op);
             dy_sq_op.add(&Q1_sq_op, &Dy_sq_op);
@@ -195,6 +246,34 @@ impl ClassGroup {
         Self::reduce_mut(x);
     }
 
+    /// Squares `x` in place `iterations` times, i.e. computes `x^(2^iterations)`.
+    pub fn repeated_square(x: &mut ClassElem, iterations: u64) {
+        for _ in 0..iterations {
+            Self::square(x);
+        }
+    }
+
+    /// Like `repeated_square`, but calls `progress` with the number of squarings
+    /// done so far after every `every` squarings, and after the last one.
+    ///
+    /// The callback runs outside the group context, so it may be as simple as
+    /// `|done| counter.store(done, Ordering::Relaxed)` on an `AtomicU64`.
+    pub fn repeated_square_with_progress<F: FnMut(u64)>(
+        x: &mut ClassElem,
+        iterations: u64,
+        every: u64,
+        mut progress: F,
+    ) {
+        let every = std::cmp::max(every, 1);
+        let mut done = 0;
+        while done < iterations {
+            let n = std::cmp::min(every, iterations - done);
+            Self::repeated_square(x, n);
+            done += n;
+            progress(done);
+        }
+    }
+
     fn reduce_mut(x: &mut ClassElem) {
         Self::normalize_mut(x);
         Self::reduce(x);
@@ -440,11 +519,10 @@ impl ClassGroup {
             } 
             
             /* nucomp calculation */
-            let mut ret = ClassElem::default();
-            //TODO: Remove these allocations
-            let mut r1 = Mpz::default();
-            let mut r2 = Mpz::default();
-            let mut m2 = Mpz::default();
+            let mut ret = pooled_elem(&mut ctx.pool);
+            let mut r1 = ctx.pool.take();
+            let mut r2 = ctx.pool.take();
+            let mut m2 = ctx.pool.take();
             // a1 = x.a;
             // a2 = y.a;
             // c2 = y.c;
@@ -548,6 +626,9 @@ impl ClassGroup {
 
             }
 
+            ctx.pool.put(r1);
+            ctx.pool.put(r2);
+            ctx.pool.put(m2);
             ret
         });
 
@@ -604,7 +685,7 @@ impl ClassGroup {
             a.mul(&s, &t);
             m.fdiv_q_mut(&a);
 
-            let mut ret = ClassElem::default();
+            let mut ret = pooled_elem(&mut ctx.pool);
 
             ret.a.mul(&s, &t);
             a.mul(&r, &u);
@@ -637,12 +718,22 @@ impl ClassGroup {
             let mut ret = ClassElem::default();
             ret.a.set_ui(1);
             ret.b.set_ui(1);
-            a.sub(&ret.b, &CLASS_GROUP_DISCRIMINANT);
+            a.sub(&ret.b, &ctx.D);
             ret.c.fdiv_q_ui(&a, 4);
             ret
         })
     }
 
+    /// Hands the integers of `x` back to the thread's context, where the
+    /// results of later operations reuse their allocations.
+    pub fn recycle(x: ClassElem) {
+        with_ctx!(|ctx: &mut ClassCtx| {
+            ctx.pool.put(x.a);
+            ctx.pool.put(x.b);
+            ctx.pool.put(x.c);
+        })
+    }
+
     pub fn inv(x: &ClassElem) -> ClassElem {
         let mut ret = ClassElem::default();
         ret.a.set(&x.a);
@@ -665,7 +756,8 @@ impl ClassGroup {
             }
 
             if n.is_odd() {
-                val = Self::op(&val, &a);
+                let next = Self::op(&val, &a);
+                Self::recycle(std::mem::replace(&mut val, next));
             }
 
             Self::square(&mut a);
@@ -709,8 +801,38 @@ impl ClassGroup {
         }
     }
 
-    fn validate(a: &Mpz, b: &Mpz, c: &Mpz) -> bool {
-        ClassGroup::discriminant(a, b, c) == *CLASS_GROUP_DISCRIMINANT
+    /// Hashes `seed` to an element of the current group whose discrete log to
+    /// the generator is unknown.
+    ///
+    /// The element is the reduced form of `(p, b, c)` for the first prime `p`
+    /// out of `hash_to_prime(seed || counter)` with `p = 3 mod 4` that has a
+    /// square root `b` of the discriminant.
+    pub fn hash_to_group(seed: &[u8]) -> ClassElem {
+        let disc = Integer::from(Self::context_discriminant());
+        let mut counter = 0_u64;
+        loop {
+            let mut buf = seed.to_vec();
+            buf.extend_from_slice(&counter.to_le_bytes());
+            counter += 1;
+
+            let p = hash_to_prime(&buf);
+            if p.mod_u(4) != 3 || disc.legendre(&p) != 1 {
+                continue;
+            }
+            // For `p = 3 mod 4`, `disc^((p + 1) / 4)` squares to `disc` mod `p`.
+            // An odd root also works mod `4p`, as `disc = 1 mod 4`.
+            let e = Integer::from(&p + 1) >> 2;
+            let mut b = disc.clone().pow_mod(&e, &p).unwrap();
+            if b.is_even() {
+                b = Integer::from(&p - &b);
+            }
+            let c = (Integer::from(b.square_ref()) - &disc) / Integer::from(&p << 2);
+            return Self::elem((Mpz::from(p), Mpz::from(b), Mpz::from(c)));
+        }
+    }
+
+    pub(crate) fn validate(a: &Mpz, b: &Mpz, c: &Mpz) -> bool {
+        ClassGroup::discriminant(a, b, c) == ClassGroup::context_discriminant()
     }
 
     fn elem_is_normal(scratch: &mut Mpz, a: &Mpz, b: &Mpz, _c: &Mpz) -> bool {
@@ -1143,6 +1265,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_recycle() {
+        let pooled = || CTX.with(|refcell| refcell.borrow().pool.len());
+        let g = ClassGroup::unknown_order_elem();
+        let expected = ClassGroup::op(&g, &g);
+        let start = pooled();
+        ClassGroup::recycle(ClassGroup::op(&g, &g));
+        assert_eq!(pooled(), start + 3);
+        // The reused integers start from zero again.
+        assert_eq!(ClassGroup::op(&g, &g), expected);
+        assert_eq!(pooled(), start);
+    }
+
+    #[test]
+    fn test_with_discriminant() {
+        let pooled = || CTX.with(|refcell| refcell.borrow().pool.len());
+        let g = ClassGroup::unknown_order_elem();
+        ClassGroup::recycle(ClassGroup::op(&g, &g));
+        let start = pooled();
+        assert!(start > 0);
+
+        // Staying on the same discriminant keeps the context and its pool.
+        ClassGroup::with_discriminant(&CLASS_GROUP_DISCRIMINANT, || {
+            assert_eq!(pooled(), start)
+        });
+        let other = Mpz::from_str("-1000039").unwrap();
+        ClassGroup::with_discriminant(&other, || {
+            assert_eq!(ClassGroup::context_discriminant(), other);
+            assert_eq!(pooled(), 0);
+        });
+        assert_eq!(pooled(), start);
+
+        // A panic inside still puts the previous context back.
+        let result = std::panic::catch_unwind(|| {
+            ClassGroup::with_discriminant(&other, || panic!("inside"))
+        });
+        assert!(result.is_err());
+        assert_eq!(ClassGroup::context_discriminant(), *CLASS_GROUP_DISCRIMINANT);
+        assert_eq!(pooled(), start);
+    }
+
     #[test]
     fn test_inv() {
         let id = ClassGroup::id();
@@ -1205,6 +1368,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_repeated_square_with_progress() {
+        let g = ClassGroup::unknown_order_elem();
+        let mut expected = g.clone();
+        ClassGroup::repeated_square(&mut expected, 25);
+
+        let mut seen = Vec::new();
+        let mut x = g.clone();
+        ClassGroup::repeated_square_with_progress(&mut x, 25, 10, |done| seen.push(done));
+        assert_eq!(x, expected);
+        assert_eq!(seen, vec![10, 20, 25]);
+
+        let counter = std::sync::atomic::AtomicU64::new(0);
+        let mut x = g;
+        ClassGroup::repeated_square_with_progress(&mut x, 25, 0, |done| {
+            counter.store(done, std::sync::atomic::Ordering::Relaxed)
+        });
+        assert_eq!(x, expected);
+        assert_eq!(counter.into_inner(), 25);
+    }
+
+    #[test]
+    fn test_hash_to_group() {
+        let h = ClassGroup::hash_to_group(b"seed");
+        assert!(ClassGroup::validate(&h.a, &h.b, &h.c));
+        assert_eq!(h, ClassGroup::hash_to_group(b"seed"));
+        assert_ne!(h, ClassGroup::hash_to_group(b"other seed"));
+        assert_ne!(h, ClassGroup::unknown_order_elem());
+    }
+
     fn split_into_three_pieces(line: &str, c: char) -> [&str; 3] {
         let mut iter = line.split(c);
         let fst = iter.next().expect("bad test file");
@@ -0,0 +1,127 @@
+//! A variable-length encoding that spends no bytes on leading zeros.
+//!
+//! Each of `a` and `b` is a length and then its big-endian magnitude with no
+//! leading zero bytes. The length is a LEB128 varint; for `b` it is shifted
+//! up by one with the sign in the low bit. `c` is recomputed from the
+//! discriminant. Elements of small test discriminants take a few bytes, and
+//! elements of different sizes can share a stream without padding to the
+//! largest.
+
+use super::{ClassElem, DeserializeError};
+use crate::num::Mpz;
+
+fn write_varint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, DeserializeError> {
+    let mut n = 0u64;
+    for (i, &byte) in bytes.iter().enumerate().take(10) {
+        n |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            if byte == 0 && i > 0 {
+                return Err(DeserializeError::NotCanonical);
+            }
+            *bytes = &bytes[i + 1..];
+            return Ok(n);
+        }
+    }
+    Err(DeserializeError::InvalidCoefficients("bad length"))
+}
+
+fn read_magnitude(bytes: &mut &[u8], len: u64) -> Result<Mpz, DeserializeError> {
+    if len > bytes.len() as u64 {
+        return Err(DeserializeError::InvalidCoefficients(
+            "truncated coefficient",
+        ));
+    }
+    let (field, rest) = bytes.split_at(len as usize);
+    if field.first() == Some(&0) {
+        return Err(DeserializeError::NotCanonical);
+    }
+    *bytes = rest;
+    Ok(Mpz::from_bytes(field))
+}
+
+impl ClassElem {
+    /// The compact encoding of `(a, b)`.
+    pub fn to_compact_bytes(&self) -> Vec<u8> {
+        let (a, b) = (self.a.to_bytes(), self.b.to_bytes());
+        let mut buf = Vec::with_capacity(a.len() + b.len() + 4);
+        write_varint(&mut buf, a.len() as u64);
+        buf.extend_from_slice(&a);
+        write_varint(&mut buf, (b.len() as u64) << 1 | self.b.is_neg() as u64);
+        buf.extend_from_slice(&b);
+        buf
+    }
+
+    /// Parses the encoding of `to_compact_bytes` as a reduced element of
+    /// the class group of `discriminant`, rejecting trailing bytes and
+    /// anything but the minimal encoding.
+    pub fn from_compact_bytes(bytes: &[u8], discriminant: &Mpz) -> Result<Self, DeserializeError> {
+        let mut rest = bytes;
+        let len = read_varint(&mut rest)?;
+        let a = read_magnitude(&mut rest, len)?;
+        let len = read_varint(&mut rest)?;
+        let mut b = read_magnitude(&mut rest, len >> 1)?;
+        if len & 1 == 1 {
+            if b.is_zero() {
+                return Err(DeserializeError::NotCanonical);
+            }
+            b.neg_mut();
+        }
+        if !rest.is_empty() {
+            return Err(DeserializeError::WrongLength {
+                expected: bytes.len() - rest.len(),
+                actual: bytes.len(),
+            });
+        }
+        Self::from_ab(a, b, discriminant)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::{ClassGroup, CLASS_GROUP_DISCRIMINANT};
+    use rug::Integer;
+
+    #[test]
+    fn test_compact() {
+        let disc = &*CLASS_GROUP_DISCRIMINANT;
+        let id = ClassGroup::id();
+        assert_eq!(id.to_compact_bytes(), vec![1, 1, 2, 1]);
+        assert_eq!(
+            ClassElem::from_compact_bytes(&[1, 1, 2, 1], disc).unwrap(),
+            id
+        );
+
+        let g = ClassGroup::pow(&ClassGroup::unknown_order_elem(), &Integer::from(31337));
+        for x in &[g.clone(), ClassGroup::inv(&g)] {
+            let bytes = x.to_compact_bytes();
+            assert_eq!(ClassElem::from_compact_bytes(&bytes, disc).unwrap(), *x);
+        }
+
+        // Leading zeros, a negative zero and trailing bytes are rejected.
+        assert_eq!(
+            ClassElem::from_compact_bytes(&[2, 0, 1, 2, 1], disc),
+            Err(DeserializeError::NotCanonical)
+        );
+        assert_eq!(
+            ClassElem::from_compact_bytes(&[1, 1, 1], disc),
+            Err(DeserializeError::NotCanonical)
+        );
+        assert_eq!(
+            ClassElem::from_compact_bytes(&[1, 1, 2, 1, 0], disc),
+            Err(DeserializeError::WrongLength {
+                expected: 4,
+                actual: 5
+            })
+        );
+        assert!(ClassElem::from_compact_bytes(&[1, 1, 4, 1], disc).is_err());
+    }
+}
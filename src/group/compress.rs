@@ -0,0 +1,235 @@
+//! chiavdf's compressed element format (`bqfc`), which fits a form of a
+//! 1024-bit discriminant in 100 bytes instead of 258.
+//!
+//! A reduced `(a, b)` is stored as `a` and a small `t` from a partial
+//! extended gcd of `a` and `|b|`: the remainder `r < sqrt(a)` it stops at
+//! satisfies `r = t * |b| (mod a)`, and since `r^2 < a` also
+//! `r^2 = t^2 * D mod a`, so `r` and then `|b| mod a` come back from `t`
+//! with a square root. When `g = gcd(a, t)` is not 1 this works modulo
+//! `a / g`, and the quotient `b0 = |b| / (a / g)` fills in the rest.
+//!
+//! The layout for a discriminant of `d` bits, `d` rounded up to a multiple
+//! of 32, is a flag byte, one byte holding the length of `g` less one, and
+//! then `a / g`, `|t / g|`, `g` and `b0`, each little-endian in
+//! `d / 16 - len`, `d / 32 - len`, `len + 1` and `len + 1` bytes, for
+//! `3 * d / 32 + 4` bytes in all. The identity and the generator `(2, 1)`
+//! are flags alone.
+
+use super::elem::check_length;
+use super::{ClassElem, DeserializeError};
+use crate::num::Mpz;
+use rug::integer::Order;
+use rug::ops::RemRounding;
+use rug::Integer;
+
+const B_SIGN: u8 = 1 << 0;
+const T_SIGN: u8 = 1 << 1;
+const IS_1: u8 = 1 << 2;
+const IS_GEN: u8 = 1 << 3;
+
+fn round_bits(discriminant_bits: usize) -> usize {
+    (discriminant_bits + 31) & !31
+}
+
+/// Writes the magnitude of `x` little-endian into exactly `size` bytes.
+fn export(buf: &mut Vec<u8>, size: usize, x: &Integer) {
+    let digits = x.to_digits::<u8>(Order::Lsf);
+    assert!(digits.len() <= size, "field does not fit the encoding");
+    buf.extend_from_slice(&digits);
+    buf.resize(buf.len() + size - digits.len(), 0);
+}
+
+fn import(bytes: &mut &[u8], size: usize) -> Integer {
+    let (field, rest) = bytes.split_at(size);
+    *bytes = rest;
+    Integer::from_digits(field, Order::Lsf)
+}
+
+/// Runs Euclid's algorithm on `(a, b)` until the remainder is at most
+/// `bound`, and returns it with `t` such that `r = t * b (mod a)`.
+fn xgcd_partial(a: &Integer, b: &Integer, bound: &Integer) -> (Integer, Integer) {
+    let (mut r2, mut r1) = (a.clone(), b.clone());
+    let (mut t2, mut t1) = (Integer::new(), Integer::from(1));
+    while r1 != 0 && r1 > *bound {
+        let (q, r) = r2.div_rem_floor(r1.clone());
+        r2 = std::mem::replace(&mut r1, r);
+        let t = t2 - &q * &t1;
+        t2 = std::mem::replace(&mut t1, t);
+    }
+    (r1, t1)
+}
+
+impl ClassElem {
+    /// The length of the compressed encoding for a discriminant of
+    /// `discriminant_bits` bits: 100 bytes for 1024 bits.
+    pub fn compressed_size(discriminant_bits: usize) -> usize {
+        round_bits(discriminant_bits) / 32 * 3 + 4
+    }
+
+    /// Compresses a reduced element of a discriminant of `discriminant_bits`
+    /// bits.
+    pub fn compress(&self, discriminant_bits: usize) -> Vec<u8> {
+        let d = round_bits(discriminant_bits);
+        let (a, b) = (Integer::from(&self.a), Integer::from(&self.b));
+        let mut buf = Vec::with_capacity(Self::compressed_size(discriminant_bits));
+        if b == 1 && a <= 2 {
+            buf.push(if a == 2 { IS_GEN } else { IS_1 });
+            buf.resize(Self::compressed_size(discriminant_bits), 0);
+            return buf;
+        }
+
+        let (a_, t, g, b0, mut flags) = if a == b {
+            (a, Integer::new(), Integer::new(), Integer::new(), 0)
+        } else {
+            let flags = if b < 0 { B_SIGN } else { 0 };
+            let b = b.abs();
+            let (_, t) = xgcd_partial(&a, &b, &Integer::from(a.sqrt_ref()));
+            let g = Integer::from(a.gcd_ref(&t));
+            let a_ = Integer::from(a.div_exact_ref(&g));
+            let t = Integer::from(t.div_exact_ref(&g));
+            let b0 = Integer::from(&b / &a_);
+            (a_, t, g, b0, flags)
+        };
+        if t < 0 {
+            flags |= T_SIGN;
+        }
+        let g_size = (g.significant_bits() as usize).div_ceil(8).max(1) - 1;
+        buf.push(flags);
+        buf.push(g_size as u8);
+        export(&mut buf, d / 16 - g_size, &a_);
+        export(&mut buf, d / 32 - g_size, &t);
+        export(&mut buf, g_size + 1, &g);
+        export(&mut buf, g_size + 1, &b0);
+        buf
+    }
+
+    /// Writes the encoding of `compress` into `out`, which must be exactly
+    /// `compressed_size(discriminant_bits)` bytes.
+    pub fn compress_into(&self, discriminant_bits: usize, out: &mut [u8]) {
+        assert_eq!(
+            out.len(),
+            Self::compressed_size(discriminant_bits),
+            "buffer is not the size of the encoding"
+        );
+        out.copy_from_slice(&self.compress(discriminant_bits));
+    }
+
+    /// Parses the encoding of `compress` as a reduced element of the class
+    /// group of `discriminant`. Only the canonical encoding is accepted.
+    pub fn decompress(bytes: &[u8], discriminant: &Mpz) -> Result<Self, DeserializeError> {
+        let invalid = |why| Err(DeserializeError::InvalidCoefficients(why));
+        let bits = discriminant.bit_length();
+        let d = round_bits(bits);
+        check_length(bytes, Self::compressed_size(bits))?;
+        let flags = bytes[0];
+        if flags >= 1 << 4 {
+            return invalid("unknown flags");
+        }
+        let (a, b) = if flags & (IS_1 | IS_GEN) != 0 {
+            let a = if flags & IS_GEN != 0 { 2 } else { 1 };
+            (Integer::from(a), Integer::from(1))
+        } else {
+            let g_size = bytes[1] as usize;
+            if g_size >= d / 32 {
+                return invalid("g is too long");
+            }
+            let mut rest = &bytes[2..];
+            let a_ = import(&mut rest, d / 16 - g_size);
+            let mut t = import(&mut rest, d / 32 - g_size);
+            if flags & T_SIGN != 0 {
+                t = -t;
+            }
+            let g = import(&mut rest, g_size + 1);
+            let b0 = import(&mut rest, g_size + 1);
+
+            if t == 0 {
+                (a_.clone(), a_)
+            } else {
+                if a_ <= 0 || g <= 0 {
+                    return invalid("a and g must be positive");
+                }
+                let t_inv = match t.clone().invert(&a_) {
+                    Ok(t_inv) => t_inv,
+                    Err(_) => return invalid("t is not invertible"),
+                };
+                let sq = Integer::from(t.square_ref()) * Integer::from(discriminant);
+                let (r, rem) = sq.rem_euc(&a_).sqrt_rem(Integer::new());
+                if rem != 0 {
+                    return invalid("no square root for t");
+                }
+                let b = (r * t_inv).rem_euc(&a_) + &b0 * &a_;
+                let b = if flags & B_SIGN != 0 { -b } else { b };
+                (a_ * g, b)
+            }
+        };
+
+        let elem = Self::from_ab(Mpz::from(a), Mpz::from(b), discriminant)?;
+        if elem.compress(bits) != bytes {
+            return Err(DeserializeError::NotCanonical);
+        }
+        Ok(elem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::{ClassGroup, CLASS_GROUP_DISCRIMINANT};
+
+    #[test]
+    fn test_compress() {
+        assert_eq!(ClassElem::compressed_size(1024), 100);
+
+        // chia-blockchain's default VDF input,
+        // `ClassgroupElement.get_default_element()`, is the generator (2, 1)
+        // of a 1024-bit discriminant as chiavdf compresses it: 0x08 and then
+        // 99 zero bytes. Any 1024-bit discriminant that is 1 mod 8 has it.
+        let chia_disc = Mpz::from(-(Integer::from(1) << 1023u32) - 7);
+        let mut chia_default = vec![0u8; 100];
+        chia_default[0] = 0x08;
+        let gen = ClassElem::decompress(&chia_default, &chia_disc).unwrap();
+        assert_eq!((gen.a.clone(), gen.b.clone()), (Mpz::from(2), Mpz::from(1)));
+        assert_eq!(gen.compress(chia_disc.bit_length()), chia_default);
+
+        let disc = &*CLASS_GROUP_DISCRIMINANT;
+        let bits = disc.bit_length();
+
+        let mut expected = vec![0u8; ClassElem::compressed_size(bits)];
+        expected[0] = IS_1;
+        assert_eq!(ClassGroup::id().compress(bits), expected);
+        assert_eq!(
+            ClassElem::decompress(&expected, disc).unwrap(),
+            ClassGroup::id()
+        );
+
+        let g = ClassGroup::unknown_order_elem();
+        let mut x = g.clone();
+        for _ in 0..50 {
+            for y in &[x.clone(), ClassGroup::inv(&x)] {
+                let bytes = y.compress(bits);
+                assert_eq!(bytes.len(), 196);
+                assert_eq!(ClassElem::decompress(&bytes, disc).unwrap(), *y);
+            }
+            x = ClassGroup::op(&x, &g);
+        }
+
+        let mut bytes = x.compress(bits);
+        assert!(ClassElem::decompress(&bytes[1..], disc).is_err());
+        bytes[0] ^= B_SIGN;
+        assert_eq!(
+            ClassElem::decompress(&bytes, disc).unwrap(),
+            ClassGroup::inv(&x)
+        );
+        bytes[0] = 0x10;
+        assert_eq!(
+            ClassElem::decompress(&bytes, disc),
+            Err(DeserializeError::InvalidCoefficients("unknown flags"))
+        );
+        // Trailing garbage after the flags of the identity is not canonical.
+        expected[10] = 1;
+        assert_eq!(
+            ClassElem::decompress(&expected, disc),
+            Err(DeserializeError::NotCanonical)
+        );
+    }
+}
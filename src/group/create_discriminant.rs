@@ -62,11 +62,7 @@ pub fn create_discriminant(seed: &[u8], length: u64) -> Mpz {
     //println!("random_bytes_len: {:?}", n.bit_length());
 
     // n -= n.clone() % M;
-    //let rem = n.clone() % Mpz::from(M as u64);
-    let mut rem = Mpz::zero();
-    rem.modulo(&n, &Mpz::from(M as u64));
-    //n = n - rem;
-    n.sub_mut(&rem);
+    n.sub_ui_mut(n.frem_u64(u64::from(M)));
     //println!("n plus: {:?}", RESIDUES[numerator % RESIDUES.len()]);
     let residue = RESIDUES[numerator % RESIDUES.len()];
     let residue = Mpz::from(residue as u64);
@@ -75,11 +71,13 @@ pub fn create_discriminant(seed: &[u8], length: u64) -> Mpz {
 
     debug_assert!(n >= Mpz::zero());
 
+    // Speed up prime-finding by quickly ruling out numbers
+    // that are known to be composite. One sieve serves every window.
+    let mut sieve = ::bit_vec::BitVec::from_elem(1 << 16, false);
+
     // This generates the smallest prime ≥ n that is of the form n + m*x.
     loop {
-        // Speed up prime-finding by quickly ruling out numbers
-        // that are known to be composite.
-        let mut sieve = ::bit_vec::BitVec::from_elem(1 << 16, false);
+        sieve.clear();
 
         //Optimize for gains
         for &(p, q) in SIEVE_INFO.iter() {
@@ -1,5 +1,7 @@
 //! Defines the ClassElem struct and associated traits.
 use crate::num::Mpz;
+use bacteria::Transcript;
+use std::fmt;
 use std::hash::{Hash, Hasher};
 
 #[allow(clippy::stutter)]
@@ -10,13 +12,177 @@ pub struct ClassElem {
     pub c: Mpz,
 }
 
+/// Why bytes from an untrusted source are not an element.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeserializeError {
+    /// The input is not the length the encoding has for the discriminant.
+    WrongLength { expected: usize, actual: usize },
+    /// A coefficient or field is out of range, or cannot come from any
+    /// element.
+    InvalidCoefficients(&'static str),
+    /// `(a, b)` is not a form of the discriminant: `4a` does not divide
+    /// `b^2 - D`.
+    DiscriminantMismatch,
+    /// The form is valid but not reduced.
+    NotReduced,
+    /// The element is valid but encoded other than as the encoder would.
+    NotCanonical,
+    /// An envelope of a version this build does not know.
+    UnsupportedVersion(u8),
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeserializeError::WrongLength { expected, actual } => {
+                write!(f, "expected {} bytes, got {}", expected, actual)
+            }
+            DeserializeError::InvalidCoefficients(why) => write!(f, "invalid element: {}", why),
+            DeserializeError::DiscriminantMismatch => {
+                f.write_str("not an element of this discriminant")
+            }
+            DeserializeError::NotReduced => f.write_str("element is not reduced"),
+            DeserializeError::NotCanonical => f.write_str("not the canonical encoding"),
+            DeserializeError::UnsupportedVersion(v) => write!(f, "unsupported version {}", v),
+        }
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+/// Fails with `WrongLength` unless `bytes` has `expected` bytes.
+pub(crate) fn check_length(bytes: &[u8], expected: usize) -> Result<(), DeserializeError> {
+    if bytes.len() == expected {
+        Ok(())
+    } else {
+        Err(DeserializeError::WrongLength {
+            expected,
+            actual: bytes.len(),
+        })
+    }
+}
+
+/// Writes `x` into `out` in big-endian two's complement, sign-extended to the
+/// whole of `out`.
+fn write_signed(out: &mut [u8], x: &Mpz) {
+    let magnitude = x.to_bytes();
+    assert!(
+        magnitude.len() < out.len() || (magnitude.len() == out.len() && magnitude[0] < 0x80),
+        "coefficient does not fit the encoding"
+    );
+    let offset = out.len() - magnitude.len();
+    out[offset..].copy_from_slice(&magnitude);
+    if x.is_neg() {
+        negate(out);
+    }
+}
+
+/// Negates a big-endian two's complement number in place.
+fn negate(bytes: &mut [u8]) {
+    let mut carry = true;
+    for byte in bytes.iter_mut().rev() {
+        let (v, c) = (!*byte).overflowing_add(carry as u8);
+        *byte = v;
+        carry = c;
+    }
+}
+
+fn read_signed(bytes: &[u8]) -> Mpz {
+    match bytes.first() {
+        Some(b) if b & 0x80 != 0 => {}
+        _ => return Mpz::from_bytes(bytes),
+    }
+    let mut magnitude = bytes.to_vec();
+    negate(&mut magnitude);
+    let mut x = Mpz::from_bytes(&magnitude);
+    x.neg_mut();
+    x
+}
+
+/// Chia's encoding: `a` and then `b`, each in big-endian two's complement
+/// over `(discriminant_bits + 16) / 16` bytes. `c` is recomputed from the
+/// discriminant when reading. The tests pin this layout but have no
+/// encodings produced by chiavdf to compare against.
 impl ClassElem {
-    fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = Vec::new();
-        // buf.extend_from_slice();
-        // buf.extend_from_slice();
+    /// The length of the encoding for a discriminant of `discriminant_bits`
+    /// bits.
+    pub fn serialized_size(discriminant_bits: usize) -> usize {
+        2 * ((discriminant_bits + 16) >> 4)
+    }
+
+    /// Encodes the element for a discriminant of `discriminant_bits` bits.
+    ///
+    /// Panics if a coefficient does not fit, which cannot happen for reduced
+    /// elements of such a discriminant.
+    pub fn to_bytes(&self, discriminant_bits: usize) -> Vec<u8> {
+        let mut buf = vec![0u8; Self::serialized_size(discriminant_bits)];
+        self.to_bytes_into(discriminant_bits, &mut buf);
         buf
     }
+
+    /// Writes the encoding of `to_bytes` into `out`, which must be exactly
+    /// `serialized_size(discriminant_bits)` bytes.
+    pub fn to_bytes_into(&self, discriminant_bits: usize, out: &mut [u8]) {
+        let size = Self::serialized_size(discriminant_bits);
+        assert_eq!(out.len(), size, "buffer is not the size of the encoding");
+        out.fill(0);
+        let (a, b) = out.split_at_mut(size / 2);
+        write_signed(a, &self.a);
+        write_signed(b, &self.b);
+    }
+
+    /// Parses the encoding of `to_bytes`, accepting only reduced elements of
+    /// the class group of `discriminant` in exactly
+    /// `serialized_size(discriminant.bit_length())` bytes.
+    pub fn from_bytes(bytes: &[u8], discriminant: &Mpz) -> Result<Self, DeserializeError> {
+        check_length(bytes, Self::serialized_size(discriminant.bit_length()))?;
+        let (a, b) = bytes.split_at(bytes.len() / 2);
+        Self::from_ab(read_signed(a), read_signed(b), discriminant)
+    }
+
+    /// The reduced element `(a, b, .)` of the class group of `discriminant`.
+    pub(crate) fn from_ab(a: Mpz, b: Mpz, discriminant: &Mpz) -> Result<Self, DeserializeError> {
+        if a.sgn() <= 0 {
+            return Err(DeserializeError::InvalidCoefficients("a must be positive"));
+        }
+
+        // `c = (b^2 - D) / 4a`, which must be exact.
+        let (mut num, mut four_a) = (Mpz::default(), Mpz::default());
+        num.mul(&b, &b);
+        num.sub_mut(discriminant);
+        four_a.mul_ui(&a, 4);
+        let (mut c, mut rem) = (Mpz::default(), Mpz::default());
+        c.fdiv_qr(&mut rem, &num, &four_a);
+        if !rem.is_zero() {
+            return Err(DeserializeError::DiscriminantMismatch);
+        }
+
+        let elem = ClassElem { a, b, c };
+        if !elem.is_reduced() {
+            return Err(DeserializeError::NotReduced);
+        }
+        Ok(elem)
+    }
+
+    /// `-a < b <= a <= c`, with `b >= 0` when `a = c`.
+    pub(crate) fn is_reduced(&self) -> bool {
+        let mut neg_a = self.a.clone();
+        neg_a.neg_mut();
+        neg_a < self.b
+            && self.b <= self.a
+            && self.a <= self.c
+            && (self.a != self.c || !self.b.is_neg())
+    }
+}
+
+impl ClassElem {
+    /// Commits the element to a Fiat-Shamir transcript. `c` is left out as it
+    /// is implied by `a`, `b` and the discriminant.
+    pub(crate) fn append_to_transcript(&self, transcript: &mut Transcript, label: &'static [u8]) {
+        transcript.append_message(label, &self.a.to_bytes());
+        transcript.append_message(label, &self.b.to_bytes());
+        transcript.append_u64(label, self.b.is_neg() as u64);
+    }
 }
 
 impl Default for ClassElem {
@@ -60,3 +226,69 @@ impl Hash for ClassElem {
 impl Eq for ClassElem {}
 unsafe impl Send for ClassElem {}
 unsafe impl Sync for ClassElem {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::{ClassGroup, CLASS_GROUP_DISCRIMINANT};
+    use rug::Integer;
+
+    #[test]
+    fn test_chia_encoding() {
+        let disc = &*CLASS_GROUP_DISCRIMINANT;
+        let bits = disc.bit_length();
+        assert_eq!(bits, 2048);
+        assert_eq!(ClassElem::serialized_size(bits), 258);
+
+        // The identity (1, 1) is two 129-byte ones.
+        let mut expected = vec![0u8; 258];
+        expected[128] = 1;
+        expected[257] = 1;
+        let id = ClassGroup::id();
+        assert_eq!(id.to_bytes(bits), expected);
+        assert_eq!(ClassElem::from_bytes(&expected, disc).unwrap(), id);
+
+        // A negative `b` is sign-extended with 0xff.
+        let g = ClassGroup::pow(&ClassGroup::unknown_order_elem(), &Integer::from(12345));
+        let inv = ClassGroup::inv(&g);
+        assert!(inv.b.is_neg());
+        let bytes = inv.to_bytes(bits);
+        assert_eq!(bytes[129], 0xff);
+        assert_eq!(ClassElem::from_bytes(&bytes, disc).unwrap(), inv);
+        assert_eq!(ClassElem::from_bytes(&g.to_bytes(bits), disc).unwrap(), g);
+
+        // Writing into a dirty buffer of the right size gives the same bytes.
+        let mut out = vec![0xaa; ClassGroup::element_size_bytes()];
+        inv.to_bytes_into(bits, &mut out);
+        assert_eq!(out, bytes);
+
+        // Strictness: length, reduction and discriminant are all checked.
+        assert_eq!(
+            ClassElem::from_bytes(&bytes[1..], disc),
+            Err(DeserializeError::WrongLength {
+                expected: 258,
+                actual: 257
+            })
+        );
+        let mut unreduced = g.clone();
+        let mut two_a = Mpz::default();
+        two_a.mul_ui(&g.a, 2);
+        unreduced.b.add_mut(&two_a);
+        assert_eq!(
+            ClassElem::from_bytes(&unreduced.to_bytes(bits), disc),
+            Err(DeserializeError::NotReduced)
+        );
+        let mut other = disc.clone();
+        other.sub_ui_mut(8);
+        assert_eq!(
+            ClassElem::from_bytes(&g.to_bytes(bits), &other),
+            Err(DeserializeError::DiscriminantMismatch)
+        );
+        let mut zero = vec![0u8; 258];
+        zero[257] = 1;
+        assert_eq!(
+            ClassElem::from_bytes(&zero, disc),
+            Err(DeserializeError::InvalidCoefficients("a must be positive"))
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! A self-describing wrapper for serialized elements and proofs.
+//!
+//! An envelope is a version byte, an 8-byte fingerprint of the discriminant
+//! and the payload: any encoding, such as `ClassElem::to_bytes` or a proof's
+//! `to_bytes`. `open` checks the header against the reader's group before
+//! the payload is parsed, so data from another group is turned away without
+//! any arithmetic on it.
+//!
+//! A batch is one envelope around a big-endian `u32` count and that many
+//! elements in the encoding of `ClassElem::to_bytes`, back to back, so a
+//! list of witnesses pays for the header once.
+
+use super::elem::check_length;
+use super::{ClassElem, ClassElemRef, ClassGroup, DeserializeError};
+use crate::num::Mpz;
+use bacteria::Transcript;
+
+/// The only envelope version so far.
+pub const VERSION: u8 = 1;
+
+/// Bytes of discriminant fingerprint in the header.
+pub const FINGERPRINT_LEN: usize = 8;
+
+const HEADER_LEN: usize = 1 + FINGERPRINT_LEN;
+
+/// A short hash identifying the group of `discriminant`.
+pub fn fingerprint(discriminant: &Mpz) -> [u8; FINGERPRINT_LEN] {
+    let mut transcript = Transcript::new(b"Classygroup.envelope");
+    transcript.append_message(b"discriminant", &discriminant.to_bytes());
+    transcript.append_u64(b"sign", discriminant.is_neg() as u64);
+    let mut out = [0u8; FINGERPRINT_LEN];
+    transcript.challenge_bytes(b"fingerprint", &mut out);
+    out
+}
+
+/// Wraps `payload` for the group of `discriminant`.
+pub fn seal(discriminant: &Mpz, payload: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(HEADER_LEN + payload.len());
+    buf.push(VERSION);
+    buf.extend_from_slice(&fingerprint(discriminant));
+    buf.extend_from_slice(payload);
+    buf
+}
+
+/// The payload of an envelope from `seal`. Fails unless the envelope is of
+/// this version and for the group of `discriminant`.
+pub fn open<'a>(discriminant: &Mpz, bytes: &'a [u8]) -> Result<&'a [u8], DeserializeError> {
+    if bytes.len() < HEADER_LEN {
+        return Err(DeserializeError::WrongLength {
+            expected: HEADER_LEN,
+            actual: bytes.len(),
+        });
+    }
+    if bytes[0] != VERSION {
+        return Err(DeserializeError::UnsupportedVersion(bytes[0]));
+    }
+    if bytes[1..HEADER_LEN] != fingerprint(discriminant)[..] {
+        return Err(DeserializeError::DiscriminantMismatch);
+    }
+    Ok(&bytes[HEADER_LEN..])
+}
+
+impl ClassElem {
+    /// The `to_bytes` encoding in an envelope for the current group.
+    pub fn to_envelope(&self) -> Vec<u8> {
+        let disc = ClassGroup::context_discriminant();
+        seal(&disc, &self.to_bytes(disc.bit_length()))
+    }
+
+    /// Parses the encoding of `to_envelope` as an element of the current
+    /// group.
+    pub fn from_envelope(bytes: &[u8]) -> Result<Self, DeserializeError> {
+        let disc = ClassGroup::context_discriminant();
+        Self::from_bytes(open(&disc, bytes)?, &disc)
+    }
+}
+
+/// Encodes `elems`, all of the current group, as a batch.
+pub fn serialize_batch(elems: &[ClassElem]) -> Vec<u8> {
+    let disc = ClassGroup::context_discriminant();
+    let bits = disc.bit_length();
+    let mut payload = Vec::with_capacity(4 + elems.len() * ClassElem::serialized_size(bits));
+    payload.extend_from_slice(&(elems.len() as u32).to_be_bytes());
+    for x in elems {
+        payload.extend_from_slice(&x.to_bytes(bits));
+    }
+    seal(&disc, &payload)
+}
+
+/// Parses the encoding of `serialize_batch` as elements of the current
+/// group. The count must match the number of elements that follow it.
+pub fn deserialize_batch(bytes: &[u8]) -> Result<Vec<ClassElem>, DeserializeError> {
+    let disc = ClassGroup::context_discriminant();
+    let payload = open(&disc, bytes)?;
+    if payload.len() < 4 {
+        return Err(DeserializeError::WrongLength {
+            expected: 4,
+            actual: payload.len(),
+        });
+    }
+    let (count, elems) = payload.split_at(4);
+    let count = u32::from_be_bytes([count[0], count[1], count[2], count[3]]) as usize;
+    let size = ClassElem::serialized_size(disc.bit_length());
+    check_length(elems, count.saturating_mul(size))?;
+    ClassElemRef::split(elems, &disc)?
+        .iter()
+        .map(|x| x.decode(&disc))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::CLASS_GROUP_DISCRIMINANT;
+    use rug::Integer;
+
+    #[test]
+    fn test_envelope() {
+        let g = ClassGroup::pow(&ClassGroup::unknown_order_elem(), &Integer::from(77));
+        let bytes = g.to_envelope();
+        assert_eq!(bytes[0], VERSION);
+        assert_eq!(ClassElem::from_envelope(&bytes).unwrap(), g);
+
+        let mut other = CLASS_GROUP_DISCRIMINANT.clone();
+        other.sub_ui_mut(8);
+        assert_ne!(fingerprint(&other), fingerprint(&CLASS_GROUP_DISCRIMINANT));
+        assert_eq!(
+            open(&other, &bytes),
+            Err(DeserializeError::DiscriminantMismatch)
+        );
+        let mut future = bytes.clone();
+        future[0] = VERSION + 1;
+        assert_eq!(
+            ClassElem::from_envelope(&future),
+            Err(DeserializeError::UnsupportedVersion(VERSION + 1))
+        );
+        assert!(ClassElem::from_envelope(&bytes[..4]).is_err());
+        assert_eq!(open(&other, &seal(&other, b"proof")).unwrap(), b"proof");
+    }
+
+    #[test]
+    fn test_batch() {
+        let g = ClassGroup::unknown_order_elem();
+        let elems: Vec<_> = (1..5u32)
+            .map(|i| ClassGroup::pow(&g, &Integer::from(i)))
+            .collect();
+        let bytes = serialize_batch(&elems);
+        let size = ClassElem::serialized_size(CLASS_GROUP_DISCRIMINANT.bit_length());
+        assert_eq!(bytes.len(), HEADER_LEN + 4 + 4 * size);
+        assert_eq!(deserialize_batch(&bytes).unwrap(), elems);
+        assert_eq!(deserialize_batch(&serialize_batch(&[])).unwrap(), vec![]);
+
+        // Dropping a whole element is caught by the count.
+        assert_eq!(
+            deserialize_batch(&bytes[..bytes.len() - size]),
+            Err(DeserializeError::WrongLength {
+                expected: 4 * size,
+                actual: 3 * size
+            })
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Tagged element encodings, so that stored elements say how they were
+//! written and the encoding can change without misreading old data.
+//!
+//! A `V1` encoding is a format byte and then the bytes of `to_bytes` or
+//! `compress`. `ChiavdfCompat` is chiavdf's compressed form exactly as
+//! chiavdf writes it, with no format byte, for talking to Chia nodes; it
+//! can only be read when the other side is known to send it.
+
+use super::{ClassElem, DeserializeError};
+use crate::num::Mpz;
+
+/// An element encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// `ClassElem::to_bytes` behind format byte 1.
+    V1Uncompressed,
+    /// `ClassElem::compress` behind format byte 2.
+    V1Compressed,
+    /// `ClassElem::compress` and nothing else.
+    ChiavdfCompat,
+}
+
+impl Format {
+    /// Every format this build reads and writes, preferred first.
+    pub const SUPPORTED: [Format; 3] = [
+        Format::V1Compressed,
+        Format::V1Uncompressed,
+        Format::ChiavdfCompat,
+    ];
+
+    /// The format byte that starts the encoding, if there is one.
+    pub fn version(self) -> Option<u8> {
+        match self {
+            Format::V1Uncompressed => Some(1),
+            Format::V1Compressed => Some(2),
+            Format::ChiavdfCompat => None,
+        }
+    }
+
+    /// The format that starts with format byte `version`.
+    pub fn from_version(version: u8) -> Result<Self, DeserializeError> {
+        match version {
+            1 => Ok(Format::V1Uncompressed),
+            2 => Ok(Format::V1Compressed),
+            v => Err(DeserializeError::UnsupportedVersion(v)),
+        }
+    }
+
+    /// The first of `ours`, in our order of preference, that the peer also
+    /// lists in `theirs`.
+    pub fn negotiate(ours: &[Format], theirs: &[Format]) -> Option<Format> {
+        ours.iter().find(|f| theirs.contains(f)).copied()
+    }
+
+    /// The length of an element's encoding for a discriminant of
+    /// `discriminant_bits` bits.
+    pub fn size(self, discriminant_bits: usize) -> usize {
+        let tag = self.version().is_some() as usize;
+        match self {
+            Format::V1Uncompressed => tag + ClassElem::serialized_size(discriminant_bits),
+            _ => tag + ClassElem::compressed_size(discriminant_bits),
+        }
+    }
+}
+
+impl ClassElem {
+    /// Encodes the element in `format` for a discriminant of
+    /// `discriminant_bits` bits.
+    pub fn encode(&self, format: Format, discriminant_bits: usize) -> Vec<u8> {
+        let mut buf = vec![0u8; format.size(discriminant_bits)];
+        self.encode_into(format, discriminant_bits, &mut buf);
+        buf
+    }
+
+    /// Writes the encoding of `encode` into `out`, which must be exactly
+    /// `format.size(discriminant_bits)` bytes.
+    pub fn encode_into(&self, format: Format, discriminant_bits: usize, out: &mut [u8]) {
+        assert_eq!(
+            out.len(),
+            format.size(discriminant_bits),
+            "buffer is not the size of the encoding"
+        );
+        let payload = match format.version() {
+            Some(v) => {
+                out[0] = v;
+                &mut out[1..]
+            }
+            None => out,
+        };
+        match format {
+            Format::V1Uncompressed => self.to_bytes_into(discriminant_bits, payload),
+            _ => self.compress_into(discriminant_bits, payload),
+        }
+    }
+
+    /// Parses the encoding of `encode` in a known `format`.
+    pub fn decode(
+        bytes: &[u8],
+        format: Format,
+        discriminant: &Mpz,
+    ) -> Result<Self, DeserializeError> {
+        let payload = match format.version() {
+            None => bytes,
+            Some(v) => match bytes.split_first() {
+                Some((&tag, rest)) if tag == v => rest,
+                Some((&tag, _)) => return Err(DeserializeError::UnsupportedVersion(tag)),
+                None => {
+                    return Err(DeserializeError::WrongLength {
+                        expected: format.size(discriminant.bit_length()),
+                        actual: 0,
+                    })
+                }
+            },
+        };
+        match format {
+            Format::V1Uncompressed => Self::from_bytes(payload, discriminant),
+            _ => Self::decompress(payload, discriminant),
+        }
+    }
+
+    /// Parses an encoding of `encode` in any `V1` format, returning the
+    /// format its first byte names along with the element.
+    pub fn decode_tagged(
+        bytes: &[u8],
+        discriminant: &Mpz,
+    ) -> Result<(Self, Format), DeserializeError> {
+        let format = match bytes.first() {
+            Some(&v) => Format::from_version(v)?,
+            None => return Err(DeserializeError::InvalidCoefficients("missing format byte")),
+        };
+        Ok((Self::decode(bytes, format, discriminant)?, format))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::{ClassGroup, CLASS_GROUP_DISCRIMINANT};
+    use rug::Integer;
+
+    #[test]
+    fn test_formats() {
+        let disc = &*CLASS_GROUP_DISCRIMINANT;
+        let bits = disc.bit_length();
+        let g = ClassGroup::pow(&ClassGroup::unknown_order_elem(), &Integer::from(9));
+        for &format in &Format::SUPPORTED {
+            let bytes = g.encode(format, bits);
+            assert_eq!(bytes.len(), format.size(bits));
+            let mut out = vec![0u8; format.size(bits)];
+            g.encode_into(format, bits, &mut out);
+            assert_eq!(out, bytes);
+            assert_eq!(ClassElem::decode(&bytes, format, disc).unwrap(), g);
+            if format.version().is_some() {
+                assert_eq!(
+                    ClassElem::decode_tagged(&bytes, disc).unwrap(),
+                    (g.clone(), format)
+                );
+            }
+        }
+        assert_eq!(g.encode(Format::ChiavdfCompat, bits), g.compress(bits));
+
+        let mut bytes = g.encode(Format::V1Compressed, bits);
+        assert_eq!(
+            ClassElem::decode(&bytes, Format::V1Uncompressed, disc),
+            Err(DeserializeError::UnsupportedVersion(2))
+        );
+        bytes[0] = 3;
+        assert_eq!(
+            ClassElem::decode_tagged(&bytes, disc),
+            Err(DeserializeError::UnsupportedVersion(3))
+        );
+
+        assert_eq!(
+            Format::negotiate(
+                &Format::SUPPORTED,
+                &[Format::ChiavdfCompat, Format::V1Uncompressed]
+            ),
+            Some(Format::V1Uncompressed)
+        );
+        assert_eq!(Format::negotiate(&[Format::V1Compressed], &[]), None);
+    }
+}
@@ -3,28 +3,171 @@
 //! structures, or standalone if you have a custom application.
 //!
 
+use crate::num::Mpz;
+use bacteria::Transcript;
 use rug::Integer;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::marker::Sized;
 
 mod elem;
-pub use elem::ClassElem;
+pub use elem::{ClassElem, DeserializeError};
+
+mod compact;
+mod compress;
+pub mod envelope;
+mod format;
+pub use format::Format;
+mod view;
+pub use view::ClassElemRef;
 
 mod class_ctx;
 use class_ctx::ClassCtx;
 
 mod lin_congruence_ctx;
 
+#[cfg(feature = "prover")]
 mod create_discriminant;
+#[cfg(feature = "prover")]
 pub use create_discriminant::create_discriminant;
 
+#[cfg(feature = "prover")]
+mod seeded;
+#[cfg(feature = "prover")]
+pub use seeded::SeededDiscriminant;
+
 mod discriminant;
 pub use discriminant::CLASS_GROUP_DISCRIMINANT;
 
 mod classy;
 pub use classy::ClassGroup;
 
+pub mod setup;
+
+#[cfg(feature = "rsa")]
+mod rsa;
+#[cfg(feature = "rsa")]
+pub use rsa::{Rsa2048, RsaElem};
+
+/// A group whose operations are associated functions, as on `ClassGroup`.
+/// Group parameters such as the discriminant live outside the elements.
+pub trait Group: Clone + Debug + Eq + Hash + Send + Sync + Sized {
+    type Elem: Clone + Debug + Eq + Hash + Send + Sync + Sized;
+    /// The parameters of the calling thread's group, e.g. the class group's
+    /// discriminant.
+    type Params: Clone + Send + Sync;
+
+    fn params() -> Self::Params;
+
+    /// Runs `f` in the group of `params`. Work handed to other threads runs
+    /// under this to stay in the caller's group.
+    fn with_params<T, F: FnOnce() -> T>(params: &Self::Params, f: F) -> T;
+
+    fn id() -> Self::Elem;
+
+    fn op(a: &Self::Elem, b: &Self::Elem) -> Self::Elem;
+
+    fn inv(a: &Self::Elem) -> Self::Elem;
+
+    /// Squares `a` in place.
+    fn square(a: &mut Self::Elem) {
+        *a = Self::op(a, a);
+    }
+
+    /// Computes `a^n`, inverting `a` for negative `n`.
+    fn pow(a: &Self::Elem, n: &Integer) -> Self::Elem {
+        let (mut base, mut n) = if *n < 0 {
+            (Self::inv(a), Integer::from(-n))
+        } else {
+            (a.clone(), n.clone())
+        };
+        let mut val = Self::id();
+        while n != 0 {
+            if n.is_odd() {
+                val = Self::op(&val, &base);
+            }
+            Self::square(&mut base);
+            n >>= 1;
+        }
+        val
+    }
+}
+
+/// A group with an element of unknown order, suitable for VDFs and
+/// accumulators.
+pub trait UnknownOrderGroup: Group {
+    fn unknown_order_elem() -> Self::Elem;
+
+    /// Binds the group's parameters into a Fiat-Shamir transcript.
+    fn append_params(transcript: &mut Transcript);
+
+    /// Binds an element into a Fiat-Shamir transcript.
+    fn append_elem(transcript: &mut Transcript, label: &'static [u8], a: &Self::Elem);
+
+    /// Whether an element received from another party is in the group.
+    fn is_elem(a: &Self::Elem) -> bool;
+}
+
+impl Group for ClassGroup {
+    type Elem = ClassElem;
+    type Params = Mpz;
+
+    fn params() -> Mpz {
+        ClassGroup::context_discriminant()
+    }
+
+    fn with_params<T, F: FnOnce() -> T>(disc: &Mpz, f: F) -> T {
+        if ClassGroup::context_discriminant() == *disc {
+            f()
+        } else {
+            ClassGroup::with_discriminant(disc, f)
+        }
+    }
+
+    fn id() -> ClassElem {
+        ClassGroup::id()
+    }
+
+    fn op(a: &ClassElem, b: &ClassElem) -> ClassElem {
+        ClassGroup::op(a, b)
+    }
+
+    fn inv(a: &ClassElem) -> ClassElem {
+        ClassGroup::inv(a)
+    }
+
+    fn square(a: &mut ClassElem) {
+        ClassGroup::square(a)
+    }
+
+    fn pow(a: &ClassElem, n: &Integer) -> ClassElem {
+        ClassGroup::pow(a, n)
+    }
+}
+
+impl UnknownOrderGroup for ClassGroup {
+    fn unknown_order_elem() -> ClassElem {
+        ClassGroup::unknown_order_elem()
+    }
+
+    fn append_params(transcript: &mut Transcript) {
+        transcript.append_message(
+            b"discriminant",
+            &ClassGroup::context_discriminant().to_bytes(),
+        );
+    }
+
+    fn append_elem(transcript: &mut Transcript, label: &'static [u8], a: &ClassElem) {
+        a.append_to_transcript(transcript, label);
+    }
+
+    /// Forms of the context discriminant with `a > 0`; the negative
+    /// definite forms of the same discriminant are not in the group.
+    fn is_elem(a: &ClassElem) -> bool {
+        a.a.sgn() > 0 && ClassGroup::validate(&a.a, &a.b, &a.c)
+    }
+}
+
 // pub fn multi_exp<G: Group>(alphas: &[G::Elem], x: &[Integer]) -> G::Elem {
 //     if alphas.len() == 1 {
 //         return alphas[0].clone();
@@ -46,6 +189,18 @@ pub use classy::ClassGroup;
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_elem() {
+        let g = ClassGroup::unknown_order_elem();
+        assert!(<ClassGroup as UnknownOrderGroup>::is_elem(&g));
+        let mut negative = g.clone();
+        negative.a.neg_mut();
+        negative.c.neg_mut();
+        assert!(ClassGroup::validate(&negative.a, &negative.b, &negative.c));
+        assert!(!<ClassGroup as UnknownOrderGroup>::is_elem(&negative));
+        assert!(!crate::commitment::is_elem(&negative));
+    }
+
     // #[test]
     // fn test_multi_exp() {
     //   let alpha_1 = Rsa2048::elem(2);
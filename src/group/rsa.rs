@@ -0,0 +1,137 @@
+//! The group `Z_N^* / {±1}` for the RSA-2048 challenge modulus `N`.
+//!
+//! Nobody is known to have factored `N`, so its order is unknown too, but
+//! unlike the class group it needed a trusted setup. It is here to compare
+//! the class group against, not for deployment.
+
+use super::{Group, UnknownOrderGroup};
+use bacteria::Transcript;
+use rug::integer::Order;
+use rug::ops::RemRounding;
+use rug::Integer;
+use std::str::FromStr;
+
+const RSA2048_MODULUS_DECIMAL: &str =
+  "251959084756578934940271832400483985714292821262040320277771378360436620207075955562640185258807\
+  8440691829064124951508218929855914917618450280848912007284499268739280728777673597141834727026189\
+  6375014971824691165077613379859095700097330459748808428401797429100642458691817195118746121515172\
+  6546322822168699875491824224336372590851418654620435767984233871847744479207399342365848238242811\
+  9816381501067481045166037730605620161967625613384414360383390441495263443219011465754445417842402\
+  0924616515723350778707749817125772467962926386356373289912154831438167899885040445364023527381951\
+  378636564391212010397122822120720357";
+
+lazy_static! {
+    static ref RSA2048_MODULUS: Integer = Integer::from_str(RSA2048_MODULUS_DECIMAL).unwrap();
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Rsa2048 {}
+
+/// An element of `Z_N^* / {±1}`, represented by the smaller of `x` and `N - x`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RsaElem(Integer);
+
+impl RsaElem {
+    pub fn value(&self) -> &Integer {
+        &self.0
+    }
+}
+
+impl Rsa2048 {
+    pub fn modulus() -> &'static Integer {
+        &RSA2048_MODULUS
+    }
+
+    /// The element represented by `x` modulo `N`.
+    pub fn elem(x: Integer) -> RsaElem {
+        let n = &*RSA2048_MODULUS;
+        let x = x.rem_euc(n);
+        let neg = Integer::from(n - &x);
+        RsaElem(if neg < x { neg } else { x })
+    }
+}
+
+impl Group for Rsa2048 {
+    type Elem = RsaElem;
+    /// The modulus is fixed.
+    type Params = ();
+
+    fn params() {}
+
+    fn with_params<T, F: FnOnce() -> T>(_: &(), f: F) -> T {
+        f()
+    }
+
+    fn id() -> RsaElem {
+        RsaElem(Integer::from(1))
+    }
+
+    fn op(a: &RsaElem, b: &RsaElem) -> RsaElem {
+        Self::elem(Integer::from(&a.0 * &b.0))
+    }
+
+    fn inv(a: &RsaElem) -> RsaElem {
+        // Every element of `Z_N^*` is a unit unless it reveals a factor of `N`.
+        Self::elem(
+            a.0.clone()
+                .invert(&RSA2048_MODULUS)
+                .expect("element is a unit"),
+        )
+    }
+
+    fn pow(a: &RsaElem, n: &Integer) -> RsaElem {
+        Self::elem(
+            a.0.clone()
+                .pow_mod(n, &RSA2048_MODULUS)
+                .expect("element is a unit"),
+        )
+    }
+}
+
+impl UnknownOrderGroup for Rsa2048 {
+    fn unknown_order_elem() -> RsaElem {
+        Self::elem(Integer::from(2))
+    }
+
+    fn append_params(transcript: &mut Transcript) {
+        transcript.append_message(b"modulus", &RSA2048_MODULUS.to_digits(Order::Msf));
+    }
+
+    fn append_elem(transcript: &mut Transcript, label: &'static [u8], a: &RsaElem) {
+        transcript.append_message(label, &a.0.to_digits(Order::Msf));
+    }
+
+    /// Elements are represented by the smaller of `x` and `N - x`, with `x` a
+    /// unit.
+    fn is_elem(a: &RsaElem) -> bool {
+        let n = &*RSA2048_MODULUS;
+        a.0 > 0 && Integer::from(&a.0 << 1) < *n && Integer::from(a.0.gcd_ref(n)) == 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modulus() {
+        assert_eq!(Rsa2048::modulus().significant_bits(), 2048);
+        assert_eq!(Rsa2048::modulus().to_string().len(), 617);
+    }
+
+    #[test]
+    fn test_ops() {
+        let two = Rsa2048::unknown_order_elem();
+        let minus_two = Rsa2048::elem(Integer::from(Rsa2048::modulus() - 2u32));
+        assert_eq!(minus_two, two);
+
+        let eight = Rsa2048::pow(&two, &Integer::from(3));
+        assert_eq!(eight, Rsa2048::elem(Integer::from(8)));
+        assert_eq!(Rsa2048::op(&eight, &Rsa2048::inv(&eight)), Rsa2048::id());
+        assert_eq!(Rsa2048::pow(&two, &Integer::from(-3)), Rsa2048::inv(&eight));
+
+        let mut sq = two.clone();
+        Rsa2048::square(&mut sq);
+        assert_eq!(sq, Rsa2048::elem(Integer::from(4)));
+    }
+}
@@ -0,0 +1,127 @@
+//! Discriminants stored as the seed and bit length they are derived from.
+//!
+//! A 2048-bit discriminant is 256 bytes on the wire; the inputs to
+//! `create_discriminant` are usually a few dozen. The encoding is a version
+//! byte, the length as a big-endian `u64` and then the seed. The prime is
+//! found again the first time it is needed and kept from then on.
+
+use super::create_discriminant;
+use crate::num::Mpz;
+use anyhow::{ensure, Result};
+use std::convert::TryInto;
+use std::ops::RangeInclusive;
+use std::sync::OnceLock;
+
+const VERSION: u8 = 1;
+
+/// The discriminant `create_discriminant(seed, length)`.
+#[derive(Clone, Debug)]
+pub struct SeededDiscriminant {
+    seed: Vec<u8>,
+    length: u64,
+    value: OnceLock<Mpz>,
+}
+
+impl SeededDiscriminant {
+    /// The bit lengths `from_bytes` accepts. Anything shorter is trivially
+    /// weak, and anything longer would let a peer make the receiver spend
+    /// unbounded time finding the prime.
+    pub const LENGTHS: RangeInclusive<u64> = 64..=16384;
+
+    pub fn new(seed: &[u8], length: u64) -> Self {
+        Self {
+            seed: seed.to_vec(),
+            length,
+            value: OnceLock::new(),
+        }
+    }
+
+    pub fn seed(&self) -> &[u8] {
+        &self.seed
+    }
+
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// The discriminant, derived on the first call.
+    pub fn discriminant(&self) -> &Mpz {
+        self.value
+            .get_or_init(|| create_discriminant(&self.seed, self.length))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(9 + self.seed.len());
+        buf.push(VERSION);
+        buf.extend_from_slice(&self.length.to_be_bytes());
+        buf.extend_from_slice(&self.seed);
+        buf
+    }
+
+    /// Parses the encoding of `to_bytes`, rejecting a length outside
+    /// `Self::LENGTHS`. The discriminant is not derived until it is asked for.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        ensure!(bytes.len() >= 9, "seeded discriminant is truncated");
+        ensure!(
+            bytes[0] == VERSION,
+            "unsupported seeded discriminant version {}",
+            bytes[0]
+        );
+        let length = u64::from_be_bytes(bytes[1..9].try_into().unwrap());
+        ensure!(
+            Self::LENGTHS.contains(&length),
+            "seeded discriminant length {} is out of range",
+            length
+        );
+        Ok(Self::new(&bytes[9..], length))
+    }
+}
+
+impl PartialEq for SeededDiscriminant {
+    fn eq(&self, other: &Self) -> bool {
+        self.seed == other.seed && self.length == other.length
+    }
+}
+
+impl Eq for SeededDiscriminant {}
+
+#[cfg(feature = "serde")]
+crate::serialize::impl_serde_bytes!(
+    SeededDiscriminant,
+    SeededDiscriminant::to_bytes,
+    SeededDiscriminant::from_bytes
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded() {
+        let seeded = SeededDiscriminant::new(b"\xaa", 512);
+        let bytes = seeded.to_bytes();
+        assert_eq!(bytes.len(), 10);
+        let parsed = SeededDiscriminant::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, seeded);
+        assert!(parsed.value.get().is_none());
+        assert_eq!(*parsed.discriminant(), create_discriminant(b"\xaa", 512));
+        assert!(parsed.value.get().is_some());
+
+        assert!(SeededDiscriminant::from_bytes(&bytes[..8]).is_err());
+        let mut future = bytes;
+        future[0] = VERSION + 1;
+        assert!(SeededDiscriminant::from_bytes(&future).is_err());
+
+        for &length in &[0, 63, 16385, u64::MAX] {
+            let bytes = SeededDiscriminant::new(b"\xaa", length).to_bytes();
+            assert!(SeededDiscriminant::from_bytes(&bytes).is_err());
+        }
+        for &length in &[64, 16384] {
+            let bytes = SeededDiscriminant::new(b"\xaa", length).to_bytes();
+            assert_eq!(
+                SeededDiscriminant::from_bytes(&bytes).unwrap().length(),
+                length
+            );
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Multi-party generation of a discriminant seed.
+//!
+//! Each party contributes a random seed, and the contributions are chained
+//! with a transcript hash into the seed passed to `create_discriminant`. The
+//! resulting group is trustless as long as one party's seed was random and
+//! chosen independently of the others. To keep the last party from grinding
+//! its seed, every party should publish `Contribution::commitment` before
+//! any seed is revealed; `SetupTranscript::verify_commitments` then checks
+//! the reveals against them.
+//!
+//! A `SetupTranscript` records every contribution together with the chain
+//! of intermediate seeds, so anyone can replay the ceremony with `verify` and
+//! each party can find its own contribution in it.
+
+use anyhow::{ensure, Result};
+use bacteria::Transcript;
+use rand::{CryptoRng, RngCore};
+
+#[cfg(feature = "prover")]
+use crate::num::Mpz;
+
+/// One party's input to the ceremony.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Contribution {
+    pub party: Vec<u8>,
+    pub seed: [u8; 32],
+}
+
+impl Contribution {
+    /// A contribution with a fresh random seed.
+    pub fn random<R: RngCore + CryptoRng>(party: &[u8], rng: &mut R) -> Self {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        Self {
+            party: party.to_vec(),
+            seed,
+        }
+    }
+
+    /// A hiding commitment to the seed, published before the reveal.
+    pub fn commitment(&self) -> [u8; 32] {
+        let mut transcript = Transcript::new(b"Classygroup.setup.commitment");
+        transcript.append_message(b"party", &self.party);
+        transcript.append_message(b"seed", &self.seed);
+        let mut out = [0u8; 32];
+        transcript.challenge_bytes(b"commitment", &mut out);
+        out
+    }
+}
+
+/// The record of a ceremony: its contributions in order, and the seed after
+/// each of them.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SetupTranscript {
+    domain: Vec<u8>,
+    contributions: Vec<Contribution>,
+    chain: Vec<[u8; 32]>,
+}
+
+fn link(domain: &[u8], prev: &[u8; 32], contribution: &Contribution) -> [u8; 32] {
+    let mut transcript = Transcript::new(b"Classygroup.setup");
+    transcript.append_message(b"domain", domain);
+    transcript.append_message(b"prev", prev);
+    transcript.append_message(b"party", &contribution.party);
+    transcript.append_message(b"seed", &contribution.seed);
+    let mut out = [0u8; 32];
+    transcript.challenge_bytes(b"next", &mut out);
+    out
+}
+
+impl SetupTranscript {
+    /// An empty ceremony for `domain`, which separates ceremonies for
+    /// different applications.
+    pub fn new(domain: &[u8]) -> Self {
+        Self {
+            domain: domain.to_vec(),
+            contributions: Vec::new(),
+            chain: Vec::new(),
+        }
+    }
+
+    pub fn domain(&self) -> &[u8] {
+        &self.domain
+    }
+
+    pub fn contributions(&self) -> &[Contribution] {
+        &self.contributions
+    }
+
+    /// The seed after each contribution.
+    pub fn chain(&self) -> &[[u8; 32]] {
+        &self.chain
+    }
+
+    /// The current seed: all zeros before the first contribution, and the
+    /// discriminant seed once the ceremony is over.
+    pub fn seed(&self) -> [u8; 32] {
+        self.chain.last().copied().unwrap_or([0u8; 32])
+    }
+
+    /// Appends a contribution and returns the new seed. Fails if the party
+    /// has already contributed.
+    pub fn contribute(&mut self, contribution: Contribution) -> Result<[u8; 32]> {
+        ensure!(
+            self.contributions
+                .iter()
+                .all(|c| c.party != contribution.party),
+            "party {:?} has already contributed",
+            contribution.party
+        );
+        let next = link(&self.domain, &self.seed(), &contribution);
+        self.contributions.push(contribution);
+        self.chain.push(next);
+        Ok(next)
+    }
+
+    /// Replays the chain from the contributions.
+    pub fn verify(&self) -> bool {
+        if self.contributions.len() != self.chain.len() {
+            return false;
+        }
+        let mut prev = [0u8; 32];
+        for (i, (c, next)) in self.contributions.iter().zip(&self.chain).enumerate() {
+            if self.contributions[..i].iter().any(|p| p.party == c.party)
+                || link(&self.domain, &prev, c) != *next
+            {
+                return false;
+            }
+            prev = *next;
+        }
+        true
+    }
+
+    /// Whether the contributions open `commitments`, published in the same
+    /// order before the reveal.
+    pub fn verify_commitments(&self, commitments: &[[u8; 32]]) -> bool {
+        commitments.len() == self.contributions.len()
+            && self
+                .contributions
+                .iter()
+                .zip(commitments)
+                .all(|(c, commitment)| c.commitment() == *commitment)
+    }
+
+    /// Whether `contribution` went into the ceremony.
+    pub fn includes(&self, contribution: &Contribution) -> bool {
+        self.contributions.contains(contribution)
+    }
+
+    /// The discriminant of `bits` bits derived from the final seed.
+    #[cfg(feature = "prover")]
+    pub fn discriminant(&self, bits: u64) -> Mpz {
+        super::create_discriminant(&self.seed(), bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_ceremony() {
+        let rng = &mut thread_rng();
+        let parties = [
+            Contribution::random(b"alice", rng),
+            Contribution::random(b"bob", rng),
+            Contribution::random(b"carol", rng),
+        ];
+        let commitments: Vec<_> = parties.iter().map(|c| c.commitment()).collect();
+
+        let mut setup = SetupTranscript::new(b"test");
+        for c in &parties {
+            setup.contribute(c.clone()).unwrap();
+        }
+        assert!(setup.contribute(parties[1].clone()).is_err());
+        assert!(setup.verify());
+        assert!(setup.verify_commitments(&commitments));
+        assert!(setup.includes(&parties[2]));
+        assert_eq!(setup.chain().len(), 3);
+
+        // Order and domain both change the seed.
+        let mut other = SetupTranscript::new(b"test");
+        for c in parties.iter().rev() {
+            other.contribute(c.clone()).unwrap();
+        }
+        assert_ne!(other.seed(), setup.seed());
+        assert!(!other.verify_commitments(&commitments));
+        let mut other = SetupTranscript::new(b"other");
+        other.contribute(parties[0].clone()).unwrap();
+        assert_ne!(other.seed(), setup.chain()[0]);
+
+        let mut tampered = setup.clone();
+        tampered.contributions[1].seed[0] ^= 1;
+        assert!(!tampered.verify());
+        assert!(!tampered.verify_commitments(&commitments));
+
+        #[cfg(feature = "prover")]
+        {
+            let d = setup.discriminant(512);
+            assert_eq!(d, setup.discriminant(512));
+            assert!(d < Mpz::from(0));
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Borrowed views of elements in the encoding of `ClassElem::to_bytes`.
+//!
+//! Parsing an element builds three `Mpz`s and divides to recover `c`. A
+//! verifier that is handed many elements and works with only a few can
+//! instead wrap the bytes in a `ClassElemRef`, which checks the length and
+//! the sign of `a` and nothing more, and `decode` the ones it keeps. The
+//! encoding is canonical, so views compare and hash as their elements do.
+
+use super::elem::check_length;
+use super::envelope::open;
+use super::{ClassElem, DeserializeError};
+use crate::num::Mpz;
+
+/// An element of the class group of some discriminant, not yet parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ClassElemRef<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ClassElemRef<'a> {
+    /// A view of the encoding of an element of the class group of
+    /// `discriminant`, without parsing it.
+    pub fn new(bytes: &'a [u8], discriminant: &Mpz) -> Result<Self, DeserializeError> {
+        check_length(bytes, ClassElem::serialized_size(discriminant.bit_length()))?;
+        let a = &bytes[..bytes.len() / 2];
+        if a[0] & 0x80 != 0 || a.iter().all(|&byte| byte == 0) {
+            return Err(DeserializeError::InvalidCoefficients("a must be positive"));
+        }
+        Ok(Self { bytes })
+    }
+
+    /// Views of the elements packed back to back in `bytes`.
+    pub fn split(bytes: &'a [u8], discriminant: &Mpz) -> Result<Vec<Self>, DeserializeError> {
+        let size = ClassElem::serialized_size(discriminant.bit_length());
+        if bytes.len() % size != 0 {
+            return Err(DeserializeError::WrongLength {
+                expected: bytes.len() / size * size,
+                actual: bytes.len(),
+            });
+        }
+        bytes
+            .chunks(size)
+            .map(|chunk| Self::new(chunk, discriminant))
+            .collect()
+    }
+
+    /// A view of the element in an envelope from `ClassElem::to_envelope`.
+    /// The envelope header is checked as `envelope::open` does.
+    pub fn from_envelope(bytes: &'a [u8], discriminant: &Mpz) -> Result<Self, DeserializeError> {
+        Self::new(open(discriminant, bytes)?, discriminant)
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Whether this is the identity `(1, 1, .)`, read off the bytes.
+    pub fn is_identity(&self) -> bool {
+        let (a, b) = self.bytes.split_at(self.bytes.len() / 2);
+        let is_one =
+            |x: &[u8]| x.iter().rev().skip(1).all(|&byte| byte == 0) && x.last() == Some(&1);
+        is_one(a) && is_one(b)
+    }
+
+    /// Parses the element, with all the checks of `ClassElem::from_bytes`.
+    pub fn decode(&self, discriminant: &Mpz) -> Result<ClassElem, DeserializeError> {
+        ClassElem::from_bytes(self.bytes, discriminant)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::{ClassGroup, CLASS_GROUP_DISCRIMINANT};
+    use rug::Integer;
+
+    #[test]
+    fn test_views() {
+        let disc = &*CLASS_GROUP_DISCRIMINANT;
+        let bits = disc.bit_length();
+        let g = ClassGroup::pow(&ClassGroup::unknown_order_elem(), &Integer::from(5));
+        let mut bytes = ClassGroup::id().to_bytes(bits);
+        bytes.extend(g.to_bytes(bits));
+        bytes.extend(g.to_bytes(bits));
+
+        let views = ClassElemRef::split(&bytes, disc).unwrap();
+        assert_eq!(views.len(), 3);
+        assert!(views[0].is_identity() && !views[1].is_identity());
+        assert_eq!(views[1], views[2]);
+        assert_eq!(views[0].decode(disc).unwrap(), ClassGroup::id());
+        assert_eq!(views[1].decode(disc).unwrap(), g);
+        assert!(ClassElemRef::split(&bytes[1..], disc).is_err());
+
+        let envelope = g.to_envelope();
+        let view = ClassElemRef::from_envelope(&envelope, disc).unwrap();
+        assert_eq!(view, views[1]);
+
+        // A negative `a` is turned away without parsing; a bad `b` is only
+        // caught by `decode`.
+        let mut bad = g.to_bytes(bits);
+        bad[0] = 0xff;
+        assert!(ClassElemRef::new(&bad, disc).is_err());
+        let mut bad = g.to_bytes(bits);
+        let last = bad.len() - 1;
+        bad[last] ^= 2;
+        assert!(ClassElemRef::new(&bad, disc).unwrap().decode(disc).is_err());
+    }
+}
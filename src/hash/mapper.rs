@@ -0,0 +1,120 @@
+//! Domain-separated hashing of application elements to primes.
+//!
+//! A mapping records the counter, or nonce, at which the first prime was
+//! found, so that a verifier can recompute the candidate and check it with
+//! a single primality test instead of repeating the search.
+
+use super::primality::is_prob_prime;
+use crate::uint::{u256, U256};
+use mohan::hash::blake256;
+use rug::Integer;
+
+/// Hashes elements to primes under a domain tag.
+///
+/// The default mapper has the empty tag. Its length is hashed like any
+/// other, so untagged elements are separated from every tagged domain, and
+/// the mapping differs from `hash_to_prime`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ElementMapper {
+    domain: Vec<u8>,
+}
+
+/// The prime an element maps to and the nonce it was found at.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MappedPrime {
+    pub prime: Integer,
+    pub nonce: u64,
+}
+
+impl ElementMapper {
+    pub fn new(domain: &[u8]) -> Self {
+        Self {
+            domain: domain.to_vec(),
+        }
+    }
+
+    pub fn domain(&self) -> &[u8] {
+        &self.domain
+    }
+
+    /// The odd candidate for `elem` at `nonce`: blake2b over the tag
+    /// prefixed with its length as a big-endian `u64`, the element and the
+    /// nonce.
+    fn candidate(&self, elem: &[u8], nonce: u64) -> U256 {
+        let mut buf = Vec::with_capacity(8 + self.domain.len() + elem.len() + 8);
+        buf.extend_from_slice(&(self.domain.len() as u64).to_be_bytes());
+        buf.extend_from_slice(&self.domain);
+        buf.extend_from_slice(elem);
+        buf.extend_from_slice(&nonce.to_le_bytes());
+        let mut hash = blake256(&buf).to_bytes();
+        hash[0] |= 1;
+        u256(hash)
+    }
+
+    /// Maps `elem` to the first prime candidate.
+    pub fn map(&self, elem: &[u8]) -> MappedPrime {
+        let mut nonce = 0_u64;
+        loop {
+            let candidate = self.candidate(elem, nonce);
+            if is_prob_prime(&candidate) {
+                return MappedPrime {
+                    prime: Integer::from(candidate),
+                    nonce,
+                };
+            }
+            nonce += 1;
+        }
+    }
+
+    pub fn prime(&self, elem: &[u8]) -> Integer {
+        self.map(elem).prime
+    }
+
+    /// Checks that `mapped` is the candidate for `elem` at its nonce and is
+    /// prime.
+    ///
+    /// Any nonce that gives a prime passes, not only the first, so whoever
+    /// supplies `mapped` can pick among many primes for the same element.
+    /// Protocols that need each element to have a single prime, such as
+    /// accumulator membership, must not accept a `MappedPrime` from
+    /// someone else; they have to recompute it with `map`.
+    pub fn verify(&self, elem: &[u8], mapped: &MappedPrime) -> bool {
+        let candidate = self.candidate(elem, mapped.nonce);
+        Integer::from(candidate) == mapped.prime && is_prob_prime(&candidate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::hash_to_prime;
+
+    #[test]
+    fn test_map_verify() {
+        let elem = b"output 0";
+        let tagged = ElementMapper::new(b"utxo");
+        let mapped = tagged.map(elem);
+        assert!(tagged.verify(elem, &mapped));
+        assert!(!tagged.verify(b"output 1", &mapped));
+        assert!(!ElementMapper::new(b"vector").verify(elem, &mapped));
+
+        let shifted = MappedPrime {
+            nonce: mapped.nonce + 1,
+            ..mapped.clone()
+        };
+        assert!(!tagged.verify(elem, &shifted));
+
+        assert_ne!(ElementMapper::default().prime(elem), hash_to_prime(elem));
+        // The tag is length-prefixed, so it cannot run into the element.
+        assert_ne!(
+            ElementMapper::new(b"utxo").prime(b"x"),
+            ElementMapper::new(b"utx").prime(b"ox")
+        );
+        // The empty tag is length-prefixed too, so an untagged element
+        // cannot imitate a tagged one.
+        let mut prefixed = 4u64.to_be_bytes().to_vec();
+        prefixed.extend_from_slice(b"utxo");
+        prefixed.extend_from_slice(elem);
+        assert_ne!(ElementMapper::default().prime(&prefixed), mapped.prime);
+    }
+}
@@ -6,7 +6,9 @@ use rug::integer::Order;
 use rug::Integer;
 use std::hash::Hash;
 pub mod primality;
-use crate::num::Mpz;
+mod mapper;
+pub use mapper::{ElementMapper, MappedPrime};
+use crate::num::{Mpz, TRIAL_DIVISION_BOUND};
 
 /// Hashes t with an incrementing counter (with blake2b) until a prime is found.
 pub fn hash_to_prime(t: &[u8]) -> Integer {
@@ -41,7 +43,9 @@ pub fn hash_to_prime_Mpz(t: &[u8]) -> Mpz {
         // Make the candidate prime odd. This gives ~7% performance gain on a 2018 Macbook Pro.
         hash[0] |= 1;
         let candidate_prime = Mpz::from_bytes(&hash);
-        if candidate_prime.is_prime(50) { //resonable value is between 15 
+        if candidate_prime.trial_division(TRIAL_DIVISION_BOUND).is_none()
+            && candidate_prime.is_prime(50) //resonable value is between 15
+        {
             return candidate_prime;
         }
         counter += 1;
@@ -60,7 +64,9 @@ pub fn hash_to_prime_bytes(t: &[u8]) -> [u8; 32] {
         // Make the candidate prime odd. This gives ~7% performance gain on a 2018 Macbook Pro.
         hash[0] |= 1;
         let candidate_prime = Mpz::from_bytes(&hash);
-        if candidate_prime.is_prime(50) { //resonable value is between 15 
+        if candidate_prime.trial_division(TRIAL_DIVISION_BOUND).is_none()
+            && candidate_prime.is_prime(50) //resonable value is between 15
+        {
             return hash;
         }
         counter += 1;
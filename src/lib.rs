@@ -17,7 +17,9 @@
 extern crate lazy_static;
 
 pub mod group;
-pub use group::{create_discriminant, ClassElem, ClassGroup};
+#[cfg(feature = "prover")]
+pub use group::create_discriminant;
+pub use group::{ClassElem, ClassGroup};
 
 pub mod num;
 pub use num::Mpz;
@@ -27,6 +29,31 @@ pub mod uint;
 pub mod hash;
 pub use hash::hash_to_prime;
 
+pub mod accumulator;
+pub use accumulator::Accumulator;
+
+pub mod commitment;
+pub use commitment::{Commitment, CommitmentKey};
+
+pub mod cl;
+
+pub mod proofs;
+
+pub mod encoding;
+
+pub mod transcript;
+pub use transcript::TranscriptProtocol;
+
+#[cfg(feature = "serde")]
+mod serialize;
+
+#[cfg(feature = "proto")]
+pub mod proto;
+
+pub mod vdf;
+#[cfg(feature = "prover")]
+pub use vdf::solve;
+
 // Get a tuple of mutable reference from a tuple.
 #[macro_export]
 macro_rules! mut_tuple_elems {
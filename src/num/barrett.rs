@@ -0,0 +1,108 @@
+//! Barrett reduction modulo a fixed modulus.
+//!
+//! Reducing modulo `m` with `mpz_mod` divides every time. When the same `m`
+//! is used over and over, `ModContext` pays for one division up front, to
+//! find `floor(4^k / m)` for a `k`-bit `m`, and then reduces any `x` below
+//! `4^k` with two multiplications, shifts and at most two subtractions.
+
+use super::Mpz;
+use gmp_mpfr_sys::gmp;
+
+/// A positive modulus with its Barrett reciprocal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModContext {
+    m: Mpz,
+    mu: Mpz,
+    k: usize,
+}
+
+impl ModContext {
+    /// Panics if `m` is not positive.
+    pub fn new(m: &Mpz) -> Self {
+        assert!(m.sgn() > 0, "ModContext: modulus must be positive");
+        let k = m.bit_length();
+        let mut pow = Mpz::one();
+        unsafe { gmp::mpz_mul_2exp(&mut pow.inner, &pow.inner, 2 * k as u64) };
+        let mut mu = Mpz::default();
+        mu.fdiv_q(&pow, m);
+        Self {
+            m: m.clone(),
+            mu,
+            k,
+        }
+    }
+
+    pub fn modulus(&self) -> &Mpz {
+        &self.m
+    }
+
+    /// `x mod m`, in `[0, m)`. Negative inputs, and those of more than
+    /// twice the bits of `m`, are reduced with an ordinary division.
+    pub fn reduce(&self, x: &Mpz) -> Mpz {
+        let mut ret = x.clone();
+        self.reduce_mut(&mut ret);
+        ret
+    }
+
+    pub fn reduce_mut(&self, x: &mut Mpz) {
+        if x.sgn() < 0 || x.bit_length() > 2 * self.k {
+            x.modulo_mut(&self.m);
+            return;
+        }
+        if *x < self.m {
+            return;
+        }
+        // q = floor(floor(x / 2^(k-1)) * mu / 2^(k+1)) is at most two
+        // below floor(x / m).
+        let mut q = Mpz::default();
+        q.tdiv_q_2exp(x, self.k as u64 - 1);
+        q.mul_mut(&self.mu);
+        q.tdiv_q_2exp(&q.clone(), self.k as u64 + 1);
+        q.mul_mut(&self.m);
+        x.sub_mut(&q);
+        while *x >= self.m {
+            x.sub_mut(&self.m);
+        }
+    }
+
+    /// `a * b mod m` for `a` and `b` in `[0, m)`.
+    pub fn mul(&self, a: &Mpz, b: &Mpz) -> Mpz {
+        let mut ret = Mpz::default();
+        ret.mul(a, b);
+        self.reduce_mut(&mut ret);
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_reduce() {
+        let rng = &mut StdRng::seed_from_u64(7);
+        for &bits in &[1, 2, 63, 64, 65, 256, 1000] {
+            let mut m = Mpz::random_bits(bits, rng);
+            m.add_ui_mut(1);
+            let ctx = ModContext::new(&m);
+            for _ in 0..100 {
+                let x = Mpz::random_bits(2 * bits + 8, rng);
+                let mut expected = Mpz::default();
+                expected.modulo(&x, &m);
+                assert_eq!(ctx.reduce(&x), expected);
+                let mut neg = Mpz::default();
+                neg.neg(&x);
+                expected.modulo(&neg, &m);
+                assert_eq!(ctx.reduce(&neg), expected);
+
+                let (a, b) = (ctx.reduce(&x), ctx.reduce(&Mpz::random_bits(bits, rng)));
+                let mut ab = Mpz::default();
+                ab.mul(&a, &b);
+                expected.modulo(&ab, &m);
+                assert_eq!(ctx.mul(&a, &b), expected);
+            }
+        }
+    }
+}
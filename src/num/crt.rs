@@ -0,0 +1,70 @@
+//! The Chinese remainder theorem.
+
+use super::Mpz;
+
+/// The least non-negative `x` with `x = r mod m` for every `(r, m)` in
+/// `congruences`, or `None` if they contradict each other. The moduli must
+/// be positive but need not be coprime; `x` is unique modulo their least
+/// common multiple. No congruences give zero.
+pub fn crt(congruences: &[(Mpz, Mpz)]) -> Option<Mpz> {
+    let (mut x, mut lcm) = (Mpz::zero(), Mpz::one());
+    let (mut g, mut diff, mut t) = (Mpz::default(), Mpz::default(), Mpz::default());
+    for (r, m) in congruences {
+        assert!(m.sgn() > 0, "crt: moduli must be positive");
+        // x + lcm * t = r mod m, so t = (r - x) / g * (lcm / g)^-1 mod m / g.
+        g.gcd(&lcm, m);
+        diff.sub(r, &x);
+        let quot = diff.checked_divexact(&g)?;
+        let mut m_g = Mpz::default();
+        m_g.divexact(m, &g);
+        let mut lcm_g = Mpz::default();
+        lcm_g.divexact(&lcm, &g);
+        t.mul(&quot, &lcm_g.invert_mod(&m_g)?);
+        t.modulo_mut(&m_g);
+        x.add_mul(&lcm, &t);
+        lcm.mul_mut(&m_g);
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn congruences(pairs: &[(i64, u64)]) -> Vec<(Mpz, Mpz)> {
+        pairs
+            .iter()
+            .map(|&(r, m)| {
+                let mut residue = Mpz::default();
+                residue.set_si(r);
+                (residue, Mpz::from(m))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt(&[]), Some(Mpz::zero()));
+        assert_eq!(
+            crt(&congruences(&[(2, 3), (3, 5), (2, 7)])),
+            Some(Mpz::from(23))
+        );
+        assert_eq!(crt(&congruences(&[(-1, 4), (0, 9)])), Some(Mpz::from(27)));
+        // Moduli sharing a factor, consistent and not.
+        assert_eq!(crt(&congruences(&[(3, 4), (5, 6)])), Some(Mpz::from(11)));
+        assert_eq!(crt(&congruences(&[(3, 4), (4, 6)])), None);
+        assert_eq!(crt(&congruences(&[(3, 4), (7, 4)])), Some(Mpz::from(3)));
+
+        let moduli = [1_000_003u64, 998_244_353, 1_000_000_007, 65_537];
+        let x = Mpz::from(0x1234_5678_9abc_def0u64);
+        let pairs: Vec<_> = moduli
+            .iter()
+            .map(|&m| {
+                let mut r = Mpz::default();
+                r.modulo(&x, &Mpz::from(m));
+                (r, Mpz::from(m))
+            })
+            .collect();
+        assert_eq!(crt(&pairs), Some(x));
+    }
+}
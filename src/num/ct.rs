@@ -0,0 +1,96 @@
+//! Comparisons and swaps whose timing does not depend on the values.
+//!
+//! GMP's own functions return as soon as they know the answer, and
+//! branching on a secret comparison leaks it. These work on the limbs
+//! directly and without branches on their contents. Only the limb count
+//! the caller passes to `ct_eq`, which is public, changes how long they
+//! take.
+
+use super::Mpz;
+use gmp_mpfr_sys::gmp;
+use std::hint::black_box;
+use std::mem::{align_of, size_of};
+use std::slice;
+
+// `conditional_swap` exchanges the `mpz_t` structs word by word, which
+// needs them to be a whole number of aligned words.
+const _: () = assert!(
+    size_of::<gmp::mpz_t>() % size_of::<usize>() == 0
+        && align_of::<gmp::mpz_t>() >= align_of::<usize>()
+);
+
+fn limbs(x: &Mpz) -> &[gmp::limb_t] {
+    unsafe { slice::from_raw_parts(gmp::mpz_limbs_read(&x.inner), x.size()) }
+}
+
+/// All ones if `choice` is set, and zero otherwise.
+fn mask(choice: bool) -> u64 {
+    0u64.wrapping_sub(black_box(choice as u64))
+}
+
+impl Mpz {
+    /// Whether `self == other`, reading `n` limbs of each, zero padded.
+    ///
+    /// # Panics
+    ///
+    /// If either operand is longer than `n` limbs.
+    pub fn ct_eq(&self, other: &Mpz, n: usize) -> bool {
+        let (a, b) = (limbs(self), limbs(other));
+        assert!(a.len() <= n && b.len() <= n, "operand exceeds {} limbs", n);
+        let mut diff = (self.inner.size ^ other.inner.size) as gmp::limb_t;
+        for i in 0..n {
+            let x = a.get(i).copied().unwrap_or(0);
+            let y = b.get(i).copied().unwrap_or(0);
+            diff |= black_box(x ^ y);
+        }
+        diff == 0
+    }
+
+    /// Swaps `a` and `b` if `choice` is set, and leaves them otherwise,
+    /// in the same time either way. Like `mpz_swap`, the swap exchanges the
+    /// whole `mpz_t`s and so which allocation each holds, so it takes the
+    /// same time whatever their sizes.
+    pub fn conditional_swap(a: &mut Mpz, b: &mut Mpz, choice: bool) {
+        let m = mask(choice) as usize;
+        let n = size_of::<gmp::mpz_t>() / size_of::<usize>();
+        // Each struct ends up as one of the two originals, whatever its fields.
+        unsafe {
+            let x = slice::from_raw_parts_mut(&mut a.inner as *mut _ as *mut usize, n);
+            let y = slice::from_raw_parts_mut(&mut b.inner as *mut _ as *mut usize, n);
+            for (x, y) in x.iter_mut().zip(y) {
+                let t = (*x ^ *y) & m;
+                *x ^= t;
+                *y ^= t;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_ct() {
+        let m = |s| Mpz::from_str(s).unwrap();
+        let x = m("340282366920938463463374607431768211457");
+        assert!(x.ct_eq(&x.clone(), 3));
+        assert!(x.ct_eq(&x.clone(), 8));
+        assert!(!x.ct_eq(&m("340282366920938463463374607431768211456"), 3));
+        assert!(!x.ct_eq(&m("-340282366920938463463374607431768211457"), 3));
+        assert!(!x.ct_eq(&m("1"), 3));
+        assert!(Mpz::zero().ct_eq(&m("0"), 0));
+        assert!(std::panic::catch_unwind(|| x.ct_eq(&m("1"), 2)).is_err());
+
+        let (mut a, mut b) = (x.clone(), m("-5"));
+        Mpz::conditional_swap(&mut a, &mut b, false);
+        assert_eq!((&a, &b), (&x, &m("-5")));
+        Mpz::conditional_swap(&mut a, &mut b, true);
+        assert_eq!((&a, &b), (&m("-5"), &x));
+        // Each still owns a valid allocation.
+        a.mul_mut(&x);
+        b.add_ui_mut(1);
+        assert_eq!(b, m("340282366920938463463374607431768211458"));
+    }
+}
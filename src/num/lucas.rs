@@ -0,0 +1,97 @@
+//! Lucas sequences modulo `n`.
+//!
+//! For integers `P` and `Q` with `D = P^2 - 4Q`, the sequences start at
+//! `U_0 = 0, U_1 = 1` and `V_0 = 2, V_1 = P`, and both satisfy
+//! `X_{j+2} = P X_{j+1} - Q X_j`. They are found here by walking down the
+//! bits of `k`, doubling with `U_{2j} = U_j V_j, V_{2j} = V_j^2 - 2 Q^j`
+//! and stepping with `U_{j+1} = (P U_j + V_j) / 2, V_{j+1} = (D U_j + P V_j) / 2`.
+//! The halving is why `n` has to be odd.
+
+use super::Mpz;
+
+/// `(U_k, V_k, Q^k)` modulo `n`, each in `[0, n)`. Panics if `k` is
+/// negative or `n` is not odd and positive.
+pub fn lucas_sequence(p: &Mpz, q: &Mpz, k: &Mpz, n: &Mpz) -> (Mpz, Mpz, Mpz) {
+    assert!(k.sgn() >= 0, "lucas_sequence: k must not be negative");
+    assert!(
+        n.sgn() > 0 && n.odd() != 0,
+        "lucas_sequence: n must be odd and positive"
+    );
+    let reduce = |x: &Mpz| {
+        let mut ret = Mpz::default();
+        ret.modulo(x, n);
+        ret
+    };
+    if k.is_zero() {
+        return (Mpz::zero(), reduce(&Mpz::from(2)), reduce(&Mpz::one()));
+    }
+    let (p, q) = (reduce(p), reduce(q));
+    let mut d = Mpz::default();
+    d.mul(&p, &p);
+    d.submul_ui(&q, 4);
+    d.modulo_mut(n);
+
+    let half = |x: &mut Mpz| {
+        if x.odd() != 0 {
+            x.add_mut(n);
+        }
+        x.tdiv_q_2exp(&x.clone(), 1);
+    };
+    let (mut u, mut v, mut q_k) = (reduce(&Mpz::one()), p.clone(), q.clone());
+    let mut t = Mpz::default();
+    for i in (0..k.bit_length() - 1).rev() {
+        u.mul_mut(&v);
+        u.modulo_mut(n);
+        v.square_mut();
+        v.submul_ui(&q_k, 2);
+        v.modulo_mut(n);
+        q_k.square_mut();
+        q_k.modulo_mut(n);
+        if k.testbit(i) {
+            t.mul(&d, &u);
+            t.add_mul(&p, &v);
+            half(&mut t);
+            t.modulo_mut(n);
+            u.mul_mut(&p);
+            u.add_mut(&v);
+            half(&mut u);
+            u.modulo_mut(n);
+            v.swap(&mut t);
+            q_k.mul_mut(&q);
+            q_k.modulo_mut(n);
+        }
+    }
+    (u, v, q_k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lucas_sequence() {
+        let m = |x: i64| {
+            let mut ret = Mpz::default();
+            ret.set_si(x);
+            ret
+        };
+        for &(p, q) in &[(1i64, -1i64), (3, 2), (-2, 5), (4, 4)] {
+            for &n in &[1i64, 9, 97, 1001] {
+                // The recurrence, directly.
+                let (mut u, mut v, mut q_k) = ((0i64, 1i64), (2i64, p), 1i64);
+                for k in 0..40 {
+                    let got = lucas_sequence(&m(p), &m(q), &m(k), &m(n));
+                    let want = (
+                        m(u.0.rem_euclid(n)),
+                        m(v.0.rem_euclid(n)),
+                        m(q_k.rem_euclid(n)),
+                    );
+                    assert_eq!(got, want, "P={} Q={} k={} n={}", p, q, k, n);
+                    u = (u.1, (p * u.1 - q * u.0) % n);
+                    v = (v.1, (p * v.1 - q * v.0) % n);
+                    q_k = q_k * q % n;
+                }
+            }
+        }
+    }
+}
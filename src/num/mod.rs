@@ -1,7 +1,37 @@
 //! BigNum Backend
+//!
+//! `Mpz` is a thin wrapper over GMP's `mpz_t`, and GMP is the only backend.
+//! It is not the only GMP user either: the group, proofs and accumulator
+//! also compute with `rug::Integer`, so a pure-Rust `Mpz` alone would not
+//! let the crate build without GMP.
 
 mod mpz;
-pub use mpz::Mpz;
+pub use mpz::{Mpz, NotInvertible};
+
+mod barrett;
+pub use barrett::ModContext;
+
+mod crt;
+pub use crt::crt;
+
+mod ct;
+
+mod lucas;
+pub use lucas::lucas_sequence;
+
+mod ops;
+
+mod pool;
+pub use pool::MpzPool;
+
+mod primality;
+pub use primality::TRIAL_DIVISION_BOUND;
+
+mod radix;
+pub use radix::ParseMpzError;
+
+#[cfg(feature = "num-traits")]
+mod traits;
 
 pub mod partial;
 pub(crate) mod rand;
@@ -2,14 +2,17 @@
 
 use gmp_mpfr_sys::gmp::{self, limb_t, mpz_t};
 use std::cmp::Ordering;
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
+use std::fmt;
 use std::hash::{Hash, Hasher};
-use std::mem::uninitialized;
-use std::os::raw::{c_int, c_ulong};
+use std::mem::MaybeUninit;
+use std::os::raw::{c_char, c_int, c_long, c_ulong};
 use std::slice;
 use std::str::FromStr;
 use std::ffi::c_void;
+use rug::integer::BorrowInteger;
 use rug::Integer;
+use super::ParseMpzError;
 
 #[derive(Debug)]
 #[cfg_attr(repr_transparent, repr(transparent))]
@@ -17,6 +20,12 @@ pub struct Mpz {
     pub inner: mpz_t,
 }
 
+// An `Mpz` owns its limbs outright, and GMP keeps no state between calls
+// beyond its allocator, which defaults to the thread-safe `malloc`. Moving
+// one to another thread is therefore fine, and so is sharing `&Mpz`: every
+// method taking `&self` passes it to GMP as a const operand, which GMP
+// only reads. The `mpz_t` pointer is what stops the compiler from seeing
+// this for itself.
 unsafe impl Send for Mpz {}
 unsafe impl Sync for Mpz {}
 impl Eq for Mpz {}
@@ -24,14 +33,45 @@ impl Eq for Mpz {}
 impl Default for Mpz {
     fn default() -> Self {
         let inner = unsafe {
-            let mut ret = uninitialized();
-            gmp::mpz_init(&mut ret);
-            ret
+            let mut ret = MaybeUninit::uninit();
+            gmp::mpz_init(ret.as_mut_ptr());
+            ret.assume_init()
         };
         Self { inner }
     }
 }
 
+impl Mpz {
+    /// Zero, with room for `bits`-bit values before it has to allocate.
+    pub fn with_capacity_bits(bits: usize) -> Self {
+        let inner = unsafe {
+            let mut ret = MaybeUninit::uninit();
+            gmp::mpz_init2(ret.as_mut_ptr(), bits as gmp::bitcnt_t);
+            ret.assume_init()
+        };
+        Self { inner }
+    }
+
+    /// The largest bit length `self` holds without reallocating.
+    pub fn capacity_bits(&self) -> usize {
+        self.inner.alloc as usize * gmp::LIMB_BITS as usize
+    }
+
+    /// Grows the allocation to hold `bits`-bit values, keeping the value.
+    /// Never shrinks.
+    pub fn reserve_bits(&mut self, bits: usize) {
+        if self.capacity_bits() < bits {
+            unsafe { gmp::mpz_realloc2(&mut self.inner, bits as gmp::bitcnt_t) }
+        }
+    }
+}
+
+impl Drop for Mpz {
+    fn drop(&mut self) {
+        unsafe { gmp::mpz_clear(&mut self.inner) }
+    }
+}
+
 impl Clone for Mpz {
     fn clone(&self) -> Self {
         let mut ret = Mpz::default();
@@ -72,7 +112,7 @@ impl Hash for Mpz {
         size.hash(state);
         if size != 0 {
             let limbs = size.checked_abs().expect("overflow") as usize;
-            let slice = unsafe { slice::from_raw_parts(&self.inner.d, limbs) };
+            let slice = unsafe { slice::from_raw_parts(gmp::mpz_limbs_read(&self.inner), limbs) };
             slice.hash(state);
         }
     }
@@ -86,23 +126,83 @@ impl From<u64> for Mpz {
     }
 }
 
+// `rug::Integer` wraps the same `mpz_t`, so conversions hand the limbs
+// over rather than copying them.
+
 impl From<Integer> for Mpz {
-  fn from(x: Integer) -> Self {
-    Mpz::from_str(&x.to_string_radix(10)).unwrap()
-  }
+    fn from(x: Integer) -> Self {
+        Self { inner: x.into_raw() }
+    }
+}
+
+impl From<Mpz> for Integer {
+    fn from(x: Mpz) -> Self {
+        let x = std::mem::ManuallyDrop::new(x);
+        unsafe { Integer::from_raw(x.inner) }
+    }
+}
+
+impl From<&Mpz> for Integer {
+    fn from(x: &Mpz) -> Self {
+        Integer::from(&*x.as_integer())
+    }
 }
 
 impl FromStr for Mpz {
-    type Err = std::ffi::NulError;
+    type Err = ParseMpzError;
 
+    /// Parses decimal, or hex after a `0x` prefix as written by `{:x}`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut ret = Mpz::default();
-        let c_str = CString::new(s)?;
-        ret.set_cstr(&c_str);
+        let (neg, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let (digits, radix) = match digits.strip_prefix("0x") {
+            Some(hex) if !hex.starts_with('-') => (hex, 16),
+            Some(_) => return Err(ParseMpzError),
+            None => (s, 10),
+        };
+        let c_str = CString::new(digits).map_err(|_| ParseMpzError)?;
+        if unsafe { gmp::mpz_set_str(&mut ret.inner, c_str.as_ptr(), radix) } != 0 {
+            return Err(ParseMpzError);
+        }
+        if neg && radix == 16 {
+            ret.neg_mut();
+        }
         Ok(ret)
     }
 }
 
+/// Hex with a `0x` prefix, after the sign.
+impl fmt::LowerHex for Mpz {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let len = unsafe { gmp::mpz_sizeinbase(&self.inner, 16) } + 2;
+        let mut buf = vec![0u8; len];
+        let s = unsafe {
+            let mut magnitude = Mpz::default();
+            magnitude.abs(self);
+            gmp::mpz_get_str(buf.as_mut_ptr() as *mut c_char, 16, &magnitude.inner);
+            CStr::from_ptr(buf.as_ptr() as *const c_char)
+        };
+        let sign = if self.is_neg() { "-" } else { "" };
+        write!(f, "{}0x{}", sign, s.to_string_lossy())
+    }
+}
+
+impl fmt::Display for Mpz {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Room for the digits, a minus sign and the terminating NUL.
+        let len = unsafe { gmp::mpz_sizeinbase(&self.inner, 10) } + 2;
+        let mut buf = vec![0u8; len];
+        let s = unsafe {
+            gmp::mpz_get_str(buf.as_mut_ptr() as *mut c_char, 10, &self.inner);
+            CStr::from_ptr(buf.as_ptr() as *const c_char)
+        };
+        f.write_str(&s.to_string_lossy())
+    }
+}
+
 // Defines wrappers around gmp_mpfr_sys.  Functions ending
 // with `_mut` correspond to giving the underlying GMP function
 // the same Mpz variable for the first two arguments, e.g.
@@ -147,6 +247,18 @@ impl Mpz {
         }
     }
 
+    /// Adds `x * val` to `self`.
+    #[inline]
+    pub fn addmul_ui(&mut self, x: &Mpz, val: u64) {
+        unsafe { gmp::mpz_addmul_ui(&mut self.inner, &x.inner, val as c_ulong) }
+    }
+
+    /// Subtracts `x * val` from `self`.
+    #[inline]
+    pub fn submul_ui(&mut self, x: &Mpz, val: u64) {
+        unsafe { gmp::mpz_submul_ui(&mut self.inner, &x.inner, val as c_ulong) }
+    }
+
     #[inline]
     pub fn cmp_mpz(&self, other: &Mpz) -> i32 {
         unsafe { gmp::mpz_cmp(&self.inner, &other.inner) }
@@ -176,18 +288,40 @@ impl Mpz {
         }
     }
 
+    /// Sets `self` to `n / d` for a `d` known to divide `n`, which is
+    /// faster than the other divisions. The result is garbage if `d` does
+    /// not divide `n`; debug builds check.
     #[inline]
     pub fn divexact(&mut self, n: &Mpz, d: &Mpz) {
+        debug_assert!(n.is_divisible(d), "divexact: inexact division");
         unsafe { gmp::mpz_divexact(&mut self.inner, &n.inner, &d.inner) }
     }
 
     #[inline]
     pub fn divexact_mut(&mut self, d: &Mpz) {
+        debug_assert!(self.is_divisible(d), "divexact: inexact division");
         unsafe {
             gmp::mpz_divexact(&mut self.inner, &self.inner, &d.inner);
         }
     }
 
+    /// `self / d` if `d` divides `self`, and `None` otherwise or if `d` is
+    /// zero.
+    pub fn checked_divexact(&self, d: &Mpz) -> Option<Mpz> {
+        if d.is_zero() || !self.is_divisible(d) {
+            return None;
+        }
+        let mut ret = Mpz::default();
+        unsafe { gmp::mpz_divexact(&mut ret.inner, &self.inner, &d.inner) };
+        Some(ret)
+    }
+
+    /// Whether `d` divides `self`; only zero is divisible by zero.
+    #[inline]
+    pub fn is_divisible(&self, d: &Mpz) -> bool {
+        unsafe { gmp::mpz_divisible_p(&self.inner, &d.inner) != 0 }
+    }
+
     #[inline]
     pub fn fdiv_q(&mut self, x: &Mpz, y: &Mpz) {
         unsafe {
@@ -249,6 +383,8 @@ impl Mpz {
         unsafe { gmp::mpz_gcd(&mut self.inner, &self.inner, &x.inner) }
     }
 
+    /// Sets `self` to `gcd(a, m)` and `d` and `e` to Bezout coefficients
+    /// with `self = a * d + m * e`, as `mpz_gcdext` does.
     #[inline]
     pub fn gcdext(&mut self, d: &mut Mpz, e: &mut Mpz, a: &Mpz, m: &Mpz) {
         unsafe {
@@ -262,6 +398,166 @@ impl Mpz {
         }
     }
 
+    /// `(g, s, t)` with `g = gcd(a, b) = s * a + t * b`, returned rather
+    /// than written in place as by `gcdext`.
+    pub fn xgcd(a: &Mpz, b: &Mpz) -> (Mpz, Mpz, Mpz) {
+        let (mut g, mut s, mut t) = (Mpz::default(), Mpz::default(), Mpz::default());
+        g.gcdext(&mut s, &mut t, a, b);
+        (g, s, t)
+    }
+
+    /// The inverse of `self` modulo `m`, in `[0, |m|)`, if there is one.
+    /// Nothing is invertible modulo zero.
+    pub fn invert_mod(&self, m: &Mpz) -> Option<Mpz> {
+        self.try_invert_mod(m).ok()
+    }
+
+    /// `invert_mod`, but on failure returns `gcd(self, m)`, which is a
+    /// factor of `m` when it is not `m` itself.
+    pub fn try_invert_mod(&self, m: &Mpz) -> Result<Mpz, NotInvertible> {
+        let mut ret = Mpz::default();
+        if !m.is_zero() && unsafe { gmp::mpz_invert(&mut ret.inner, &self.inner, &m.inner) } != 0 {
+            return Ok(ret);
+        }
+        ret.gcd(self, m);
+        Err(NotInvertible { gcd: ret })
+    }
+
+    /// The Kronecker symbol `(self | n)`, which is the Jacobi symbol when
+    /// `n` is odd and positive and the Legendre symbol when `n` is prime.
+    #[inline]
+    pub fn kronecker(&self, n: &Mpz) -> i32 {
+        unsafe { gmp::mpz_kronecker(&self.inner, &n.inner) as i32 }
+    }
+
+    /// `(self | n)` for a machine-word `n`, without building an `Mpz`.
+    #[inline]
+    pub fn kronecker_si(&self, n: i64) -> i32 {
+        unsafe { gmp::mpz_kronecker_si(&self.inner, n as c_long) as i32 }
+    }
+
+    #[inline]
+    pub fn kronecker_ui(&self, n: u64) -> i32 {
+        unsafe { gmp::mpz_kronecker_ui(&self.inner, n as c_ulong) as i32 }
+    }
+
+    /// `(a | n)` for a machine-word `a`, as when testing whether a small
+    /// prime splits in the order of discriminant `a`.
+    #[inline]
+    pub fn si_kronecker(a: i64, n: &Mpz) -> i32 {
+        unsafe { gmp::mpz_si_kronecker(a as c_long, &n.inner) as i32 }
+    }
+
+    /// The Jacobi symbol `(self | n)`. Panics unless `n` is odd and
+    /// positive.
+    #[inline]
+    pub fn jacobi(&self, n: &Mpz) -> i32 {
+        assert!(n.sgn() > 0 && n.odd() != 0, "jacobi: n must be odd and positive");
+        unsafe { gmp::mpz_jacobi(&self.inner, &n.inner) as i32 }
+    }
+
+    /// The Legendre symbol `(self | p)` for an odd prime `p`, which is not
+    /// checked.
+    #[inline]
+    pub fn legendre(&self, p: &Mpz) -> i32 {
+        self.jacobi(p)
+    }
+
+    /// `self^exp mod modulus`, in `[0, |modulus|)`. A negative `exp` raises
+    /// the inverse of `self`. Panics if `modulus` is zero, or if `exp` is
+    /// negative and `self` has no inverse.
+    pub fn powm(&self, exp: &Mpz, modulus: &Mpz) -> Mpz {
+        assert!(!modulus.is_zero(), "powm: modulus is zero");
+        if exp.is_neg() && self.invert_mod(modulus).is_none() {
+            panic!("powm: negative exponent of a non-invertible base");
+        }
+        let mut ret = Mpz::default();
+        unsafe { gmp::mpz_powm(&mut ret.inner, &self.inner, &exp.inner, &modulus.inner) };
+        ret
+    }
+
+    /// `powm` with a word exponent.
+    pub fn powm_ui(&self, exp: u64, modulus: &Mpz) -> Mpz {
+        assert!(!modulus.is_zero(), "powm: modulus is zero");
+        let mut ret = Mpz::default();
+        unsafe { gmp::mpz_powm_ui(&mut ret.inner, &self.inner, exp as c_ulong, &modulus.inner) };
+        ret
+    }
+
+    /// `powm` in time and memory access patterns that do not depend on
+    /// `self` or `exp`, for secret exponents. Panics unless `exp` is
+    /// positive and `modulus` odd.
+    pub fn powm_sec(&self, exp: &Mpz, modulus: &Mpz) -> Mpz {
+        assert!(exp.sgn() > 0, "powm_sec: exponent must be positive");
+        assert!(modulus.odd() != 0, "powm_sec: modulus must be odd");
+        let mut ret = Mpz::default();
+        unsafe { gmp::mpz_powm_sec(&mut ret.inner, &self.inner, &exp.inner, &modulus.inner) };
+        ret
+    }
+
+    /// A square root of `self` modulo the odd prime `p`, in `[0, p)`, if
+    /// `self` is a square; the other root is `p` minus this one. `p` is not
+    /// checked for primality, and the result is meaningless if it is not
+    /// prime.
+    ///
+    /// Primes `p = 3 mod 4` take one exponentiation; the rest go through
+    /// Tonelli-Shanks.
+    pub fn sqrt_mod_prime(&self, p: &Mpz) -> Option<Mpz> {
+        let powm = |base: &Mpz, exp: &Mpz| base.powm(exp, p);
+        let mut a = Mpz::default();
+        a.modulo(self, p);
+        if a.is_zero() {
+            return Some(a);
+        }
+        if a.legendre(p) != 1 {
+            return None;
+        }
+
+        let mut q = p.clone();
+        q.sub_ui_mut(1);
+        let s = unsafe { gmp::mpz_scan1(&q.inner, 0) };
+        q.tdiv_q_2exp(&q.clone(), s);
+        let mut exp = q.clone();
+        exp.add_ui_mut(1);
+        exp.tdiv_q_2exp(&exp.clone(), 1);
+        let mut r = powm(&a, &exp);
+        if s == 1 {
+            return Some(r);
+        }
+
+        let mut z = Mpz::from(2);
+        while z.legendre(p) != -1 {
+            z.add_ui_mut(1);
+        }
+        let mut c = powm(&z, &q);
+        let mut t = powm(&a, &q);
+        let mut m = s;
+        let mut t2 = Mpz::default();
+        while !t.is_one() {
+            // The least i with t^(2^i) = 1, which is below m.
+            let mut i = 0;
+            t2.set(&t);
+            while !t2.is_one() {
+                t2.square_mut();
+                t2.modulo_mut(p);
+                i += 1;
+            }
+            let mut b = c.clone();
+            for _ in 0..m - i - 1 {
+                b.square_mut();
+                b.modulo_mut(p);
+            }
+            m = i;
+            c.mul(&b, &b);
+            c.modulo_mut(p);
+            t.mul_mut(&c);
+            t.modulo_mut(p);
+            r.mul_mut(&b);
+            r.modulo_mut(p);
+        }
+        Some(r)
+    }
+
     #[inline]
     pub fn get_si(&self) -> i64 {
         unsafe { gmp::mpz_get_si(&self.inner) }
@@ -315,6 +611,20 @@ impl Mpz {
         unsafe { gmp::mpz_odd_p(&self.inner) }
     }
 
+    /// `floor(sqrt(self))`. Panics if `self` is negative.
+    pub fn isqrt(&self) -> Mpz {
+        assert!(!self.is_neg(), "isqrt: negative operand");
+        let mut ret = Mpz::default();
+        unsafe { gmp::mpz_sqrt(&mut ret.inner, &self.inner) };
+        ret
+    }
+
+    /// Whether `self` is the square of an integer; zero and one are.
+    #[inline]
+    pub fn is_perfect_square(&self) -> bool {
+        unsafe { gmp::mpz_perfect_square_p(&self.inner) != 0 }
+    }
+
     #[inline]
     pub fn root_mut(&mut self, x: u64) -> i32 {
         unsafe { gmp::mpz_root(&mut self.inner, &self.inner, x) }
@@ -377,6 +687,56 @@ impl Mpz {
         unsafe { gmp::mpz_tdiv_q_2exp(&mut self.inner, &op1.inner, op2) }
     }
 
+    // The bit operations treat negative numbers as infinite two's
+    // complement, as GMP does.
+
+    #[inline]
+    pub fn setbit(&mut self, bit: usize) {
+        unsafe { gmp::mpz_setbit(&mut self.inner, bit as gmp::bitcnt_t) }
+    }
+
+    #[inline]
+    pub fn clrbit(&mut self, bit: usize) {
+        unsafe { gmp::mpz_clrbit(&mut self.inner, bit as gmp::bitcnt_t) }
+    }
+
+    #[inline]
+    pub fn combit(&mut self, bit: usize) {
+        unsafe { gmp::mpz_combit(&mut self.inner, bit as gmp::bitcnt_t) }
+    }
+
+    #[inline]
+    pub fn testbit(&self, bit: usize) -> bool {
+        unsafe { gmp::mpz_tstbit(&self.inner, bit as gmp::bitcnt_t) != 0 }
+    }
+
+    /// The index of the first set bit at or above `start`, if there is one.
+    #[inline]
+    pub fn scan1(&self, start: usize) -> Option<usize> {
+        match unsafe { gmp::mpz_scan1(&self.inner, start as gmp::bitcnt_t) } {
+            gmp::bitcnt_t::MAX => None,
+            i => Some(i as usize),
+        }
+    }
+
+    /// The index of the first clear bit at or above `start`, if there is one.
+    #[inline]
+    pub fn scan0(&self, start: usize) -> Option<usize> {
+        match unsafe { gmp::mpz_scan0(&self.inner, start as gmp::bitcnt_t) } {
+            gmp::bitcnt_t::MAX => None,
+            i => Some(i as usize),
+        }
+    }
+
+    /// The number of set bits, which is infinite for negative numbers.
+    #[inline]
+    pub fn popcount(&self) -> Option<usize> {
+        match unsafe { gmp::mpz_popcount(&self.inner) } {
+            gmp::bitcnt_t::MAX => None,
+            n => Some(n as usize),
+        }
+    }
+
     #[inline]
     pub fn bit_length(&self) -> usize {
         unsafe { gmp::mpz_sizeinbase(&self.inner, 2) as usize }
@@ -427,24 +787,98 @@ impl Mpz {
         }
     }
 
+    /// Sets `self` to `a * b + c * d`, building the sum in `self` with no
+    /// temporary.
+    #[inline]
+    pub fn fmma(&mut self, a: &Mpz, b: &Mpz, c: &Mpz, d: &Mpz) {
+        self.mul(a, b);
+        self.add_mul(c, d);
+    }
+
+    /// Sets `self` to `a * b - c * d`, building the difference in `self`
+    /// with no temporary.
+    #[inline]
+    pub fn fmms(&mut self, a: &Mpz, b: &Mpz, c: &Mpz, d: &Mpz) {
+        self.mul(a, b);
+        self.submul(c, d);
+    }
+
+    /// Sets `self` to `x + a * b`, in the space `self` already has.
+    #[inline]
+    pub fn assign_add_mul(&mut self, x: &Mpz, a: &Mpz, b: &Mpz) {
+        self.set(x);
+        self.add_mul(a, b);
+    }
+
+    /// Sets `self` to `x - a * b`, in the space `self` already has.
+    #[inline]
+    pub fn assign_sub_mul(&mut self, x: &Mpz, a: &Mpz, b: &Mpz) {
+        self.set(x);
+        self.submul(a, b);
+    }
+
     #[inline]
     pub fn from_bytes(data: &[u8]) -> Self {
         raw_import(data)
     }
 
+    /// Big-endian bytes of the absolute value, the inverse of `from_bytes`.
+    #[inline]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        raw_export(self)
+    }
+
+    /// Minimal big-endian two's complement, the layout of num-bigint's
+    /// `BigInt::to_signed_bytes_be`, so values pass to and from `BigInt`
+    /// through `from_signed_bytes_be` without regard to sign.
+    pub fn to_signed_bytes_be(&self) -> Vec<u8> {
+        if !self.is_neg() {
+            let mut bytes = self.to_bytes();
+            if bytes.first().is_none_or(|&b| b & 0x80 != 0) {
+                bytes.insert(0, 0);
+            }
+            return bytes;
+        }
+        // `-n` is the complement of `n - 1`.
+        let mut n = self.clone();
+        n.abs_mut();
+        n.sub_ui_mut(1);
+        let mut bytes: Vec<u8> = n.to_bytes().iter().map(|b| !b).collect();
+        if bytes.first().is_none_or(|&b| b & 0x80 == 0) {
+            bytes.insert(0, 0xff);
+        }
+        bytes
+    }
+
+    /// Parses big-endian two's complement of any length, as written by
+    /// `to_signed_bytes_be` or num-bigint. No bytes at all is zero.
+    pub fn from_signed_bytes_be(bytes: &[u8]) -> Self {
+        match bytes.first() {
+            Some(&b) if b & 0x80 != 0 => {
+                let complement: Vec<u8> = bytes.iter().map(|b| !b).collect();
+                let mut x = Mpz::from_bytes(&complement);
+                x.add_ui_mut(1);
+                x.neg_mut();
+                x
+            }
+            _ => Mpz::from_bytes(bytes),
+        }
+    }
+
     #[inline]
     pub fn to_u64(&self) -> Option<u64> {
         unsafe { Some(gmp::mpz_get_ui(&self.inner)) }
     }
 
+    /// `self` as a `rug::Integer`, for rug's safe API, without copying.
     #[inline]
-    pub fn one() -> Mpz {
-        unsafe {
-            let mut mpz = std::mem::uninitialized();
-            gmp::mpz_init_set_ui(&mut mpz, 1);
+    pub fn as_integer(&self) -> BorrowInteger<'_> {
+        unsafe { BorrowInteger::from_raw(self.inner) }
+    }
 
-            Mpz { inner: mpz }
-        }
+    #[inline]
+    pub fn one() -> Mpz {
+        Mpz::from(1)
     }
 
     #[inline]
@@ -468,16 +902,27 @@ impl Mpz {
         }
     }
 
+    /// `probab_prime` with `iterations` rounds, and then the Baillie-PSW
+    /// test of `is_bpsw_prime`, so that a few rounds are enough whatever
+    /// test the linked GMP runs.
     #[inline]
     pub fn is_prime(&self, iterations: usize) -> bool {
         let is_prime = self.probab_prime(iterations as i32);
         if is_prime == ProbabPrimeResult::ProbablyPrime || is_prime == ProbabPrimeResult::Prime {
-            return true;
+            return self.is_bpsw_prime();
         }
 
         return false;
     }
 
+    /// `self mod modulus`, in `[0, modulus)` whatever the sign of `self`.
+    /// Panics if `modulus` is zero.
+    #[inline]
+    pub fn frem_u64(&self, modulus: u64) -> u64 {
+        assert!(modulus != 0, "frem_u64: division by zero");
+        unsafe { gmp::mpz_fdiv_ui(&self.inner, modulus as c_ulong) as u64 }
+    }
+
     #[inline]
     pub fn crem_u16(&self, modulus: u16) -> u16 {
         let res = unsafe { gmp::mpz_cdiv_ui(&self.inner, c_ulong::from(modulus)) };
@@ -495,6 +940,21 @@ pub fn fmpz_gcdinv(d: &mut Mpz, a: &mut Mpz, f: &Mpz, g: &Mpz) {
     
 }
 
+/// Why `Mpz::try_invert_mod` failed: `self` and `m` share the factor `gcd`,
+/// or `m` is zero and `gcd` is `|self|`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotInvertible {
+    pub gcd: Mpz,
+}
+
+impl fmt::Display for NotInvertible {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "not invertible: shares the factor {} with the modulus", self.gcd)
+    }
+}
+
+impl std::error::Error for NotInvertible {}
+
 /// The result of running probab_prime
 #[derive(PartialEq)]
 pub enum ProbabPrimeResult {
@@ -544,47 +1004,84 @@ pub fn mpz_gcdinv(d: &mut Mpz, a: &mut Mpz, f: &Mpz, g: &Mpz) {
 
 
 fn raw_import(buf: &[u8]) -> Mpz {
-    let mut obj = Mpz::default();
-
-    unsafe { 
-        gmp::mpz_import(
-            &mut obj.inner, 
-            buf.len(), 
-            1, 
-            1, 
-            1, 
-            0, 
-            buf.as_ptr() as *const _
-        ) 
-    }
-    obj
+    Mpz::from_raw_import(buf, 1, 1, 1)
 }
 
 
 /// Helper function to export Mpz to raw network bytes
 fn raw_export(raw: &Mpz) -> Vec<u8> {
-    //let mut buf = Vec::<u8>::with_capacity(raw.bit_length());
-    let mut buf = Vec::new();
-
-    unsafe {
-        let buf_ptr = buf.as_mut_ptr();
-        let mut count = std::mem::MaybeUninit::uninit();
-        let count_ptr = count.as_mut_ptr();
-
-        let ptr2 = gmp::mpz_export(
-            buf_ptr as *mut c_void,
-            count_ptr,
-            1, //countp
-            1, //size
-            1, //endian
-            0, //nails
-            &raw.inner
-        );
-        //assert_eq!(buf_ptr, ptr2);
+    raw.to_raw_export(1, 1, 1)
+}
+
+fn check_export_options(order: i32, size: usize, endian: i32) {
+    assert!(order == 1 || order == -1, "order must be 1 or -1");
+    assert!(size > 0, "words must be at least a byte");
+    assert!((-1..=1).contains(&endian), "endian must be 1, -1 or 0");
+}
+
+impl Mpz {
+    /// The absolute value as `mpz_export` writes it with no nails: `size`-byte
+    /// words, most significant first for `order` 1 and least for -1, each
+    /// word big-endian for `endian` 1, little for -1 and native for 0. As in
+    /// GMP the sign is dropped, and zero is no words.
+    pub fn to_raw_export(&self, order: i32, size: usize, endian: i32) -> Vec<u8> {
+        check_export_options(order, size, endian);
+        let words = self.bit_length().div_ceil(8 * size);
+        let mut buf = vec![0u8; words * size];
+        let mut count = 0;
+
+        unsafe {
+            gmp::mpz_export(
+                buf.as_mut_ptr() as *mut c_void,
+                &mut count,
+                order,
+                size,
+                endian,
+                0, //nails
+                &self.inner,
+            );
+        }
+
+        buf.truncate(count * size);
+        buf
     }
 
-    println!("exbuf: {:?}", buf);
-    buf
+    /// Reads the words of `to_raw_export` with the same options, as
+    /// `mpz_import` would. `data` must be a whole number of words.
+    pub fn from_raw_import(data: &[u8], order: i32, size: usize, endian: i32) -> Self {
+        check_export_options(order, size, endian);
+        assert!(data.len() % size == 0, "data is not a whole number of words");
+        let mut obj = Mpz::default();
+
+        unsafe {
+            gmp::mpz_import(
+                &mut obj.inner,
+                data.len() / size,
+                order,
+                size,
+                endian,
+                0, //nails
+                data.as_ptr() as *const _
+            )
+        }
+        obj
+    }
+
+    /// Sets `self` to the big-endian magnitude `bytes`, as `from_bytes`
+    /// reads it, in the space `self` already has.
+    pub fn assign_from_slice(&mut self, bytes: &[u8]) {
+        unsafe {
+            gmp::mpz_import(
+                &mut self.inner,
+                bytes.len(),
+                1,
+                1,
+                1,
+                0,
+                bytes.as_ptr() as *const _,
+            )
+        }
+    }
 }
 
 // pub fn raw_export(raw: &Mpz) -> Vec<u8> {
@@ -609,17 +1106,343 @@ mod test {
 
     #[test]
     fn test_import_export() {
-        // let mut obj = Mpz::default();
-        // let ex = raw_export(&obj);
-        // let im = raw_import(&ex);
-        // assert_eq!(im, obj);
-
-        // let mut obj = Mpz::default();
-        // obj.set_ui(55);
-        // println!("ex: {:?}", obj);
-        // let ex = raw_export(&obj);
-        // println!("ex2: {:?}", ex);
-        // let im = raw_import(&ex);
-        // assert_eq!(im, obj);
-    }
-}
\ No newline at end of file
+        let obj = Mpz::default();
+        let ex = raw_export(&obj);
+        assert!(ex.is_empty());
+        let im = raw_import(&ex);
+        assert_eq!(im, obj);
+
+        let mut obj = Mpz::default();
+        obj.set_ui(55);
+        let ex = raw_export(&obj);
+        assert_eq!(ex, vec![55]);
+        let im = raw_import(&ex);
+        assert_eq!(im, obj);
+
+        let obj = Mpz::from_str("340282366920938463463374607431768211457").unwrap();
+        let ex = obj.to_bytes();
+        assert_eq!(ex.len(), 17);
+        assert_eq!(Mpz::from_bytes(&ex), obj);
+    }
+
+    #[test]
+    fn test_xgcd() {
+        for &(a, b) in &[("240", "46"), ("-35", "15"), ("17", "0"), ("0", "0"), ("12345678", "87654321")] {
+            let (a, b) = (Mpz::from_str(a).unwrap(), Mpz::from_str(b).unwrap());
+            let (g, s, t) = Mpz::xgcd(&a, &b);
+            let mut expected = Mpz::default();
+            expected.gcd(&a, &b);
+            assert_eq!(g, expected);
+            let mut sum = Mpz::default();
+            sum.mul(&s, &a);
+            sum.add_mul(&t, &b);
+            assert_eq!(sum, g);
+        }
+        let inv = Mpz::from(3).invert_mod(&Mpz::from(7)).unwrap();
+        assert_eq!(inv, Mpz::from(5));
+        assert!(Mpz::from(6).invert_mod(&Mpz::from(9)).is_none());
+        assert_eq!(
+            Mpz::from(6).try_invert_mod(&Mpz::from(9)),
+            Err(NotInvertible { gcd: Mpz::from(3) })
+        );
+        assert!(Mpz::from(6).invert_mod(&Mpz::zero()).is_none());
+    }
+
+    #[test]
+    fn test_kronecker() {
+        // Euler's criterion for odd primes.
+        for &p in &[3i64, 5, 7, 11, 13, 101] {
+            let pz = Mpz::from(p as u64);
+            for a in -20i64..20 {
+                let mut x = Mpz::default();
+                x.set_si(a);
+                let euler = x.powm_ui(((p - 1) / 2) as u64, &pz);
+                let expected = match euler.get_si() {
+                    0 => 0,
+                    1 => 1,
+                    _ => -1,
+                };
+                x.set_si(a);
+                assert_eq!(x.legendre(&pz), expected);
+                assert_eq!(x.kronecker(&pz), expected);
+                assert_eq!(x.kronecker_si(p), expected);
+                assert_eq!(x.kronecker_ui(p as u64), expected);
+                assert_eq!(Mpz::si_kronecker(a, &pz), expected);
+            }
+        }
+        // (a | 2) depends on a mod 8, and (a | -1) on the sign of a.
+        for &(a, expected) in &[(1, 1), (3, -1), (5, -1), (7, 1), (4, 0)] {
+            assert_eq!(Mpz::from(a).kronecker_si(2), expected);
+        }
+        let mut minus_three = Mpz::default();
+        minus_three.set_si(-3);
+        assert_eq!(minus_three.kronecker_si(-1), -1);
+        assert_eq!(Mpz::from(3).kronecker_si(-1), 1);
+        assert_eq!(Mpz::from(2).jacobi(&Mpz::from(15)), 1);
+    }
+
+    #[test]
+    fn test_sqrt_mod_prime() {
+        // 3, 5 and 1 mod 8, the last two through Tonelli-Shanks, and a
+        // prime with a large power of two in p - 1.
+        let primes = ["7", "13", "17", "41", "257", "65537", "3221225473"];
+        for p in primes.iter().map(|p| Mpz::from_str(p).unwrap()) {
+            let mut squares = 0;
+            for a in 0u64..200 {
+                let a = Mpz::from(a);
+                match a.sqrt_mod_prime(&p) {
+                    Some(r) => {
+                        assert!(r < p);
+                        let (mut lhs, mut rhs) = (Mpz::default(), Mpz::default());
+                        lhs.mul(&r, &r);
+                        lhs.modulo_mut(&p);
+                        rhs.modulo(&a, &p);
+                        assert_eq!(lhs, rhs);
+                        squares += 1;
+                    }
+                    None => assert_eq!(a.legendre(&p), -1),
+                }
+            }
+            assert!(squares > 0 && squares < 200);
+        }
+        let mut minus_one = Mpz::default();
+        minus_one.set_si(-1);
+        assert_eq!(minus_one.sqrt_mod_prime(&Mpz::from(7)), None);
+        assert!(minus_one.sqrt_mod_prime(&Mpz::from(13)).is_some());
+    }
+
+    #[test]
+    fn test_checked_divexact() {
+        let a = Mpz::from_str("-340282366920938463463374607431768211456").unwrap();
+        let b = Mpz::from(1024);
+        let q = a.checked_divexact(&b).unwrap();
+        let mut expected = Mpz::default();
+        expected.divexact(&a, &b);
+        assert_eq!(q, expected);
+        assert_eq!(q, Mpz::from_str("-332306998946228968225951765070086144").unwrap());
+        assert_eq!(a.checked_divexact(&Mpz::from(3)), None);
+        assert_eq!(a.checked_divexact(&Mpz::zero()), None);
+        assert_eq!(Mpz::zero().checked_divexact(&b), Some(Mpz::zero()));
+        assert!(Mpz::zero().is_divisible(&Mpz::zero()) && !b.is_divisible(&Mpz::zero()));
+    }
+
+    #[test]
+    fn test_word_ops() {
+        let x = Mpz::from_str("-340282366920938463463374607431768211457").unwrap();
+        let m = 1_000_000_007u64;
+        let mut expected = Mpz::default();
+        expected.modulo(&x, &Mpz::from(m));
+        assert_eq!(Mpz::from(x.frem_u64(m)), expected);
+        assert_eq!(Mpz::from(7).frem_u64(u64::MAX), 7);
+
+        let mut acc = Mpz::from(5);
+        acc.addmul_ui(&x, u64::MAX);
+        let mut check = Mpz::default();
+        check.mul(&x, &Mpz::from(u64::MAX));
+        check.add_ui_mut(5);
+        assert_eq!(acc, check);
+        acc.submul_ui(&x, u64::MAX);
+        assert_eq!(acc, Mpz::from(5));
+    }
+
+    #[test]
+    fn test_bits() {
+        let mut x = Mpz::zero();
+        x.setbit(3);
+        x.setbit(70);
+        assert_eq!(x.popcount(), Some(2));
+        assert!(x.testbit(70) && !x.testbit(69));
+        assert_eq!(x.scan1(0), Some(3));
+        assert_eq!(x.scan1(4), Some(70));
+        assert_eq!(x.scan1(71), None);
+        assert_eq!(x.scan0(3), Some(4));
+        x.clrbit(70);
+        x.combit(0);
+        assert_eq!(x, Mpz::from(9));
+
+        // -8 is ...11111000.
+        let mut neg = Mpz::default();
+        neg.set_si(-8);
+        assert_eq!(neg.scan1(0), Some(3));
+        assert_eq!(neg.scan0(3), None);
+        assert_eq!(neg.popcount(), None);
+        assert!(neg.testbit(1000));
+    }
+
+    #[test]
+    fn test_assign() {
+        let (x, a, b) = (Mpz::from(100), Mpz::from(7), Mpz::from(9));
+        let mut r = Mpz::from_str("340282366920938463463374607431768211457").unwrap();
+        r.assign_add_mul(&x, &a, &b);
+        assert_eq!(r, Mpz::from(163));
+        r.assign_sub_mul(&x, &a, &b);
+        assert_eq!(r, Mpz::from(37));
+        r.fmma(&x, &a, &b, &b);
+        assert_eq!(r, Mpz::from(781));
+        r.fmms(&x, &a, &b, &b);
+        assert_eq!(r, Mpz::from(619));
+
+        let bytes = [0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01];
+        r.assign_from_slice(&bytes);
+        assert_eq!(r, Mpz::from_bytes(&bytes));
+        r.assign_from_slice(&[]);
+        assert!(r.is_zero());
+    }
+
+    #[test]
+    fn test_integer_conversions() {
+        for s in &["0", "-1", "340282366920938463463374607431768211457"] {
+            let (x, i) = (Mpz::from_str(s).unwrap(), Integer::from_str(s).unwrap());
+            assert_eq!(*x.as_integer(), i);
+            assert_eq!(Integer::from(&x), i);
+            assert_eq!(Mpz::from(i.clone()), x);
+            assert_eq!(Integer::from(x), i);
+        }
+    }
+
+    #[test]
+    fn test_threads() {
+        // Shared operands read from many threads at once, and results
+        // moved back out.
+        let x = Mpz::from_str("340282366920938463463374607431768211457").unwrap();
+        let results: Vec<Mpz> = std::thread::scope(|s| {
+            let handles: Vec<_> = (1..=8u64)
+                .map(|i| {
+                    let x = &x;
+                    s.spawn(move || {
+                        let mut acc = Mpz::from(i);
+                        for _ in 0..1000 {
+                            acc.mul_mut(x);
+                            acc.modulo_mut(&Mpz::from(1_000_000_007));
+                        }
+                        acc
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        let expected = x.powm_ui(1000, &Mpz::from(1_000_000_007));
+        for (i, r) in (1..=8u64).zip(&results) {
+            let mut e = Mpz::default();
+            e.mul_ui(&expected, i);
+            e.modulo_mut(&Mpz::from(1_000_000_007));
+            assert_eq!(*r, e);
+        }
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut x = Mpz::from_str("340282366920938463463374607431768211457").unwrap();
+        x.reserve_bits(4096);
+        assert!(x.capacity_bits() >= 4096);
+        assert_eq!(x, Mpz::from_str("340282366920938463463374607431768211457").unwrap());
+        let capacity = x.capacity_bits();
+        x.reserve_bits(8);
+        assert_eq!(x.capacity_bits(), capacity);
+        assert!(Mpz::with_capacity_bits(2048).capacity_bits() >= 2048);
+    }
+
+    #[test]
+    fn test_powm() {
+        let m = Mpz::from(1_000_000_007);
+        let x = Mpz::from_str("340282366920938463463374607431768211457").unwrap();
+        let e = Mpz::from(65537);
+        let expected = x.powm_ui(65537, &m);
+        assert_eq!(x.powm(&e, &m), expected);
+        assert_eq!(x.powm_sec(&e, &m), expected);
+
+        // Fermat, and a negative exponent through the inverse.
+        assert_eq!(x.powm(&Mpz::from(1_000_000_006), &m), Mpz::one());
+        let mut minus_one = Mpz::default();
+        minus_one.set_si(-1);
+        assert_eq!(x.powm(&minus_one, &m), x.invert_mod(&m).unwrap());
+        assert!(std::panic::catch_unwind(|| Mpz::from(6).powm(&minus_one, &Mpz::from(9))).is_err());
+        assert!(std::panic::catch_unwind(|| x.powm(&e, &Mpz::zero())).is_err());
+    }
+
+    #[test]
+    fn test_isqrt() {
+        for n in 0u64..1000 {
+            let r = Mpz::from(n).isqrt().to_u64().unwrap();
+            assert!(r * r <= n && (r + 1) * (r + 1) > n);
+            assert_eq!(Mpz::from(n).is_perfect_square(), r * r == n);
+        }
+        let mut x = Mpz::from_str("340282366920938463463374607431768211457").unwrap();
+        let r = x.isqrt();
+        assert_eq!(r, Mpz::from_str("18446744073709551616").unwrap());
+        x.sub_ui_mut(1);
+        assert!(x.is_perfect_square());
+        let mut minus = Mpz::default();
+        minus.set_si(-4);
+        assert!(!minus.is_perfect_square());
+        assert!(std::panic::catch_unwind(|| minus.isqrt()).is_err());
+    }
+
+    #[test]
+    fn test_raw_export_options() {
+        let x = Mpz::from_str("0x0102030405").unwrap();
+        for &(order, size, endian, ref bytes) in &[
+            (1, 1, 1, vec![1, 2, 3, 4, 5]),
+            (-1, 1, 1, vec![5, 4, 3, 2, 1]),
+            (1, 4, 1, vec![0, 0, 0, 1, 2, 3, 4, 5]),
+            (-1, 4, -1, vec![5, 4, 3, 2, 1, 0, 0, 0]),
+            (-1, 2, 1, vec![4, 5, 2, 3, 0, 1]),
+        ] {
+            assert_eq!(x.to_raw_export(order, size, endian), *bytes);
+            assert_eq!(Mpz::from_raw_import(bytes, order, size, endian), x);
+        }
+        assert!(Mpz::zero().to_raw_export(-1, 8, 0).is_empty());
+    }
+
+    #[test]
+    fn test_signed_bytes() {
+        // The same bytes num-bigint gives.
+        for (s, bytes) in &[
+            ("0", &[0][..]),
+            ("127", &[0x7f]),
+            ("128", &[0x00, 0x80]),
+            ("-1", &[0xff]),
+            ("-128", &[0x80]),
+            ("-129", &[0xff, 0x7f]),
+            ("-256", &[0xff, 0x00]),
+        ] {
+            let x = Mpz::from_str(s).unwrap();
+            assert_eq!(x.to_signed_bytes_be(), *bytes);
+            assert_eq!(Mpz::from_signed_bytes_be(bytes), x);
+        }
+        assert_eq!(Mpz::from_signed_bytes_be(&[]), Mpz::zero());
+        assert_eq!(Mpz::from_signed_bytes_be(&[0xff, 0xff, 0x80]), Mpz::from_str("-128").unwrap());
+    }
+
+    #[test]
+    fn test_hash_limbs() {
+        use std::collections::hash_map::DefaultHasher;
+        let hash = |x: &Mpz| {
+            let mut hasher = DefaultHasher::new();
+            x.hash(&mut hasher);
+            hasher.finish()
+        };
+        // Equal values in separate allocations, over several limbs.
+        let x = Mpz::from_str("340282366920938463463374607431768211457").unwrap();
+        let mut y = Mpz::with_capacity_bits(4096);
+        y.set(&x);
+        assert_eq!(hash(&x), hash(&y));
+        y.add_ui_mut(1);
+        assert_ne!(hash(&x), hash(&y));
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(Mpz::from_str("-0xff").unwrap(), Mpz::from_str("-255").unwrap());
+        for s in &["0xzz", "0x", "-0x-1", "12a", "", "0x1\0"] {
+            assert_eq!(Mpz::from_str(s), Err(ParseMpzError), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        for s in &["0", "55", "-7", "340282366920938463463374607431768211457"] {
+            assert_eq!(Mpz::from_str(s).unwrap().to_string(), *s);
+        }
+    }
+}
@@ -0,0 +1,340 @@
+//! Arithmetic operators on `Mpz`, for code that would rather write `x + y`
+//! than call the `gmp` wrappers. Either operand may be owned or borrowed,
+//! and the right one may also be a `u64` or `i64`. Iterators of either
+//! kind of operand can be summed and multiplied out.
+//!
+//! Division and remainder truncate toward zero, as they do for Rust's
+//! primitive integers, and dividing by zero panics rather than letting GMP
+//! abort the process.
+
+use super::Mpz;
+use gmp_mpfr_sys::gmp;
+use std::iter::{Product, Sum};
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Shl, ShlAssign, Shr,
+    ShrAssign, Sub, SubAssign,
+};
+use std::os::raw::{c_long, c_ulong};
+
+fn check_divisor(d: &Mpz) {
+    if d.is_zero() {
+        panic!("attempt to divide by zero");
+    }
+}
+
+/// Implements `$trait` and `$assign_trait` for every mix of owned and
+/// borrowed operands with the GMP function `$f(rop, op1, op2)`. Owned
+/// left operands are reused for the result.
+macro_rules! impl_op {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign:ident, $f:path, $check:expr) => {
+        impl $assign_trait<&Mpz> for Mpz {
+            fn $assign(&mut self, rhs: &Mpz) {
+                let check: fn(&Mpz) = $check;
+                check(rhs);
+                unsafe { $f(&mut self.inner, &self.inner, &rhs.inner) };
+            }
+        }
+
+        impl $assign_trait for Mpz {
+            fn $assign(&mut self, rhs: Mpz) {
+                $assign_trait::$assign(self, &rhs);
+            }
+        }
+
+        impl $trait<&Mpz> for &Mpz {
+            type Output = Mpz;
+
+            fn $method(self, rhs: &Mpz) -> Mpz {
+                let check: fn(&Mpz) = $check;
+                check(rhs);
+                let mut ret = Mpz::default();
+                unsafe { $f(&mut ret.inner, &self.inner, &rhs.inner) };
+                ret
+            }
+        }
+
+        impl $trait<&Mpz> for Mpz {
+            type Output = Mpz;
+
+            fn $method(mut self, rhs: &Mpz) -> Mpz {
+                $assign_trait::$assign(&mut self, rhs);
+                self
+            }
+        }
+
+        impl $trait<Mpz> for &Mpz {
+            type Output = Mpz;
+
+            fn $method(self, rhs: Mpz) -> Mpz {
+                $trait::$method(self, &rhs)
+            }
+        }
+
+        impl $trait for Mpz {
+            type Output = Mpz;
+
+            fn $method(self, rhs: Mpz) -> Mpz {
+                $trait::$method(self, &rhs)
+            }
+        }
+    };
+}
+
+impl_op!(Add, add, AddAssign, add_assign, gmp::mpz_add, |_| ());
+impl_op!(Sub, sub, SubAssign, sub_assign, gmp::mpz_sub, |_| ());
+impl_op!(Mul, mul, MulAssign, mul_assign, gmp::mpz_mul, |_| ());
+impl_op!(
+    Div,
+    div,
+    DivAssign,
+    div_assign,
+    gmp::mpz_tdiv_q,
+    check_divisor
+);
+impl_op!(
+    Rem,
+    rem,
+    RemAssign,
+    rem_assign,
+    gmp::mpz_tdiv_r,
+    check_divisor
+);
+
+// Word operands, through GMP's `_ui` functions with the sign handled here.
+
+fn check_word(d: u64) {
+    if d == 0 {
+        panic!("attempt to divide by zero");
+    }
+}
+
+unsafe fn add_u64(rop: gmp::mpz_ptr, op: gmp::mpz_srcptr, v: u64) {
+    gmp::mpz_add_ui(rop, op, v as c_ulong)
+}
+
+unsafe fn sub_u64(rop: gmp::mpz_ptr, op: gmp::mpz_srcptr, v: u64) {
+    gmp::mpz_sub_ui(rop, op, v as c_ulong)
+}
+
+unsafe fn mul_u64(rop: gmp::mpz_ptr, op: gmp::mpz_srcptr, v: u64) {
+    gmp::mpz_mul_ui(rop, op, v as c_ulong)
+}
+
+unsafe fn div_u64(rop: gmp::mpz_ptr, op: gmp::mpz_srcptr, v: u64) {
+    check_word(v);
+    gmp::mpz_tdiv_q_ui(rop, op, v as c_ulong);
+}
+
+unsafe fn rem_u64(rop: gmp::mpz_ptr, op: gmp::mpz_srcptr, v: u64) {
+    check_word(v);
+    gmp::mpz_tdiv_r_ui(rop, op, v as c_ulong);
+}
+
+unsafe fn add_i64(rop: gmp::mpz_ptr, op: gmp::mpz_srcptr, v: i64) {
+    match v < 0 {
+        true => sub_u64(rop, op, v.unsigned_abs()),
+        false => add_u64(rop, op, v as u64),
+    }
+}
+
+unsafe fn sub_i64(rop: gmp::mpz_ptr, op: gmp::mpz_srcptr, v: i64) {
+    match v < 0 {
+        true => add_u64(rop, op, v.unsigned_abs()),
+        false => sub_u64(rop, op, v as u64),
+    }
+}
+
+unsafe fn mul_i64(rop: gmp::mpz_ptr, op: gmp::mpz_srcptr, v: i64) {
+    gmp::mpz_mul_si(rop, op, v as c_long)
+}
+
+unsafe fn div_i64(rop: gmp::mpz_ptr, op: gmp::mpz_srcptr, v: i64) {
+    div_u64(rop, op, v.unsigned_abs());
+    if v < 0 {
+        gmp::mpz_neg(rop, rop);
+    }
+}
+
+// A truncated remainder takes the sign of the dividend only.
+unsafe fn rem_i64(rop: gmp::mpz_ptr, op: gmp::mpz_srcptr, v: i64) {
+    rem_u64(rop, op, v.unsigned_abs())
+}
+
+/// Implements `$trait` and `$assign_trait` with a right operand of the
+/// primitive `$prim`, with `$f(rop, op, word)`.
+macro_rules! impl_word_op {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign:ident, $prim:ty, $f:path) => {
+        impl $assign_trait<$prim> for Mpz {
+            fn $assign(&mut self, rhs: $prim) {
+                unsafe { $f(&mut self.inner, &self.inner, rhs) };
+            }
+        }
+
+        impl $trait<$prim> for &Mpz {
+            type Output = Mpz;
+
+            fn $method(self, rhs: $prim) -> Mpz {
+                let mut ret = Mpz::default();
+                unsafe { $f(&mut ret.inner, &self.inner, rhs) };
+                ret
+            }
+        }
+
+        impl $trait<$prim> for Mpz {
+            type Output = Mpz;
+
+            fn $method(mut self, rhs: $prim) -> Mpz {
+                $assign_trait::$assign(&mut self, rhs);
+                self
+            }
+        }
+    };
+}
+
+impl_word_op!(Add, add, AddAssign, add_assign, u64, add_u64);
+impl_word_op!(Sub, sub, SubAssign, sub_assign, u64, sub_u64);
+impl_word_op!(Mul, mul, MulAssign, mul_assign, u64, mul_u64);
+impl_word_op!(Div, div, DivAssign, div_assign, u64, div_u64);
+impl_word_op!(Rem, rem, RemAssign, rem_assign, u64, rem_u64);
+impl_word_op!(Add, add, AddAssign, add_assign, i64, add_i64);
+impl_word_op!(Sub, sub, SubAssign, sub_assign, i64, sub_i64);
+impl_word_op!(Mul, mul, MulAssign, mul_assign, i64, mul_i64);
+impl_word_op!(Div, div, DivAssign, div_assign, i64, div_i64);
+impl_word_op!(Rem, rem, RemAssign, rem_assign, i64, rem_i64);
+
+impl Neg for &Mpz {
+    type Output = Mpz;
+
+    fn neg(self) -> Mpz {
+        let mut ret = Mpz::default();
+        Mpz::neg(&mut ret, self);
+        ret
+    }
+}
+
+impl Neg for Mpz {
+    type Output = Mpz;
+
+    fn neg(mut self) -> Mpz {
+        self.neg_mut();
+        self
+    }
+}
+
+/// Implements the shift `$trait` and `$assign_trait` by a bit count with
+/// `$f(rop, op, bits)`.
+macro_rules! impl_shift {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign:ident, $f:path) => {
+        impl $trait<usize> for &Mpz {
+            type Output = Mpz;
+
+            fn $method(self, bits: usize) -> Mpz {
+                let mut ret = Mpz::default();
+                unsafe { $f(&mut ret.inner, &self.inner, bits as gmp::bitcnt_t) };
+                ret
+            }
+        }
+
+        impl $trait<usize> for Mpz {
+            type Output = Mpz;
+
+            fn $method(mut self, bits: usize) -> Mpz {
+                self.$assign(bits);
+                self
+            }
+        }
+
+        impl $assign_trait<usize> for Mpz {
+            fn $assign(&mut self, bits: usize) {
+                unsafe { $f(&mut self.inner, &self.inner, bits as gmp::bitcnt_t) };
+            }
+        }
+    };
+}
+
+// Right shifts round toward negative infinity, as `>>` does on `i64`.
+impl_shift!(Shl, shl, ShlAssign, shl_assign, gmp::mpz_mul_2exp);
+impl_shift!(Shr, shr, ShrAssign, shr_assign, gmp::mpz_fdiv_q_2exp);
+
+impl Sum for Mpz {
+    fn sum<I: Iterator<Item = Mpz>>(iter: I) -> Mpz {
+        iter.fold(Mpz::zero(), |acc, x| acc + x)
+    }
+}
+
+impl<'a> Sum<&'a Mpz> for Mpz {
+    fn sum<I: Iterator<Item = &'a Mpz>>(iter: I) -> Mpz {
+        iter.fold(Mpz::zero(), |acc, x| acc + x)
+    }
+}
+
+impl Product for Mpz {
+    fn product<I: Iterator<Item = Mpz>>(iter: I) -> Mpz {
+        iter.fold(Mpz::one(), |acc, x| acc * x)
+    }
+}
+
+impl<'a> Product<&'a Mpz> for Mpz {
+    fn product<I: Iterator<Item = &'a Mpz>>(iter: I) -> Mpz {
+        iter.fold(Mpz::one(), |acc, x| acc * x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_ops() {
+        let m = |s| Mpz::from_str(s).unwrap();
+        assert_eq!(m("7") + m("-10"), m("-3"));
+        assert_eq!(&m("7") - &m("10"), m("-3"));
+        assert_eq!(m("-7") * m("6"), m("-42"));
+        // Truncating, like `-7 / 2 == -3` and `-7 % 2 == -1` on `i64`.
+        assert_eq!(m("-7") / m("2"), m("-3"));
+        assert_eq!(m("-7") % m("2"), m("-1"));
+        assert_eq!(-m("5"), m("-5"));
+        assert!(std::panic::catch_unwind(|| m("1") / Mpz::zero()).is_err());
+
+        // Every mix of owned and borrowed operands, and words.
+        let (a, b) = (m("-17"), m("5"));
+        assert_eq!(a.clone() * &b, m("-85"));
+        assert_eq!(&a / b.clone(), m("-3"));
+        let mut x = a.clone();
+        x += &b;
+        x -= b.clone();
+        x *= m("2");
+        x %= &m("5");
+        assert_eq!(x, m("-4"));
+        assert_eq!(&a + 20u64, m("3"));
+        assert_eq!(&a + -3i64, m("-20"));
+        assert_eq!(a.clone() - -20i64, m("3"));
+        assert_eq!(&a * -2i64, m("34"));
+        assert_eq!(&a / 5u64, m("-3"));
+        assert_eq!(&a / -5i64, m("3"));
+        assert_eq!(&a % 5u64, m("-2"));
+        assert_eq!(&a % -5i64, m("-2"));
+        let mut w = m("-18446744073709551615");
+        w += u64::MAX;
+        w -= i64::MIN;
+        assert_eq!(w, m("9223372036854775808"));
+        assert!(std::panic::catch_unwind(|| &a / 0u64).is_err());
+        assert!(std::panic::catch_unwind(|| &a % 0i64).is_err());
+
+        assert_eq!(&m("3") << 100, m("3802951800684688204490109616128"));
+        assert_eq!(m("-7") >> 1, m("-4"));
+        let mut x = m("1");
+        x <<= 65;
+        x >>= 64;
+        assert_eq!(x, m("2"));
+
+        let primes = [m("2"), m("3"), m("5"), m("-7")];
+        assert_eq!(primes.iter().product::<Mpz>(), m("-210"));
+        assert_eq!(primes.iter().sum::<Mpz>(), m("3"));
+        assert_eq!(primes.iter().cloned().product::<Mpz>(), m("-210"));
+        assert_eq!(primes.iter().cloned().sum::<Mpz>(), m("3"));
+        assert_eq!(std::iter::empty::<Mpz>().product::<Mpz>(), m("1"));
+        assert_eq!(std::iter::empty::<&Mpz>().sum::<Mpz>(), m("0"));
+    }
+}
@@ -58,13 +58,8 @@ impl PartialGCDContext {
             let mut _t = r2.bit_length();
             let mut _t1 = r1.bit_length();
 
-            let mut bits = ((std::cmp::max(_t, _t1)) - (gmp::LIMB_BITS as usize) + 1) as u64;
-            //println!("bits: {:?}", bits);
-
-            //Bits
-            if bits < 0 {
-                bits = 0;
-            }
+            // Clamp at zero when both remainders already fit in a limb.
+            let bits = (std::cmp::max(_t, _t1) + 1).saturating_sub(gmp::LIMB_BITS as usize) as u64;
 
             self.r.tdiv_q_2exp(&r2, bits);
             let mut rr2 = self.r.get_si();
@@ -129,8 +124,8 @@ impl PartialGCDContext {
 
             if _index == 0 {
                 // multiprecision step
-                let tmp = r2.clone();
-                self.q.fdiv_qr(r2, &tmp, &r1); //i r2 is taken here what we do
+                self.q.fdiv_qr(&mut self.t1, r2, r1);
+                r2.swap(&mut self.t1);
                 r2.swap(r1);
                 c2.sub_mul(&c1, &self.q);
                 c2.swap(c1);
@@ -177,11 +172,55 @@ impl PartialGCDContext {
     } //function end
 }
 
+/// Runs the partial Euclidean algorithm on `(r2, r1)` until the remainder
+/// is at most `bound`, and returns the last two remainders and cofactors
+/// `(r2, r1, c2, c1)`, with `c2 * r1 - c1 * r2 = ±r2_orig`.
+///
+/// Allocates a fresh `PartialGCDContext`; loops should keep one of their
+/// own and call `PartialGCDContext::xgcd_partial`.
+pub fn xgcd_partial(r2: &Mpz, r1: &Mpz, bound: &Mpz) -> (Mpz, Mpz, Mpz, Mpz) {
+    let (mut r2, mut r1) = (r2.clone(), r1.clone());
+    let (mut c2, mut c1) = (Mpz::default(), Mpz::default());
+    PartialGCDContext::default().xgcd_partial(&mut c2, &mut c1, &mut r2, &mut r1, bound);
+    (r2, r1, c2, c1)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::num::rand;
 
+    /// One quotient at a time, straight from the definition.
+    fn naive(r2: &Mpz, r1: &Mpz, bound: &Mpz) -> (Mpz, Mpz, Mpz, Mpz) {
+        let (mut r2, mut r1) = (r2.clone(), r1.clone());
+        let (mut c2, mut c1) = (Mpz::zero(), Mpz::default());
+        c1.set_si(-1);
+        let (mut q, mut r) = (Mpz::default(), Mpz::default());
+        while !r1.is_zero() && r1 > *bound {
+            q.fdiv_qr(&mut r, &r2, &r1);
+            r2 = std::mem::replace(&mut r1, r.clone());
+            c2.sub_mul(&c1, &q);
+            std::mem::swap(&mut c2, &mut c1);
+        }
+        (r2, r1, c2, c1)
+    }
+
+    #[test]
+    fn test_against_naive() {
+        let mut rand_state = rand::randinit();
+        let (mut a, mut b, mut bound) = (Mpz::default(), Mpz::default(), Mpz::default());
+        for bits in &[20, 64, 200, 1000] {
+            for _ in 0..50 {
+                unsafe {
+                    gmp::mpz_urandomb(&mut a.inner, &mut rand_state.gmp, *bits);
+                    gmp::mpz_urandomm(&mut b.inner, &mut rand_state.gmp, &a.inner);
+                    gmp::mpz_urandomb(&mut bound.inner, &mut rand_state.gmp, *bits / 2);
+                }
+                assert_eq!(xgcd_partial(&a, &b, &bound), naive(&a, &b, &bound));
+            }
+        }
+    }
+
     #[test]
     fn test_partial_gcd() {
         let mut context = PartialGCDContext::default();
@@ -0,0 +1,73 @@
+//! A free list of `Mpz` values.
+//!
+//! Code that makes many short-lived integers can hand them back to an
+//! `MpzPool` instead of dropping them, and take them out again later with
+//! their limb buffers still allocated. Once the pool holds as many values
+//! as are live at once, and each has grown to the size it needs, taking and
+//! putting no longer touch the allocator.
+
+use super::Mpz;
+
+/// Recycled `Mpz` values, each handed out as zero.
+#[derive(Debug, Default)]
+pub struct MpzPool {
+    free: Vec<Mpz>,
+    bits: usize,
+}
+
+impl MpzPool {
+    /// An empty pool whose newly made values have room for `bits` bits.
+    pub fn with_capacity_bits(bits: usize) -> Self {
+        Self {
+            free: Vec::new(),
+            bits,
+        }
+    }
+
+    /// A zero, reusing a value put back earlier if there is one.
+    pub fn take(&mut self) -> Mpz {
+        match self.free.pop() {
+            Some(mut x) => {
+                x.set_ui(0);
+                x
+            }
+            None => Mpz::with_capacity_bits(self.bits),
+        }
+    }
+
+    /// Keeps `x`, and its allocation, for a later `take`.
+    pub fn put(&mut self, x: Mpz) {
+        self.free.push(x);
+    }
+
+    /// The number of values waiting to be taken.
+    pub fn len(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.free.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool() {
+        let mut pool = MpzPool::with_capacity_bits(256);
+        let mut x = pool.take();
+        assert!(x.is_zero());
+        assert!(x.capacity_bits() >= 256);
+        x.set_ui(7);
+        x.reserve_bits(4096);
+        pool.put(x);
+        assert_eq!(pool.len(), 1);
+
+        let y = pool.take();
+        assert!(y.is_zero());
+        assert!(y.capacity_bits() >= 4096);
+        assert!(pool.is_empty());
+    }
+}
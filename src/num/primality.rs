@@ -0,0 +1,259 @@
+//! The Baillie-PSW probable prime test on `Mpz`, as `hash::primality`
+//! does it for `U256`: trial division, a strong Fermat test to base 2 and a
+//! strong Lucas test with Selfridge's parameters. No composite is known to
+//! pass it, and none exist below 2^64.
+//!
+//! Trial division runs over a table of the primes below 2^16 that the build
+//! script generates, and is exposed as `trial_division` so that callers
+//! testing many random candidates can throw most of them away before any
+//! modular exponentiation.
+//!
+//! `next_prime` also holds GMP's candidates to that test. `prev_prime`
+//! walks down the odd numbers itself, as `mpz_prevprime` is missing before
+//! GMP 6.3.
+
+use super::{lucas_sequence, Mpz};
+use gmp_mpfr_sys::gmp;
+use std::cmp::Ordering;
+use std::os::raw::c_ulong;
+
+include!(concat!(env!("OUT_DIR"), "/primes.rs"));
+
+/// The trial division bound of `is_bpsw_prime`, and a reasonable one for
+/// sieving random candidates of a few hundred bits.
+pub const TRIAL_DIVISION_BOUND: u64 = 1000;
+
+impl Mpz {
+    /// The least prime `p <= bound` that divides `self` and is less than
+    /// `|self|`, so `Some` means `self` is composite. Only the primes below
+    /// 2^16 are tried, however large `bound` is; zero and units give `None`.
+    pub fn trial_division(&self, bound: u64) -> Option<u64> {
+        let primes = PRIMES.iter().map(|&p| u64::from(p));
+        for p in primes.take_while(|&p| p <= bound) {
+            let word = p as c_ulong;
+            if unsafe {
+                gmp::mpz_divisible_ui_p(&self.inner, word) != 0
+                    && gmp::mpz_cmpabs_ui(&self.inner, word) > 0
+            } {
+                return Some(p);
+            }
+        }
+        None
+    }
+
+    /// Whether `self` passes the Baillie-PSW test.
+    pub fn is_bpsw_prime(&self) -> bool {
+        if self.cmp_si(2) < 0 || self.trial_division(TRIAL_DIVISION_BOUND).is_some() {
+            return false;
+        }
+        // Trial division is a full factorisation below the bound squared.
+        let square = (TRIAL_DIVISION_BOUND * TRIAL_DIVISION_BOUND) as c_ulong;
+        if unsafe { gmp::mpz_cmp_ui(&self.inner, square) } < 0 {
+            return true;
+        }
+        self.passes_miller_rabin_base_2() && self.passes_strong_lucas()
+    }
+
+    /// The least prime greater than `self`.
+    pub fn next_prime(&self) -> Mpz {
+        let mut ret = self.clone();
+        loop {
+            unsafe { gmp::mpz_nextprime(&mut ret.inner, &ret.inner) };
+            if ret.is_bpsw_prime() {
+                return ret;
+            }
+        }
+    }
+
+    /// The greatest prime less than `self`, if there is one.
+    pub fn prev_prime(&self) -> Option<Mpz> {
+        match self.cmp_si(3).cmp(&0) {
+            Ordering::Less => return None,
+            Ordering::Equal => return Some(Mpz::from(2)),
+            Ordering::Greater => {}
+        }
+        let mut ret = self.clone();
+        ret.sub_ui_mut(1 + ret.odd() as u64);
+        while !ret.is_bpsw_prime() {
+            ret.sub_ui_mut(2);
+        }
+        Some(ret)
+    }
+
+    fn passes_miller_rabin_base_2(&self) -> bool {
+        let mut n_minus_1 = self.clone();
+        n_minus_1.sub_ui_mut(1);
+        let r = unsafe { gmp::mpz_scan1(&n_minus_1.inner, 0) };
+        let mut d = Mpz::default();
+        d.tdiv_q_2exp(&n_minus_1, r);
+
+        let mut x = Mpz::from(2).powm(&d, self);
+        if x.is_one() || x == n_minus_1 {
+            return true;
+        }
+        for _ in 1..r {
+            x.square_mut();
+            x.modulo_mut(self);
+            if x.is_one() {
+                return false;
+            }
+            if x == n_minus_1 {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// The strong Lucas test with `P = 1` and `Q = (1 - D) / 4`, for the
+    /// first `D` in `5, -7, 9, -11, ...` with `(D | n) = -1`. `self` must be
+    /// greater than `TRIAL_DIVISION_BOUND^2` and have no smaller factor.
+    fn passes_strong_lucas(&self) -> bool {
+        let n = self;
+        // No such `D` exists for squares.
+        if n.is_perfect_square() {
+            return false;
+        }
+        let mut d = 5i64;
+        loop {
+            match Mpz::si_kronecker(d, n) {
+                -1 => break,
+                // `|D|` shares a factor with `n`, which is past the small
+                // primes, so `n` is composite.
+                0 => return false,
+                _ => d = if d > 0 { -d - 2 } else { -d + 2 },
+            }
+        }
+        let q = (1 - d) / 4;
+
+        // Lucas sequences at `n + 1 = k * 2^s`, k odd.
+        let mut k = n.clone();
+        k.add_ui_mut(1);
+        let s = k.scan1(0).unwrap();
+        k.tdiv_q_2exp(&k.clone(), s as u64);
+        let mut big_q = Mpz::default();
+        big_q.set_si(q);
+        let (u, mut v, mut q_k) = lucas_sequence(&Mpz::one(), &big_q, &k, n);
+        if u.is_zero() || v.is_zero() {
+            return true;
+        }
+        for _ in 1..s {
+            v.square_mut();
+            v.sub_mut(&q_k);
+            v.sub_mut(&q_k);
+            v.modulo_mut(n);
+            if v.is_zero() {
+                return true;
+            }
+            q_k.square_mut();
+            q_k.modulo_mut(n);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_bpsw() {
+        let sieve: Vec<bool> = (0..2000u64)
+            .map(|n| n >= 2 && (2..n).take_while(|d| d * d <= n).all(|d| n % d != 0))
+            .collect();
+        for (n, &prime) in sieve.iter().enumerate() {
+            assert_eq!(Mpz::from(n as u64).is_bpsw_prime(), prime, "{}", n);
+        }
+        // Strong pseudoprimes to base 2, and strong Lucas pseudoprimes.
+        for &n in &[
+            2047u64, 3277, 4033, 4681, 8321, 5459, 5777, 10877, 16109, 18971,
+        ] {
+            assert!(!Mpz::from(n).is_bpsw_prime(), "{}", n);
+        }
+        // The same kinds above `TRIAL_DIVISION_BOUND^2` with no factor below
+        // the bound, so that only the two passes can catch them.
+        for &n in SPSP2.iter().chain(SLPSP) {
+            let n = Mpz::from(n);
+            assert_eq!(n.trial_division(TRIAL_DIVISION_BOUND), None);
+            assert!(!n.is_bpsw_prime(), "{}", n);
+        }
+        let big = Mpz::from_str("340282366920938463463374607431768211297").unwrap();
+        assert!(big.is_bpsw_prime());
+        let mut square = big.clone();
+        square.square_mut();
+        assert!(!square.is_bpsw_prime());
+        let mut minus = Mpz::default();
+        minus.set_si(-7);
+        assert!(!minus.is_bpsw_prime());
+    }
+
+    /// Strong pseudoprimes to base 2 above 10^6 with no factor below 1000:
+    /// 1093^2, 1013 * 1657, 1069 * 2137, 1103 * 2089, 1163 * 2657 and
+    /// 1021 * 3061.
+    const SPSP2: &[u64] = &[1194649, 1678541, 2284453, 2304167, 3090091, 3125281];
+
+    /// Strong Lucas pseudoprimes with Selfridge's parameters, likewise:
+    /// 1069 * 1601, 1063 * 2129, 1123 * 2243, 1619 * 1621, 1153 * 2309 and
+    /// 1381 * 1931.
+    const SLPSP: &[u64] = &[1711469, 2263127, 2518889, 2624399, 2662277, 2666711];
+
+    #[test]
+    fn test_bpsw_passes() {
+        let primes = [1000003u64, 2147483647, 4294967291];
+        for &n in SPSP2.iter().chain(&[3215031751]).chain(&primes) {
+            assert!(Mpz::from(n).passes_miller_rabin_base_2(), "{}", n);
+        }
+        for &n in SLPSP {
+            assert!(!Mpz::from(n).passes_miller_rabin_base_2(), "{}", n);
+        }
+        for &n in SLPSP.iter().chain(&primes) {
+            assert!(Mpz::from(n).passes_strong_lucas(), "{}", n);
+        }
+        // 3215031751 = 151 * 751 * 28351 is a strong pseudoprime to the
+        // bases 2, 3, 5 and 7, but not a Lucas one.
+        for &n in SPSP2.iter().chain(&[3215031751]) {
+            assert!(!Mpz::from(n).passes_strong_lucas(), "{}", n);
+        }
+    }
+
+    #[test]
+    fn test_trial_division() {
+        let m = |x: i64| {
+            let mut ret = Mpz::default();
+            ret.set_si(x);
+            ret
+        };
+        assert_eq!(m(0).trial_division(100), None);
+        assert_eq!(m(-1).trial_division(100), None);
+        assert_eq!(m(2).trial_division(100), None);
+        assert_eq!(m(97).trial_division(100), None);
+        assert_eq!(m(-91).trial_division(100), Some(7));
+        assert_eq!(m(3 * 101).trial_division(100), Some(3));
+        assert_eq!(m(101 * 103).trial_division(100), None);
+        assert_eq!(m(65521 * 65521).trial_division(u64::MAX), Some(65521));
+        assert_eq!(m(65537 * 65537).trial_division(u64::MAX), None);
+    }
+
+    #[test]
+    fn test_next_prev_prime() {
+        let primes: Vec<u64> = (0..2000u64)
+            .filter(|&n| Mpz::from(n).is_bpsw_prime())
+            .collect();
+        for n in 0..1990u64 {
+            let next = primes.iter().find(|&&p| p > n).unwrap();
+            assert_eq!(Mpz::from(n).next_prime(), Mpz::from(*next));
+            let prev = primes.iter().rev().find(|&&p| p < n);
+            assert_eq!(Mpz::from(n).prev_prime(), prev.map(|&p| Mpz::from(p)));
+        }
+        let mut minus = Mpz::default();
+        minus.set_si(-10);
+        assert_eq!(minus.next_prime(), Mpz::from(2));
+        assert_eq!(minus.prev_prime(), None);
+
+        let big = Mpz::from_str("340282366920938463463374607431768211297").unwrap();
+        let mut above = big.clone();
+        above.add_ui_mut(1);
+        assert_eq!(above.prev_prime(), Some(big.clone()));
+        assert!(big.next_prime() > big);
+    }
+}
@@ -0,0 +1,108 @@
+//! Parsing and formatting `Mpz` in radices from 2 to 62, with GMP's digits:
+//! `0-9`, then `a-z` in either case up to radix 36, and above that `A-Z`
+//! for 10 to 35 and `a-z` for 36 to 61.
+
+use super::Mpz;
+use gmp_mpfr_sys::gmp;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::c_char;
+
+/// Why `Mpz::from_str_radix` rejected a string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseMpzError;
+
+impl fmt::Display for ParseMpzError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid digit in integer")
+    }
+}
+
+impl std::error::Error for ParseMpzError {}
+
+fn digit_value(c: char, radix: u32) -> Option<u32> {
+    let value = match c {
+        '0'..='9' => c as u32 - '0' as u32,
+        'a'..='z' if radix <= 36 => c as u32 - 'a' as u32 + 10,
+        'A'..='Z' => c as u32 - 'A' as u32 + 10,
+        'a'..='z' => c as u32 - 'a' as u32 + 36,
+        _ => return None,
+    };
+    Some(value).filter(|&v| v < radix)
+}
+
+impl Mpz {
+    /// Parses an optionally signed string of digits in `radix`, from 2 to
+    /// 62. Prefixes such as `0x` and whitespace are rejected.
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<Self, ParseMpzError> {
+        let digits = s.strip_prefix('-').unwrap_or(s);
+        let valid = (2..=62).contains(&radix)
+            && !digits.is_empty()
+            && digits.chars().all(|c| digit_value(c, radix).is_some());
+        if !valid {
+            return Err(ParseMpzError);
+        }
+        let c_str = CString::new(s).map_err(|_| ParseMpzError)?;
+        let mut ret = Mpz::default();
+        match unsafe { gmp::mpz_set_str(&mut ret.inner, c_str.as_ptr(), radix as i32) } {
+            0 => Ok(ret),
+            _ => Err(ParseMpzError),
+        }
+    }
+
+    /// The digits of `self` in `radix`, from 2 to 62, after a `-` if it is
+    /// negative. Letters are lowercase up to radix 36. Panics on any other
+    /// radix.
+    pub fn to_string_radix(&self, radix: u32) -> String {
+        assert!(
+            (2..=62).contains(&radix),
+            "radix {} is not in 2..=62",
+            radix
+        );
+        // Room for the digits, a minus sign and the terminating NUL.
+        let len = unsafe { gmp::mpz_sizeinbase(&self.inner, radix as i32) } + 2;
+        let mut buf = vec![0u8; len];
+        let s = unsafe {
+            gmp::mpz_get_str(buf.as_mut_ptr() as *mut c_char, radix as i32, &self.inner);
+            CStr::from_ptr(buf.as_ptr() as *const c_char)
+        };
+        s.to_string_lossy().into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_radix() {
+        let x = Mpz::from_str("-340282366920938463463374607431768211457").unwrap();
+        for radix in 2..=62 {
+            let s = x.to_string_radix(radix);
+            assert_eq!(Mpz::from_str_radix(&s, radix), Ok(x.clone()), "{}", radix);
+        }
+        assert_eq!(x.to_string_radix(10), x.to_string());
+        assert_eq!(Mpz::from(255).to_string_radix(16), "ff");
+        assert_eq!(Mpz::from(61).to_string_radix(62), "z");
+        assert_eq!(Mpz::from(35).to_string_radix(62), "Z");
+        assert_eq!(Mpz::from_str_radix("FF", 16), Ok(Mpz::from(255)));
+        assert_eq!(
+            Mpz::from_str_radix("1Az", 62),
+            Ok(Mpz::from(3844 + 10 * 62 + 61))
+        );
+
+        for &(s, radix) in &[
+            ("12", 2),
+            ("z", 35),
+            ("", 10),
+            ("-", 10),
+            ("1 2", 10),
+            ("0x1", 16),
+            ("1", 63),
+            ("1", 1),
+        ] {
+            assert_eq!(Mpz::from_str_radix(s, radix), Err(ParseMpzError), "{:?}", s);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 //! Mpz randomness functions, ported from Flint
 
+use super::Mpz;
 use gmp_mpfr_sys::gmp::{
     // limb_t,
     randinit_default,
@@ -8,6 +9,33 @@ use gmp_mpfr_sys::gmp::{
     // mpz_urandomm
     randstate_t,
 };
+use rand::{CryptoRng, RngCore};
+
+impl Mpz {
+    /// A uniformly random integer in `[0, 2^bits)`.
+    pub fn random_bits<R: RngCore + CryptoRng>(bits: usize, rng: &mut R) -> Mpz {
+        let mut bytes = vec![0u8; bits.div_ceil(8)];
+        rng.fill_bytes(&mut bytes);
+        if bits % 8 != 0 {
+            bytes[0] &= (1u8 << (bits % 8)) - 1;
+        }
+        Mpz::from_bytes(&bytes)
+    }
+
+    /// A uniformly random integer in `[0, bound)`, by rejection sampling
+    /// over `random_bits`, which takes fewer than two draws on average.
+    /// Panics if `bound` is not positive.
+    pub fn random_below<R: RngCore + CryptoRng>(bound: &Mpz, rng: &mut R) -> Mpz {
+        assert!(bound.sgn() > 0, "random_below: bound must be positive");
+        let bits = bound.bit_length();
+        loop {
+            let x = Mpz::random_bits(bits, rng);
+            if x < *bound {
+                return x;
+            }
+        }
+    }
+}
 
 /// Holds the gmp randomness
 pub struct RandState {
@@ -197,3 +225,30 @@ pub fn randinit() -> RandState {
 //         unsafe { mpz_rrandomb(&mut f.inner, &mut state.gmp, bits); }
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_random() {
+        let rng = &mut StdRng::seed_from_u64(1);
+        assert_eq!(Mpz::random_bits(0, rng), Mpz::zero());
+        for &bits in &[1, 7, 8, 9, 64, 200] {
+            let x = Mpz::random_bits(bits, rng);
+            assert!(x.bit_length() <= bits);
+        }
+
+        // Each of the ten values turns up about as often as the others.
+        let bound = Mpz::from(10);
+        let mut counts = [0u32; 10];
+        for _ in 0..10_000 {
+            let x = Mpz::random_below(&bound, rng);
+            counts[x.to_u64().unwrap() as usize] += 1;
+        }
+        assert!(counts.iter().all(|&n| n > 850 && n < 1150), "{:?}", counts);
+        assert_eq!(Mpz::random_below(&Mpz::one(), rng), Mpz::zero());
+    }
+}
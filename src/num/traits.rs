@@ -0,0 +1,136 @@
+//! `num-traits` implementations for `Mpz`, behind the `num-traits` feature,
+//! so generic numeric code can run on GMP integers.
+//!
+//! The semantics are those of Rust's signed primitives: division truncates
+//! and `checked_div` refuses a zero divisor.
+
+use super::{Mpz, ParseMpzError};
+use gmp_mpfr_sys::gmp;
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Num, One, Pow, Signed, Zero};
+
+impl Zero for Mpz {
+    fn zero() -> Self {
+        Mpz::default()
+    }
+
+    fn is_zero(&self) -> bool {
+        Mpz::is_zero(self)
+    }
+}
+
+impl One for Mpz {
+    fn one() -> Self {
+        Mpz::from(1)
+    }
+}
+
+impl Num for Mpz {
+    type FromStrRadixErr = ParseMpzError;
+
+    /// `Mpz::from_str_radix`, for radices from 2 to 62.
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self, ParseMpzError> {
+        Mpz::from_str_radix(s, radix)
+    }
+}
+
+impl Signed for Mpz {
+    fn abs(&self) -> Self {
+        let mut ret = Mpz::default();
+        Mpz::abs(&mut ret, self);
+        ret
+    }
+
+    fn abs_sub(&self, other: &Self) -> Self {
+        if self <= other {
+            Mpz::default()
+        } else {
+            self - other
+        }
+    }
+
+    fn signum(&self) -> Self {
+        let mut ret = Mpz::default();
+        ret.set_si(self.sgn() as i64);
+        ret
+    }
+
+    fn is_positive(&self) -> bool {
+        self.sgn() > 0
+    }
+
+    fn is_negative(&self) -> bool {
+        self.sgn() < 0
+    }
+}
+
+impl CheckedAdd for Mpz {
+    fn checked_add(&self, v: &Self) -> Option<Self> {
+        Some(self + v)
+    }
+}
+
+impl CheckedSub for Mpz {
+    fn checked_sub(&self, v: &Self) -> Option<Self> {
+        Some(self - v)
+    }
+}
+
+impl CheckedMul for Mpz {
+    fn checked_mul(&self, v: &Self) -> Option<Self> {
+        Some(self * v)
+    }
+}
+
+impl CheckedDiv for Mpz {
+    fn checked_div(&self, v: &Self) -> Option<Self> {
+        if v.is_zero() {
+            None
+        } else {
+            Some(self / v)
+        }
+    }
+}
+
+impl Pow<u32> for &Mpz {
+    type Output = Mpz;
+
+    fn pow(self, exp: u32) -> Mpz {
+        let mut ret = Mpz::default();
+        unsafe { gmp::mpz_pow_ui(&mut ret.inner, &self.inner, exp.into()) };
+        ret
+    }
+}
+
+impl Pow<u32> for Mpz {
+    type Output = Mpz;
+
+    fn pow(self, exp: u32) -> Mpz {
+        (&self).pow(exp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    /// Generic code that only knows `Num + Signed`.
+    fn sum_of_abs<T: Num + Signed + Clone>(xs: &[T]) -> T {
+        xs.iter().fold(T::zero(), |acc, x| acc + x.abs())
+    }
+
+    #[test]
+    fn test_num_traits() {
+        let m = |s| Mpz::from_str(s).unwrap();
+        assert_eq!(sum_of_abs(&[m("-3"), m("4"), m("-5")]), m("12"));
+        assert_eq!(Mpz::from_str_radix("-ff", 16).unwrap(), m("-255"));
+        assert_eq!(Mpz::from_str_radix("z", 36).unwrap(), m("35"));
+        assert!(Mpz::from_str_radix("12a", 10).is_err());
+        assert!(Mpz::from_str_radix("", 10).is_err());
+        assert_eq!(Signed::signum(&m("-9")), m("-1"));
+        assert_eq!(m("3").abs_sub(&m("5")), Mpz::zero());
+        assert_eq!(m("7").checked_div(&Mpz::zero()), None);
+        assert_eq!(m("-7").checked_div(&m("2")), Some(m("-3")));
+        assert_eq!(m("-2").pow(63u32), m("-9223372036854775808"));
+    }
+}
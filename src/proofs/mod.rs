@@ -0,0 +1,15 @@
+//! Succinct arguments about exponents in the class group, after Boneh, Bünz
+//! and Fisch, "Batching techniques for accumulators with applications to
+//! IOPs and stateless blockchains". https://eprint.iacr.org/2018/1188.pdf
+//!
+//! All proofs are made non-interactive with Fiat-Shamir and run in the
+//! calling thread's current class group.
+
+mod poe;
+pub use poe::{prove_poe, verify_poe, Poe};
+mod poddh;
+pub use poddh::Poddh;
+mod poke;
+pub use poke::{Poke, Poke2};
+mod zkpoke;
+pub use zkpoke::ZkPoke;
@@ -0,0 +1,172 @@
+//! Proof that two pairs share a discrete log, BBF18's PoDDH.
+//!
+//! Shows that `(u1, w1, u2, w2)` has `w1 = u1^x` and `w2 = u2^x` for one `x`
+//! known to the prover, as when several accumulators or commitments are
+//! raised to the same exponent. It is PoKE run on both pairs at once: the
+//! quotients by the challenge prime `l` come from the same `x / l`, and a
+//! single remainder `r = x mod l` must close every equation.
+
+use crate::encoding::{json_decimal, json_object};
+use crate::group::{ClassGroup, UnknownOrderGroup};
+use crate::transcript::TranscriptProtocol;
+use crate::vdf::codec::{read_all, read_elem, read_integer, write_elem, write_integer};
+use anyhow::Result;
+use bacteria::Transcript;
+use rug::Integer;
+use std::io::{Read, Write};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Poddh<G: UnknownOrderGroup = ClassGroup> {
+    /// `g^x` for the group's generator `g`.
+    pub z: G::Elem,
+    pub q_1: G::Elem,
+    pub q_2: G::Elem,
+    pub q_g: G::Elem,
+    pub r: Integer,
+}
+
+fn challenge<G: UnknownOrderGroup>(
+    transcript: &mut Transcript,
+    statement: [&G::Elem; 4],
+    z: &G::Elem,
+) -> Integer {
+    transcript.domain_sep::<G>(b"poddh");
+    transcript.append_elem::<G>(b"u1", statement[0]);
+    transcript.append_elem::<G>(b"w1", statement[1]);
+    transcript.append_elem::<G>(b"u2", statement[2]);
+    transcript.append_elem::<G>(b"w2", statement[3]);
+    transcript.append_elem::<G>(b"z", z);
+    transcript.challenge_prime(b"l")
+}
+
+impl<G: UnknownOrderGroup> Poddh<G> {
+    /// Proves `u1^x = w1` and `u2^x = w2`.
+    pub fn prove(u1: &G::Elem, w1: &G::Elem, u2: &G::Elem, w2: &G::Elem, x: &Integer) -> Self {
+        let mut transcript = Transcript::new(b"Classygroup.poddh");
+        Self::prove_with_transcript(&mut transcript, u1, w1, u2, w2, x)
+    }
+
+    /// `prove` as a step of a larger protocol that shares `transcript`.
+    pub fn prove_with_transcript(
+        transcript: &mut Transcript,
+        u1: &G::Elem,
+        w1: &G::Elem,
+        u2: &G::Elem,
+        w2: &G::Elem,
+        x: &Integer,
+    ) -> Self {
+        let g = G::unknown_order_elem();
+        let z = G::pow(&g, x);
+        let l = challenge::<G>(transcript, [u1, w1, u2, w2], &z);
+        let (q, r) = x.clone().div_rem_euc(l);
+        Self {
+            q_1: G::pow(u1, &q),
+            q_2: G::pow(u2, &q),
+            q_g: G::pow(&g, &q),
+            z,
+            r,
+        }
+    }
+
+    /// Checks a proof from `prove` that `w1 = u1^x` and `w2 = u2^x` for the
+    /// same `x`.
+    pub fn verify(&self, u1: &G::Elem, w1: &G::Elem, u2: &G::Elem, w2: &G::Elem) -> bool {
+        let mut transcript = Transcript::new(b"Classygroup.poddh");
+        self.verify_with_transcript(&mut transcript, u1, w1, u2, w2)
+    }
+
+    /// Checks a proof from `prove_with_transcript`, given the transcript as
+    /// it was before the proof.
+    pub fn verify_with_transcript(
+        &self,
+        transcript: &mut Transcript,
+        u1: &G::Elem,
+        w1: &G::Elem,
+        u2: &G::Elem,
+        w2: &G::Elem,
+    ) -> bool {
+        if ![&self.z, &self.q_1, &self.q_2, &self.q_g]
+            .iter()
+            .all(|e| G::is_elem(e))
+        {
+            return false;
+        }
+        let l = challenge::<G>(transcript, [u1, w1, u2, w2], &self.z);
+        if self.r < 0 || self.r >= l {
+            return false;
+        }
+        let g = G::unknown_order_elem();
+        let lhs = |q: &G::Elem, base: &G::Elem| G::op(&G::pow(q, &l), &G::pow(base, &self.r));
+        lhs(&self.q_1, u1) == *w1 && lhs(&self.q_2, u2) == *w2 && lhs(&self.q_g, &g) == self.z
+    }
+}
+
+impl Poddh<ClassGroup> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for e in &[&self.z, &self.q_1, &self.q_2, &self.q_g] {
+            write_elem(&mut buf, e);
+        }
+        write_integer(&mut buf, &self.r);
+        buf
+    }
+
+    /// Parses the encoding of `to_bytes`. Group membership is left to
+    /// `verify`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        read_all(bytes, |r| Self::read_from(r))
+    }
+
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        w.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    /// Reads a proof from `write_to`, reading no further than its end.
+    pub fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        Ok(Self {
+            z: read_elem(r)?,
+            q_1: read_elem(r)?,
+            q_2: read_elem(r)?,
+            q_g: read_elem(r)?,
+            r: read_integer(r)?,
+        })
+    }
+
+    /// The proof as JSON with every number in decimal, for debugging.
+    pub fn to_json_debug(&self) -> String {
+        json_object(&[
+            ("z", self.z.to_json_debug()),
+            ("q_1", self.q_1.to_json_debug()),
+            ("q_2", self.q_2.to_json_debug()),
+            ("q_g", self.q_g.to_json_debug()),
+            ("r", json_decimal(&self.r)),
+        ])
+    }
+}
+
+crate::encoding::impl_hex!(Poddh<ClassGroup>, Poddh::to_bytes, Poddh::from_bytes);
+
+#[cfg(feature = "serde")]
+crate::serialize::impl_serde_bytes!(Poddh<ClassGroup>, Poddh::to_bytes, Poddh::from_bytes);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prove_verify() {
+        let g = ClassGroup::unknown_order_elem();
+        let h = ClassGroup::hash_to_group(b"h");
+        let x = Integer::from(Integer::u_pow_u(3, 300));
+        let (gx, hx) = (ClassGroup::pow(&g, &x), ClassGroup::pow(&h, &x));
+
+        let proof = Poddh::<ClassGroup>::prove(&g, &gx, &h, &hx, &x);
+        assert!(proof.verify(&g, &gx, &h, &hx));
+        assert_eq!(Poddh::from_bytes(&proof.to_bytes()).unwrap(), proof);
+
+        let hy = ClassGroup::op(&hx, &h);
+        assert!(!proof.verify(&g, &gx, &h, &hy));
+        assert!(!Poddh::<ClassGroup>::prove(&g, &gx, &h, &hy, &x).verify(&g, &gx, &h, &hy));
+    }
+}
@@ -0,0 +1,149 @@
+//! Non-interactive proof of exponentiation, Wesolowski's argument for
+//! `base^exp = result` with a large public `exp` (BBF18, section 3.1).
+//!
+//! The prover sends `q = base^floor(exp / l)` for a challenge prime `l`; the
+//! verifier checks `q^l * base^(exp mod l) = result`, which costs two
+//! exponentiations by numbers the size of `l` instead of one by `exp`.
+//!
+//! The accumulator proves its updates with it, and a Wesolowski VDF proof
+//! is the same argument for the exponent `2^T`.
+
+use crate::encoding::json_object;
+use crate::group::{ClassElem, ClassGroup, UnknownOrderGroup};
+use crate::transcript::TranscriptProtocol;
+use crate::vdf::codec::{read_all, read_elem, write_elem};
+use anyhow::Result;
+use bacteria::Transcript;
+use rug::Integer;
+use std::io::{Read, Write};
+
+/// Proves `u^x = w` in the class group. `x` must not be negative.
+pub fn prove_poe(u: &ClassElem, x: &Integer, w: &ClassElem) -> Poe {
+    Poe::prove(u, x, w)
+}
+
+/// Checks a proof from `prove_poe` that `u^x = w`.
+pub fn verify_poe(u: &ClassElem, x: &Integer, w: &ClassElem, proof: &Poe) -> bool {
+    proof.verify(u, x, w)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Poe<G: UnknownOrderGroup = ClassGroup> {
+    pub q: G::Elem,
+}
+
+fn challenge<G: UnknownOrderGroup>(
+    transcript: &mut Transcript,
+    base: &G::Elem,
+    exp: &Integer,
+    result: &G::Elem,
+) -> Integer {
+    transcript.domain_sep::<G>(b"poe");
+    transcript.append_elem::<G>(b"base", base);
+    transcript.append_integer(b"exp", exp);
+    transcript.append_elem::<G>(b"result", result);
+    transcript.challenge_prime(b"l")
+}
+
+impl<G: UnknownOrderGroup> Poe<G> {
+    /// Proves `base^exp = result`. `exp` must not be negative.
+    pub fn prove(base: &G::Elem, exp: &Integer, result: &G::Elem) -> Self {
+        Self::prove_with_transcript(&mut Transcript::new(b"Classygroup.poe"), base, exp, result)
+    }
+
+    /// `prove` as a step of a larger protocol that shares `transcript`.
+    pub fn prove_with_transcript(
+        transcript: &mut Transcript,
+        base: &G::Elem,
+        exp: &Integer,
+        result: &G::Elem,
+    ) -> Self {
+        let l = challenge::<G>(transcript, base, exp, result);
+        Self {
+            q: G::pow(base, &Integer::from(exp / &l)),
+        }
+    }
+
+    /// Checks a proof that `base^exp = result`.
+    pub fn verify(&self, base: &G::Elem, exp: &Integer, result: &G::Elem) -> bool {
+        self.verify_with_transcript(&mut Transcript::new(b"Classygroup.poe"), base, exp, result)
+    }
+
+    /// Checks a proof from `prove_with_transcript`, given the transcript as
+    /// it was before the proof.
+    pub fn verify_with_transcript(
+        &self,
+        transcript: &mut Transcript,
+        base: &G::Elem,
+        exp: &Integer,
+        result: &G::Elem,
+    ) -> bool {
+        if *exp < 0 || !G::is_elem(&self.q) {
+            return false;
+        }
+        let l = challenge::<G>(transcript, base, exp, result);
+        let r = Integer::from(exp % &l);
+        G::op(&G::pow(&self.q, &l), &G::pow(base, &r)) == *result
+    }
+}
+
+impl Poe<ClassGroup> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_elem(&mut buf, &self.q);
+        buf
+    }
+
+    /// Parses the encoding of `to_bytes`. Group membership is left to
+    /// `verify`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        read_all(bytes, |r| Self::read_from(r))
+    }
+
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.q.write_to(w)
+    }
+
+    /// Reads a proof from `write_to`, reading no further than its end.
+    pub fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        Ok(Self { q: read_elem(r)? })
+    }
+
+    /// The proof as JSON with every number in decimal, for debugging.
+    pub fn to_json_debug(&self) -> String {
+        json_object(&[("q", self.q.to_json_debug())])
+    }
+}
+
+crate::encoding::impl_hex!(Poe<ClassGroup>, Poe::to_bytes, Poe::from_bytes);
+
+#[cfg(feature = "serde")]
+crate::serialize::impl_serde_bytes!(Poe<ClassGroup>, Poe::to_bytes, Poe::from_bytes);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prove_verify() {
+        let g = ClassGroup::unknown_order_elem();
+        let exp = Integer::from(Integer::u_pow_u(3, 500));
+        let result = ClassGroup::pow(&g, &exp);
+        let proof = prove_poe(&g, &exp, &result);
+        assert!(verify_poe(&g, &exp, &result, &proof));
+        assert!(!proof.verify(&g, &Integer::from(&exp + 1), &result));
+        assert!(!proof.verify(&g, &exp, &g));
+
+        // A proof is bound to the transcript it was made on.
+        let mut outer = Transcript::new(b"outer");
+        let composed = Poe::<ClassGroup>::prove_with_transcript(&mut outer, &g, &exp, &result);
+        let mut outer = Transcript::new(b"outer");
+        assert!(composed.verify_with_transcript(&mut outer, &g, &exp, &result));
+        assert!(!composed.verify(&g, &exp, &result));
+
+        let forged = Poe::<ClassGroup> {
+            q: ClassGroup::op(&proof.q, &g),
+        };
+        assert!(!forged.verify(&g, &exp, &result));
+    }
+}
@@ -0,0 +1,293 @@
+//! Proofs of knowledge of an exponent, BBF18's PoKE and PoKE2.
+//!
+//! Both show knowledge of some `x` with `u^x = w` for any base `u`, by
+//! committing to it as `z = g^x` for the group's generator `g` and
+//! compressing the responses with a challenge prime `l`, as in a proof of
+//! exponentiation. PoKE2 checks both relations in one equation through a
+//! random linear combination and so sends one element fewer.
+
+use crate::encoding::{json_decimal, json_object};
+use crate::group::{ClassGroup, UnknownOrderGroup};
+use crate::transcript::TranscriptProtocol;
+use crate::vdf::codec::{read_all, read_elem, read_integer, write_elem, write_integer};
+use anyhow::Result;
+use bacteria::Transcript;
+use rug::Integer;
+use std::io::{Read, Write};
+
+/// A proof of knowledge of `x` with `u^x = w`: `q_u = u^(x / l)`,
+/// `q_g = g^(x / l)` and `r = x mod l`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Poke<G: UnknownOrderGroup = ClassGroup> {
+    pub z: G::Elem,
+    pub q_u: G::Elem,
+    pub q_g: G::Elem,
+    pub r: Integer,
+}
+
+/// A proof of knowledge of `x` with `u^x = w`: `q = (u * g^alpha)^(x / l)`
+/// and `r = x mod l`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Poke2<G: UnknownOrderGroup = ClassGroup> {
+    pub z: G::Elem,
+    pub q: G::Elem,
+    pub r: Integer,
+}
+
+fn append_statement<G: UnknownOrderGroup>(
+    transcript: &mut Transcript,
+    protocol: &'static [u8],
+    u: &G::Elem,
+    w: &G::Elem,
+    z: &G::Elem,
+) {
+    transcript.domain_sep::<G>(protocol);
+    transcript.append_elem::<G>(b"u", u);
+    transcript.append_elem::<G>(b"w", w);
+    transcript.append_elem::<G>(b"z", z);
+}
+
+impl<G: UnknownOrderGroup> Poke<G> {
+    /// Proves knowledge of `x` with `u^x = w`.
+    pub fn prove(u: &G::Elem, w: &G::Elem, x: &Integer) -> Self {
+        Self::prove_with_transcript(&mut Transcript::new(b"Classygroup.poke"), u, w, x)
+    }
+
+    /// `prove` as a step of a larger protocol that shares `transcript`.
+    pub fn prove_with_transcript(
+        transcript: &mut Transcript,
+        u: &G::Elem,
+        w: &G::Elem,
+        x: &Integer,
+    ) -> Self {
+        let g = G::unknown_order_elem();
+        let z = G::pow(&g, x);
+        append_statement::<G>(transcript, b"poke", u, w, &z);
+        let l = transcript.challenge_prime(b"l");
+        let (q, r) = x.clone().div_rem_euc(l);
+        Self {
+            q_u: G::pow(u, &q),
+            q_g: G::pow(&g, &q),
+            z,
+            r,
+        }
+    }
+
+    /// Checks a proof from `prove` that the prover knows `x` with `u^x = w`.
+    pub fn verify(&self, u: &G::Elem, w: &G::Elem) -> bool {
+        self.verify_with_transcript(&mut Transcript::new(b"Classygroup.poke"), u, w)
+    }
+
+    /// Checks a proof from `prove_with_transcript`, given the transcript as
+    /// it was before the proof.
+    pub fn verify_with_transcript(
+        &self,
+        transcript: &mut Transcript,
+        u: &G::Elem,
+        w: &G::Elem,
+    ) -> bool {
+        if ![&self.z, &self.q_u, &self.q_g]
+            .iter()
+            .all(|e| G::is_elem(e))
+        {
+            return false;
+        }
+        append_statement::<G>(transcript, b"poke", u, w, &self.z);
+        let l = transcript.challenge_prime(b"l");
+        if self.r < 0 || self.r >= l {
+            return false;
+        }
+        let g = G::unknown_order_elem();
+        let lhs = |q: &G::Elem, base: &G::Elem| G::op(&G::pow(q, &l), &G::pow(base, &self.r));
+        lhs(&self.q_u, u) == *w && lhs(&self.q_g, &g) == self.z
+    }
+}
+
+impl Poke<ClassGroup> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_elem(&mut buf, &self.z);
+        write_elem(&mut buf, &self.q_u);
+        write_elem(&mut buf, &self.q_g);
+        write_integer(&mut buf, &self.r);
+        buf
+    }
+
+    /// Parses the encoding of `to_bytes`. Group membership is left to
+    /// `verify`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        read_all(bytes, |r| Self::read_from(r))
+    }
+
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        w.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    /// Reads a proof from `write_to`, reading no further than its end.
+    pub fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        Ok(Self {
+            z: read_elem(r)?,
+            q_u: read_elem(r)?,
+            q_g: read_elem(r)?,
+            r: read_integer(r)?,
+        })
+    }
+
+    /// The proof as JSON with every number in decimal, for debugging.
+    pub fn to_json_debug(&self) -> String {
+        json_object(&[
+            ("z", self.z.to_json_debug()),
+            ("q_u", self.q_u.to_json_debug()),
+            ("q_g", self.q_g.to_json_debug()),
+            ("r", json_decimal(&self.r)),
+        ])
+    }
+}
+
+impl<G: UnknownOrderGroup> Poke2<G> {
+    /// Proves knowledge of `x` with `u^x = w`.
+    pub fn prove(u: &G::Elem, w: &G::Elem, x: &Integer) -> Self {
+        Self::prove_with_transcript(&mut Transcript::new(b"Classygroup.poke2"), u, w, x)
+    }
+
+    /// `prove` as a step of a larger protocol that shares `transcript`.
+    pub fn prove_with_transcript(
+        transcript: &mut Transcript,
+        u: &G::Elem,
+        w: &G::Elem,
+        x: &Integer,
+    ) -> Self {
+        let g = G::unknown_order_elem();
+        let z = G::pow(&g, x);
+        append_statement::<G>(transcript, b"poke2", u, w, &z);
+        let l = transcript.challenge_prime(b"l");
+        let alpha = transcript.challenge_integer(b"alpha", 128);
+
+        let base = G::op(u, &G::pow(&g, &alpha));
+        let (q, r) = x.clone().div_rem_euc(l);
+        Self {
+            q: G::pow(&base, &q),
+            z,
+            r,
+        }
+    }
+
+    /// Checks a proof from `prove` that the prover knows `x` with `u^x = w`.
+    pub fn verify(&self, u: &G::Elem, w: &G::Elem) -> bool {
+        self.verify_with_transcript(&mut Transcript::new(b"Classygroup.poke2"), u, w)
+    }
+
+    /// Checks a proof from `prove_with_transcript`, given the transcript as
+    /// it was before the proof.
+    pub fn verify_with_transcript(
+        &self,
+        transcript: &mut Transcript,
+        u: &G::Elem,
+        w: &G::Elem,
+    ) -> bool {
+        if !G::is_elem(&self.z) || !G::is_elem(&self.q) {
+            return false;
+        }
+        append_statement::<G>(transcript, b"poke2", u, w, &self.z);
+        let l = transcript.challenge_prime(b"l");
+        let alpha = transcript.challenge_integer(b"alpha", 128);
+        if self.r < 0 || self.r >= l {
+            return false;
+        }
+
+        // `q^l * (u * g^alpha)^r = u^x * z^alpha = w * z^alpha`.
+        let g = G::unknown_order_elem();
+        let base = G::op(u, &G::pow(&g, &alpha));
+        let lhs = G::op(&G::pow(&self.q, &l), &G::pow(&base, &self.r));
+        lhs == G::op(w, &G::pow(&self.z, &alpha))
+    }
+}
+
+impl Poke2<ClassGroup> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_elem(&mut buf, &self.z);
+        write_elem(&mut buf, &self.q);
+        write_integer(&mut buf, &self.r);
+        buf
+    }
+
+    /// Parses the encoding of `to_bytes`. Group membership is left to
+    /// `verify`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        read_all(bytes, |r| Self::read_from(r))
+    }
+
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        w.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    /// Reads a proof from `write_to`, reading no further than its end.
+    pub fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        Ok(Self {
+            z: read_elem(r)?,
+            q: read_elem(r)?,
+            r: read_integer(r)?,
+        })
+    }
+
+    /// The proof as JSON with every number in decimal, for debugging.
+    pub fn to_json_debug(&self) -> String {
+        json_object(&[
+            ("z", self.z.to_json_debug()),
+            ("q", self.q.to_json_debug()),
+            ("r", json_decimal(&self.r)),
+        ])
+    }
+}
+
+crate::encoding::impl_hex!(Poke<ClassGroup>, Poke::to_bytes, Poke::from_bytes);
+
+#[cfg(feature = "serde")]
+crate::serialize::impl_serde_bytes!(Poke<ClassGroup>, Poke::to_bytes, Poke::from_bytes);
+
+crate::encoding::impl_hex!(Poke2<ClassGroup>, Poke2::to_bytes, Poke2::from_bytes);
+
+#[cfg(feature = "serde")]
+crate::serialize::impl_serde_bytes!(Poke2<ClassGroup>, Poke2::to_bytes, Poke2::from_bytes);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poke() {
+        let u = ClassGroup::hash_to_group(b"u");
+        let x = Integer::from(Integer::u_pow_u(5, 400));
+        let w = ClassGroup::pow(&u, &x);
+
+        let proof = Poke::<ClassGroup>::prove(&u, &w, &x);
+        assert!(proof.verify(&u, &w));
+        assert!(!proof.verify(&u, &u));
+        assert!(!Poke::<ClassGroup>::prove(&u, &w, &Integer::from(&x + 1)).verify(&u, &w));
+
+        let bytes = proof.to_bytes();
+        assert_eq!(Poke::from_bytes(&bytes).unwrap(), proof);
+        assert!(Poke::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_poke2() {
+        let u = ClassGroup::hash_to_group(b"u");
+        let x = Integer::from(Integer::u_pow_u(5, 400));
+        let w = ClassGroup::pow(&u, &x);
+
+        let proof = Poke2::<ClassGroup>::prove(&u, &w, &x);
+        assert!(proof.verify(&u, &w));
+        assert!(!proof.verify(&w, &u));
+        assert!(!Poke2::<ClassGroup>::prove(&u, &w, &Integer::from(&x - 1)).verify(&u, &w));
+
+        let bytes = proof.to_bytes();
+        assert_eq!(Poke2::from_bytes(&bytes).unwrap(), proof);
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(Poke2::from_bytes(&trailing).is_err());
+    }
+}
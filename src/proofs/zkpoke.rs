@@ -0,0 +1,130 @@
+//! Zero-knowledge proof of knowledge of an exponent, BBF18's ZKPoKRep.
+//!
+//! The prover commits to `x` as `z = g^x * h^rho` and runs a blinded
+//! Schnorr protocol for `x` in both `z` and `w = u^x`. The responses are
+//! compressed like a proof of exponentiation, into quotients by a challenge
+//! prime `l` and remainders smaller than it, so the proof stays constant
+//! size however large `x` is.
+
+use crate::commitment::{is_elem, random_bits, CommitmentKey, CHALLENGE_BITS, STATISTICAL_BITS};
+use crate::group::{ClassElem, ClassGroup, UnknownOrderGroup};
+use crate::transcript::TranscriptProtocol;
+use bacteria::Transcript;
+use rand::{CryptoRng, RngCore};
+use rug::Integer;
+
+/// A proof of knowledge of `x` with `u^x = w` that reveals nothing about `x`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ZkPoke {
+    z: ClassElem,
+    a_g: ClassElem,
+    a_u: ClassElem,
+    q_g: ClassElem,
+    q_u: ClassElem,
+    r_x: Integer,
+    r_rho: Integer,
+}
+
+/// The challenge `c` and prime `l` for a statement and first message.
+fn challenges(
+    key: &CommitmentKey,
+    u: &ClassElem,
+    w: &ClassElem,
+    first: [&ClassElem; 3],
+) -> (Integer, Integer) {
+    let mut transcript = Transcript::new(b"Classygroup.zkpoke");
+    ClassGroup::append_params(&mut transcript);
+    transcript.append_elem::<ClassGroup>(b"g", key.g());
+    transcript.append_elem::<ClassGroup>(b"h", key.h());
+    transcript.append_elem::<ClassGroup>(b"u", u);
+    transcript.append_elem::<ClassGroup>(b"w", w);
+    transcript.append_elem::<ClassGroup>(b"z", first[0]);
+    transcript.append_elem::<ClassGroup>(b"a_g", first[1]);
+    transcript.append_elem::<ClassGroup>(b"a_u", first[2]);
+    let c = transcript.challenge_integer(b"c", CHALLENGE_BITS);
+    (c, transcript.challenge_prime(b"l"))
+}
+
+impl ZkPoke {
+    /// Proves knowledge of `x` with `u^x = w`, blinding with the bases of
+    /// `key`.
+    pub fn prove<R: RngCore + CryptoRng>(
+        key: &CommitmentKey,
+        u: &ClassElem,
+        w: &ClassElem,
+        x: &Integer,
+        rng: &mut R,
+    ) -> Self {
+        let commit = |m: &Integer, r: &Integer| key.commit(m, r).value().clone();
+
+        // Masks exceed `c * x` and `c * rho` by `STATISTICAL_BITS`.
+        let bits = std::cmp::max(x.significant_bits(), CommitmentKey::randomness_bits());
+        let rho_x = random_bits(rng, CommitmentKey::randomness_bits());
+        let k = random_bits(rng, bits + CHALLENGE_BITS + STATISTICAL_BITS);
+        let rho_k = random_bits(rng, bits + CHALLENGE_BITS + STATISTICAL_BITS);
+        let z = commit(x, &rho_x);
+        let a_g = commit(&k, &rho_k);
+        let a_u = ClassGroup::pow(u, &k);
+
+        let (c, l) = challenges(key, u, w, [&z, &a_g, &a_u]);
+        let s_x = k + Integer::from(&c * x);
+        let s_rho = rho_k + Integer::from(&c * &rho_x);
+        let (q_x, r_x) = s_x.div_rem_euc(l.clone());
+        let (q_rho, r_rho) = s_rho.div_rem_euc(l);
+        Self {
+            q_g: commit(&q_x, &q_rho),
+            q_u: ClassGroup::pow(u, &q_x),
+            z,
+            a_g,
+            a_u,
+            r_x,
+            r_rho,
+        }
+    }
+
+    /// Checks a proof from `prove` that the prover knows `x` with `u^x = w`.
+    pub fn verify(&self, key: &CommitmentKey, u: &ClassElem, w: &ClassElem) -> bool {
+        let elems = [&self.z, &self.a_g, &self.a_u, &self.q_g, &self.q_u];
+        if !elems.iter().all(|e| is_elem(e)) {
+            return false;
+        }
+        let (c, l) = challenges(key, u, w, [&self.z, &self.a_g, &self.a_u]);
+        let in_range = |r: &Integer| *r >= 0 && *r < l;
+        if !in_range(&self.r_x) || !in_range(&self.r_rho) {
+            return false;
+        }
+
+        let g_side = ClassGroup::op(
+            &ClassGroup::pow(&self.q_g, &l),
+            key.commit(&self.r_x, &self.r_rho).value(),
+        );
+        let u_side = ClassGroup::op(
+            &ClassGroup::pow(&self.q_u, &l),
+            &ClassGroup::pow(u, &self.r_x),
+        );
+        g_side == ClassGroup::op(&self.a_g, &ClassGroup::pow(&self.z, &c))
+            && u_side == ClassGroup::op(&self.a_u, &ClassGroup::pow(w, &c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_prove_verify() {
+        let key = CommitmentKey::setup(b"test");
+        let u = ClassGroup::hash_to_group(b"u");
+        let x = Integer::from(Integer::u_pow_u(7, 300));
+        let w = ClassGroup::pow(&u, &x);
+
+        let proof = ZkPoke::prove(&key, &u, &w, &x, &mut thread_rng());
+        assert!(proof.verify(&key, &u, &w));
+        assert!(!proof.verify(&key, &u, &u));
+        assert!(!proof.verify(&CommitmentKey::setup(b"other"), &u, &w));
+
+        let wrong = ZkPoke::prove(&key, &u, &w, &Integer::from(&x + 1), &mut thread_rng());
+        assert!(!wrong.verify(&key, &u, &w));
+    }
+}
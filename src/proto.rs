@@ -0,0 +1,212 @@
+//! Protocol Buffers encodings of elements, proofs and accumulator updates,
+//! behind the `proto` feature.
+//!
+//! The messages are those of `proto/classygroup.proto`, so services in other
+//! languages can generate their side from the schema. The wire format is
+//! written by hand here rather than pulling in a code generator: every
+//! field is a byte string or a nested message. Decoding skips fields it
+//! does not know, as protobuf readers must, and keeps the last value of a
+//! field given twice. An element is sent as `a` and `b` only and must be a
+//! reduced form of the calling thread's class group, which `c` is
+//! recomputed from. Beyond that only the framing is checked; proofs still
+//! need to be verified.
+
+use crate::group::ClassElem;
+use crate::num::Mpz;
+use crate::proofs::Poe;
+use crate::vdf::{PietrzakProof, WesolowskiProof};
+use crate::ClassGroup;
+use anyhow::{anyhow, bail, Result};
+
+/// A type with a message in `proto/classygroup.proto`.
+pub trait ProtoMessage: Sized {
+    fn encode_proto(&self) -> Vec<u8>;
+
+    /// Parses a message. Missing nested messages are an error; missing
+    /// integers are zero, as in proto3.
+    fn decode_proto(bytes: &[u8]) -> Result<Self>;
+}
+
+const LENGTH_DELIMITED: u64 = 2;
+
+fn write_varint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64> {
+    let mut n = 0u64;
+    for (i, &byte) in bytes.iter().enumerate().take(10) {
+        n |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            *bytes = &bytes[i + 1..];
+            return Ok(n);
+        }
+    }
+    bail!("invalid varint")
+}
+
+/// Appends field `number` holding `value`.
+pub(crate) fn write_field(buf: &mut Vec<u8>, number: u32, value: &[u8]) {
+    write_varint(buf, (number as u64) << 3 | LENGTH_DELIMITED);
+    write_varint(buf, value.len() as u64);
+    buf.extend_from_slice(value);
+}
+
+/// The length-delimited fields of a message in order, skipping fields of
+/// the other wire types.
+pub(crate) fn read_fields(mut bytes: &[u8]) -> Result<Vec<(u32, &[u8])>> {
+    let mut fields = Vec::new();
+    while !bytes.is_empty() {
+        let key = read_varint(&mut bytes)?;
+        let skip = match key & 7 {
+            0 => {
+                read_varint(&mut bytes)?;
+                continue;
+            }
+            1 => 8,
+            LENGTH_DELIMITED => read_varint(&mut bytes)?,
+            5 => 4,
+            t => bail!("unsupported wire type {}", t),
+        };
+        if skip > bytes.len() as u64 {
+            bail!("field is truncated");
+        }
+        let (value, rest) = bytes.split_at(skip as usize);
+        if key & 7 == LENGTH_DELIMITED {
+            fields.push(((key >> 3) as u32, value));
+        }
+        bytes = rest;
+    }
+    Ok(fields)
+}
+
+/// The last value of field `number`, if it is present.
+pub(crate) fn field<'a>(fields: &[(u32, &'a [u8])], number: u32) -> Option<&'a [u8]> {
+    fields
+        .iter()
+        .rev()
+        .find(|(n, _)| *n == number)
+        .map(|(_, value)| *value)
+}
+
+/// Decodes the nested message in field `number`, which must be present.
+pub(crate) fn message<T: ProtoMessage>(fields: &[(u32, &[u8])], number: u32) -> Result<T> {
+    let value = field(fields, number).ok_or_else(|| anyhow!("missing field {}", number))?;
+    T::decode_proto(value)
+}
+
+impl ProtoMessage for ClassElem {
+    fn encode_proto(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_field(&mut buf, 1, &self.a.to_signed_bytes_be());
+        write_field(&mut buf, 2, &self.b.to_signed_bytes_be());
+        buf
+    }
+
+    /// Parses `a` and `b` against `ClassGroup::context_discriminant`.
+    fn decode_proto(bytes: &[u8]) -> Result<Self> {
+        let fields = read_fields(bytes)?;
+        let int = |n| Mpz::from_signed_bytes_be(field(&fields, n).unwrap_or(&[]));
+        Ok(ClassElem::from_ab(
+            int(1),
+            int(2),
+            &ClassGroup::context_discriminant(),
+        )?)
+    }
+}
+
+impl ProtoMessage for Poe<ClassGroup> {
+    fn encode_proto(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_field(&mut buf, 1, &self.q.encode_proto());
+        buf
+    }
+
+    fn decode_proto(bytes: &[u8]) -> Result<Self> {
+        Ok(Poe {
+            q: message(&read_fields(bytes)?, 1)?,
+        })
+    }
+}
+
+impl ProtoMessage for WesolowskiProof {
+    fn encode_proto(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_field(&mut buf, 1, &self.pi.encode_proto());
+        buf
+    }
+
+    fn decode_proto(bytes: &[u8]) -> Result<Self> {
+        Ok(WesolowskiProof {
+            pi: message(&read_fields(bytes)?, 1)?,
+        })
+    }
+}
+
+impl ProtoMessage for PietrzakProof {
+    fn encode_proto(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for mu in &self.midpoints {
+            write_field(&mut buf, 1, &mu.encode_proto());
+        }
+        buf
+    }
+
+    fn decode_proto(bytes: &[u8]) -> Result<Self> {
+        let midpoints = read_fields(bytes)?
+            .into_iter()
+            .filter(|(n, _)| *n == 1)
+            .map(|(_, value)| ClassElem::decode_proto(value))
+            .collect::<Result<_>>()?;
+        Ok(PietrzakProof { midpoints })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accumulator::{Accumulator, UpdateBatch, UpdateProof};
+    use rug::Integer;
+
+    #[test]
+    fn test_round_trip() {
+        let g = ClassGroup::unknown_order_elem();
+        let x = ClassGroup::inv(&ClassGroup::pow(&g, &Integer::from(3)));
+        assert_eq!(ClassElem::decode_proto(&x.encode_proto()).unwrap(), x);
+        let proof = PietrzakProof {
+            midpoints: vec![g.clone(), x.clone()],
+        };
+        assert_eq!(
+            PietrzakProof::decode_proto(&proof.encode_proto()).unwrap(),
+            proof
+        );
+
+        let (acc, w) = Accumulator::<ClassGroup>::empty().add(&[b"a", b"b"]);
+        let update = UpdateBatch::new(&acc, &[(b"a", w[0].clone())], &[b"c"])
+            .unwrap()
+            .apply();
+        let bytes = update.encode_proto();
+        let decoded = <(Accumulator, UpdateProof)>::decode_proto(&bytes).unwrap();
+        assert_eq!(decoded, update);
+
+        // Unknown fields are skipped; a missing nested message is an error.
+        let mut extended = WesolowskiProof { pi: x.clone() }.encode_proto();
+        extended.extend_from_slice(&[0x10, 0x05, 0x1d, 1, 2, 3, 4]);
+        assert_eq!(WesolowskiProof::decode_proto(&extended).unwrap().pi, x);
+        assert!(WesolowskiProof::decode_proto(&[]).is_err());
+        assert!(ClassElem::decode_proto(&[0x0a, 0x05, 1]).is_err());
+
+        // Only `a` and `b` are sent, and they must fit the context's group.
+        let mut bytes = Vec::new();
+        write_field(&mut bytes, 1, &x.a.to_signed_bytes_be());
+        write_field(&mut bytes, 2, &x.b.to_signed_bytes_be());
+        assert_eq!(x.encode_proto(), bytes);
+        assert!(ClassElem::decode_proto(&[]).is_err());
+        let other = Mpz::from(-(Integer::from(1) << 511u32) - 7);
+        assert!(ClassGroup::with_discriminant(&other, || ClassElem::decode_proto(&bytes)).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! `serde` support, behind the `serde` feature.
+//!
+//! Every type serializes as a single byte string holding its canonical byte
+//! encoding, the one its `to_bytes` or the `vdf::codec` wire format already
+//! uses, so serde-based formats carry exactly the bytes the rest of the
+//! crate reads and writes. Discriminants are `Mpz`s. Deserializing checks
+//! what the type's own parser checks and no more: elements and proofs still
+//! need to be verified, while an accumulator checkpoint must be for the
+//! current group.
+
+use serde::de::{self, SeqAccess, Visitor};
+use std::fmt;
+
+/// Accepts a byte string however the format represents it; self-describing
+/// formats such as JSON write bytes as a sequence of numbers.
+pub(crate) struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a byte string")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut v = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(b) = seq.next_element()? {
+            v.push(b);
+        }
+        Ok(v)
+    }
+}
+
+/// Implements `Serialize` and `Deserialize` for `$ty` as the byte string
+/// `$to_bytes(&x)`, parsed back with `$from_bytes`.
+macro_rules! impl_serde_bytes {
+    ($ty:ty, $to_bytes:expr, $from_bytes:expr) => {
+        impl ::serde::Serialize for $ty {
+            fn serialize<S: ::serde::Serializer>(
+                &self,
+                s: S,
+            ) -> ::std::result::Result<S::Ok, S::Error> {
+                let to_bytes: fn(&$ty) -> Vec<u8> = $to_bytes;
+                s.serialize_bytes(&to_bytes(self))
+            }
+        }
+
+        impl<'de> ::serde::Deserialize<'de> for $ty {
+            fn deserialize<D: ::serde::Deserializer<'de>>(
+                d: D,
+            ) -> ::std::result::Result<Self, D::Error> {
+                let from_bytes: fn(&[u8]) -> ::anyhow::Result<$ty> = $from_bytes;
+                let bytes = d.deserialize_byte_buf($crate::serialize::BytesVisitor)?;
+                from_bytes(&bytes).map_err(::serde::de::Error::custom)
+            }
+        }
+    };
+}
+pub(crate) use impl_serde_bytes;
+
+#[cfg(test)]
+mod tests {
+    use crate::accumulator::Accumulator;
+    use crate::group::{ClassElem, ClassGroup, CLASS_GROUP_DISCRIMINANT};
+    use crate::num::Mpz;
+    use crate::proofs::{Poe, Poke2};
+    use rug::Integer;
+    use serde::{de::DeserializeOwned, Serialize};
+    use std::fmt::Debug;
+
+    fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(x: &T) {
+        let json = serde_json::to_string(x).unwrap();
+        assert_eq!(serde_json::from_str::<T>(&json).unwrap(), *x);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(&*CLASS_GROUP_DISCRIMINANT);
+        let mut neg = Mpz::from(5);
+        neg.neg_mut();
+        round_trip(&neg);
+        let g = ClassGroup::unknown_order_elem();
+        let w = ClassGroup::pow(&g, &Integer::from(1000));
+        round_trip(&w);
+        round_trip(&Poe::<ClassGroup>::prove(&g, &Integer::from(1000), &w));
+        round_trip(&Poke2::<ClassGroup>::prove(&g, &w, &Integer::from(1000)));
+        let (acc, witnesses) = Accumulator::<ClassGroup>::empty().add(&[b"a", b"b"]);
+        round_trip(&acc);
+        round_trip(&witnesses[0]);
+        round_trip(&acc.prove_membership(&[b"a"], &witnesses[0]).unwrap());
+
+        // The bytes are those of the canonical encoding, and trailing or
+        // truncated input is rejected.
+        let bytes: Vec<u8> = serde_json::from_str(&serde_json::to_string(&acc).unwrap()).unwrap();
+        assert_eq!(bytes, acc.to_bytes());
+        let json = serde_json::to_string(&[&bytes[..], &[0]].concat()).unwrap();
+        assert!(serde_json::from_str::<Accumulator>(&json).is_err());
+        let bytes: Vec<u8> = serde_json::from_str(&serde_json::to_string(&w).unwrap()).unwrap();
+        let json = serde_json::to_string(&bytes[..bytes.len() - 1]).unwrap();
+        assert!(serde_json::from_str::<ClassElem>(&json).is_err());
+    }
+}
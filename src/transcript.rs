@@ -0,0 +1,86 @@
+//! The Fiat-Shamir transcript layer shared by every proof in the crate.
+//!
+//! `TranscriptProtocol` extends `bacteria::Transcript`, a Merlin-style
+//! transcript, with typed appends for group elements and integers and with
+//! the challenges the proofs draw. A proof that runs as one step of a larger
+//! protocol takes the caller's transcript and opens with `domain_sep`, which
+//! binds the step and the group, so each step's challenges depend on
+//! everything before it and steps can never be confused with one another.
+
+use crate::group::UnknownOrderGroup;
+use crate::hash::hash_to_prime;
+use bacteria::Transcript;
+use rug::integer::Order;
+use rug::Integer;
+
+pub trait TranscriptProtocol {
+    /// Starts the sub-protocol `protocol` in the group `G`.
+    fn domain_sep<G: UnknownOrderGroup>(&mut self, protocol: &'static [u8]);
+
+    fn append_elem<G: UnknownOrderGroup>(&mut self, label: &'static [u8], elem: &G::Elem);
+
+    /// Appends the magnitude of `x`, big-endian, and then its sign.
+    fn append_integer(&mut self, label: &'static [u8], x: &Integer);
+
+    /// A 256-bit prime, hashed from the transcript as by `hash_to_prime`.
+    fn challenge_prime(&mut self, label: &'static [u8]) -> Integer;
+
+    /// A uniform non-negative integer below `2^bits`. `bits` must be a
+    /// multiple of 8.
+    fn challenge_integer(&mut self, label: &'static [u8], bits: u32) -> Integer;
+}
+
+impl TranscriptProtocol for Transcript {
+    fn domain_sep<G: UnknownOrderGroup>(&mut self, protocol: &'static [u8]) {
+        self.append_message(b"dom-sep", protocol);
+        G::append_params(self);
+    }
+
+    fn append_elem<G: UnknownOrderGroup>(&mut self, label: &'static [u8], elem: &G::Elem) {
+        G::append_elem(self, label, elem);
+    }
+
+    fn append_integer(&mut self, label: &'static [u8], x: &Integer) {
+        self.append_message(label, &x.to_digits::<u8>(Order::Msf));
+        self.append_u64(b"sign", (*x < 0) as u64);
+    }
+
+    fn challenge_prime(&mut self, label: &'static [u8]) -> Integer {
+        let mut seed = [0u8; 32];
+        self.challenge_bytes(label, &mut seed);
+        hash_to_prime(&seed)
+    }
+
+    fn challenge_integer(&mut self, label: &'static [u8], bits: u32) -> Integer {
+        debug_assert_eq!(bits % 8, 0);
+        let mut bytes = vec![0u8; (bits / 8) as usize];
+        self.challenge_bytes(label, &mut bytes);
+        Integer::from_digits(&bytes, Order::Lsf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::ClassGroup;
+
+    #[test]
+    fn test_domain_separation() {
+        let g = ClassGroup::unknown_order_elem();
+        let challenge = |protocol: &'static [u8], x: i32| {
+            let mut transcript = Transcript::new(b"test");
+            transcript.domain_sep::<ClassGroup>(protocol);
+            transcript.append_elem::<ClassGroup>(b"g", &g);
+            transcript.append_integer(b"x", &Integer::from(x));
+            transcript.challenge_prime(b"l")
+        };
+        let l = challenge(b"one", 5);
+        assert!(l.is_probably_prime(30) != rug::integer::IsPrime::No);
+        assert_eq!(l, challenge(b"one", 5));
+        assert_ne!(l, challenge(b"two", 5));
+        assert_ne!(l, challenge(b"one", -5));
+
+        let mut transcript = Transcript::new(b"test");
+        assert!(transcript.challenge_integer(b"c", 128).significant_bits() <= 128);
+    }
+}
@@ -1,19 +1,36 @@
-//! Zero-allocation U256 and U512 types built on GMP. We created this module specifically for our
-//! use case of implementing primality checking for 256-bit integers, but it may be worth
-//! polishing a bit for more general use.
+//! Zero-allocation U256, U512, U1024 and U2048 types built on GMP. We created this module
+//! specifically for our use case of implementing primality checking for 256-bit integers; the wider
+//! types are sized for class group coefficients at the usual discriminant sizes.
+//! All arithmetic goes through GMP's `mpn` functions on the fixed limb arrays, which never
+//! allocate, rather than through `mpz_t`. Each type converts to `Mpz`, and back from any
+//! non-negative `Mpz` that fits, so code can switch to fixed width when its operands are small.
 //! Obviously there are a lot of unsafe {} blocks to work with GMP. Take care when using this module
 //! because there may be bugs we did not catch.
 #![allow(clippy::cast_sign_loss)]
 
+use crate::num::Mpz;
 use gmp_mpfr_sys::gmp;
 use gmp_mpfr_sys::gmp::mpz_t;
 use rug::integer::Order;
 use rug::Integer;
 use std::cmp::{min, Ord, Ordering, PartialOrd};
-use std::convert::From;
+use std::convert::{From, TryFrom};
+use std::fmt;
 use std::mem::transmute;
 use std::ops;
 
+/// Why an `Mpz` could not become a fixed-width integer: it is negative or too wide.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TryFromMpzError;
+
+impl fmt::Display for TryFromMpzError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("integer out of range for fixed-width type")
+    }
+}
+
+impl std::error::Error for TryFromMpzError {}
+
 macro_rules! u_types {
   ($($t:ident,$size:expr),+) => {
     $(
@@ -134,6 +151,74 @@ macro_rules! u_types {
           unsafe { gmp::mpn_set_str(x.data(), &bytes[0] as *const u8, bytes.len(), 256) };
           x
         }
+
+        /// The number of bits needed to write `self`, zero for zero.
+        pub fn bits(&self) -> u32 {
+          match self.size {
+            0 => 0,
+            n => n as u32 * gmp::LIMB_BITS as u32 - self.limbs[n as usize - 1].leading_zeros(),
+          }
+        }
+
+        /// `self + x`, or `None` if it overflows.
+        pub fn checked_add(self, x: Self) -> Option<Self> {
+          let mut y = self;
+          let carry = unsafe { gmp::mpn_add_n(y.limbs.as_mut_ptr(), self.data(), x.data(), $size) };
+          y.normalize_size();
+          if carry == 0 { Some(y) } else { None }
+        }
+
+        /// `self - x`, or `None` if it is negative.
+        pub fn checked_sub(self, x: Self) -> Option<Self> {
+          let mut y = self;
+          let borrow = unsafe { gmp::mpn_sub_n(y.limbs.as_mut_ptr(), self.data(), x.data(), $size) };
+          y.normalize_size();
+          if borrow == 0 { Some(y) } else { None }
+        }
+
+        /// `self * x`, or `None` if it overflows.
+        pub fn checked_mul(self, x: &Self) -> Option<Self> {
+          if self.is_zero() || x.is_zero() {
+            return Some(Self::zero());
+          }
+          // The product has at least `self.size + x.size - 1` limbs.
+          if self.size + x.size > $size + 1 {
+            return None;
+          }
+          // `mpn_mul` wants the longer operand first.
+          let (a, b) = if self.size >= x.size { (&self, x) } else { (x, &self) };
+          let mut wide = [0u64; $size * 2];
+          unsafe { gmp::mpn_mul(wide.as_mut_ptr(), a.data(), a.size, b.data(), b.size) };
+          if wide[$size..].iter().any(|&l| l != 0) {
+            return None;
+          }
+          let mut limbs = [0; $size];
+          limbs.copy_from_slice(&wide[..$size]);
+          Some(Self::from(limbs))
+        }
+
+        /// `(self / x, self % x)`. Panics if `x` is zero.
+        pub fn div_rem(self, x: &Self) -> (Self, Self) {
+          assert!(!x.is_zero(), "attempt to divide by zero");
+          if x.size > self.size {
+            return (Self::zero(), self);
+          }
+          let (mut q, mut r) = (Self::zero(), Self::zero());
+          unsafe {
+            gmp::mpn_tdiv_qr(
+              q.limbs.as_mut_ptr(),
+              r.limbs.as_mut_ptr(),
+              0,
+              self.data(),
+              self.size,
+              x.data(),
+              x.size,
+            )
+          };
+          q.normalize_size();
+          r.normalize_size();
+          (q, r)
+        }
       }
 
       impl PartialEq<u64> for $t {
@@ -210,12 +295,17 @@ macro_rules! u_types {
         }
       }
 
+      /// Bits shifted past the top are dropped.
       impl ops::ShlAssign<u32> for $t {
-        fn shl_assign(&mut self, mut x: u32) {
-          while x != 0 {
-            let sz = min(gmp::LIMB_BITS as u32, x);
-            x -= sz;
-            unsafe { gmp::mpn_lshift(self.data(), self.data(), $size, sz) };
+        fn shl_assign(&mut self, x: u32) {
+          let (limbs, bits) = ((x / gmp::LIMB_BITS as u32) as usize, x % gmp::LIMB_BITS as u32);
+          let limbs = min(limbs, $size);
+          self.limbs.copy_within(..$size - limbs, limbs);
+          for l in &mut self.limbs[..limbs] {
+            *l = 0;
+          }
+          if bits != 0 {
+            unsafe { gmp::mpn_lshift(self.data(), self.data(), $size, bits) };
           }
           self.normalize_size();
         }
@@ -231,11 +321,15 @@ macro_rules! u_types {
       }
 
       impl ops::ShrAssign<u32> for $t {
-        fn shr_assign(&mut self, mut x: u32) {
-          while x != 0 {
-            let sz = min(gmp::LIMB_BITS as u32, x);
-            x -= sz;
-            unsafe { gmp::mpn_rshift(self.data(), self.data(), $size, sz) };
+        fn shr_assign(&mut self, x: u32) {
+          let (limbs, bits) = ((x / gmp::LIMB_BITS as u32) as usize, x % gmp::LIMB_BITS as u32);
+          let limbs = min(limbs, $size);
+          self.limbs.copy_within(limbs.., 0);
+          for l in &mut self.limbs[$size - limbs..] {
+            *l = 0;
+          }
+          if bits != 0 {
+            unsafe { gmp::mpn_rshift(self.data(), self.data(), $size, bits) };
           }
           self.normalize_size();
         }
@@ -313,24 +407,9 @@ macro_rules! u_types {
 
       impl ops::Rem<&Self> for $t {
         type Output = Self;
+        /// Panics if `x` is zero.
         fn rem(self, x: &Self) -> Self {
-          if x.size > self.size {
-            return self;
-          }
-          let (y, mut rem) = (Self::zero(), Self::zero());
-          unsafe {
-            gmp::mpn_tdiv_qr(
-              y.data(),
-              rem.data(),
-              0,
-              self.data(),
-              self.size,
-              x.data(),
-              x.size,
-            )
-          };
-          rem.normalize_size();
-          rem
+          self.div_rem(x).1
         }
       }
 
@@ -344,22 +423,7 @@ macro_rules! u_types {
 
       impl ops::RemAssign<&Self> for $t {
         fn rem_assign(&mut self, x: &Self) {
-          if x.size > self.size {
-            return;
-          }
-          let y = Self::zero();
-          unsafe {
-            gmp::mpn_tdiv_qr(
-              y.data(),
-              self.data(),
-              0,
-              self.data(),
-              self.size,
-              x.data(),
-              x.size,
-            )
-          };
-          self.normalize_size();
+          *self = *self % x;
         }
       }
 
@@ -372,24 +436,9 @@ macro_rules! u_types {
 
       impl ops::Div<&Self> for $t {
         type Output = Self;
+        /// Panics if `x` is zero.
         fn div(self, x: &Self) -> Self {
-          if x.size > self.size {
-            return self;
-          }
-          let (mut y, rem) = ($t::zero(), $t::zero());
-          unsafe {
-            gmp::mpn_tdiv_qr(
-              y.data(),
-              rem.data(),
-              0,
-              self.data(),
-              self.size,
-              x.data(),
-              x.size,
-            )
-          };
-          y.normalize_size();
-          y
+          self.div_rem(x).0
         }
       }
 
@@ -401,76 +450,151 @@ macro_rules! u_types {
         }
       }
 
+      impl ops::DivAssign<&Self> for $t {
+        fn div_assign(&mut self, x: &Self) {
+          *self = *self / x;
+        }
+      }
+
+      impl ops::DivAssign for $t {
+        fn div_assign(&mut self, x: Self) {
+          #![allow(clippy::op_ref)]
+          *self /= &x;
+        }
+      }
+
+      impl ops::MulAssign<&Self> for $t {
+        /// Panics if the product overflows.
+        fn mul_assign(&mut self, x: &Self) {
+          *self = self.checked_mul(x).expect("attempt to multiply with overflow");
+        }
+      }
+
+      impl ops::MulAssign for $t {
+        fn mul_assign(&mut self, x: Self) {
+          *self *= &x;
+        }
+      }
+
       impl From<$t> for Integer {
         fn from(x: $t) -> Integer {
           Integer::from_digits(&x.limbs, Order::Lsf)
         }
       }
+
+      impl From<$t> for Mpz {
+        fn from(x: $t) -> Mpz {
+          Mpz::from(Integer::from(x))
+        }
+      }
+
+      impl TryFrom<&Mpz> for $t {
+        type Error = TryFromMpzError;
+        fn try_from(x: &Mpz) -> Result<Self, TryFromMpzError> {
+          if x.sgn() < 0 || x.bit_length() > $size * gmp::LIMB_BITS as usize {
+            return Err(TryFromMpzError);
+          }
+          let mut limbs = [0; $size];
+          x.as_integer().write_digits(&mut limbs, Order::Lsf);
+          Ok(Self::from(limbs))
+        }
+      }
     )+
   }
 }
 
-u_types!(U256, 4, U512, 8);
+u_types!(U256, 4, U512, 8, U1024, 16, U2048, 32);
 
-impl U512 {
-    /// Returns the lower half of this U512 as a U256.
-    /// TODO: Make checked?
-    pub fn low_u256(self) -> U256 {
-        let mut x = unsafe { transmute::<Self, (U256, [u64; 4])>(self) }.0;
-        x.normalize_size();
-        x
-    }
-}
+/// Conversions and mixed operations between `$half` and `$full`, which has twice as many limbs.
+macro_rules! wide_types {
+  ($($half:ident, $hsize:expr, $full:ident, $low:ident);+) => {
+    $(
+      impl $full {
+        /// Returns the lower half of this number as the narrower type.
+        /// TODO: Make checked?
+        pub fn $low(self) -> $half {
+          let mut limbs = [0; $hsize];
+          limbs.copy_from_slice(&self.limbs[..$hsize]);
+          $half::from(limbs)
+        }
+      }
 
-impl From<&U256> for U512 {
-    fn from(x: &U256) -> Self {
-        let mut limbs = [0; 8];
-        limbs[..4].copy_from_slice(&x.limbs);
-        Self {
+      impl From<&$half> for $full {
+        fn from(x: &$half) -> Self {
+          let mut limbs = [0; $hsize * 2];
+          limbs[..$hsize].copy_from_slice(&x.limbs);
+          Self {
             size: x.size,
             limbs,
+          }
         }
-    }
-}
-
-impl From<U256> for U512 {
-    fn from(x: U256) -> Self {
-        Self::from(&x)
-    }
-}
+      }
 
-// This gets its own implementation for performance.
-impl ops::Rem<&U256> for U512 {
-    type Output = U256;
-    fn rem(self, x: &U256) -> U256 {
-        if x.size > self.size {
-            return self.low_u256();
+      impl From<$half> for $full {
+        fn from(x: $half) -> Self {
+          Self::from(&x)
         }
-        let (y, mut rem) = (Self::zero(), U256::zero());
-        unsafe {
+      }
+
+      // This gets its own implementation for performance.
+      impl ops::Rem<&$half> for $full {
+        type Output = $half;
+        /// Panics if `x` is zero.
+        fn rem(self, x: &$half) -> $half {
+          assert!(!x.is_zero(), "attempt to divide by zero");
+          if x.size > self.size {
+            return self.$low();
+          }
+          let (mut y, mut rem) = (Self::zero(), $half::zero());
+          unsafe {
             gmp::mpn_tdiv_qr(
-                y.data(),
-                rem.data(),
-                0,
-                self.data(),
-                self.size,
-                x.data(),
-                x.size,
+              y.limbs.as_mut_ptr(),
+              rem.limbs.as_mut_ptr(),
+              0,
+              self.data(),
+              self.size,
+              x.data(),
+              x.size,
             )
-        };
-        rem.normalize_size();
-        rem
-    }
-}
+          };
+          rem.normalize_size();
+          rem
+        }
+      }
 
-impl ops::Rem<U256> for U512 {
-    type Output = U256;
-    fn rem(self, x: U256) -> U256 {
-        #![allow(clippy::op_ref)]
-        self % &x
-    }
+      impl ops::Rem<$half> for $full {
+        type Output = $half;
+        fn rem(self, x: $half) -> $half {
+          #![allow(clippy::op_ref)]
+          self % &x
+        }
+      }
+
+      /// It turns out to be faster to provide multiplication into the type twice as wide (e.g.
+      /// U256 * U256 -> U512), because it lets us use `mpn_mul_n` instead of `mpn_mul`.
+      impl ops::Mul<&Self> for $half {
+        type Output = $full;
+        fn mul(self, x: &Self) -> $full {
+          let mut y = $full::zero();
+          unsafe { gmp::mpn_mul_n(y.limbs.as_mut_ptr(), self.data(), x.data(), $hsize) };
+          y.normalize_size();
+          y
+        }
+      }
+
+      impl ops::Mul for $half {
+        type Output = $full;
+        fn mul(self, x: Self) -> $full {
+          #![allow(clippy::op_ref)]
+          self * &x
+        }
+      }
+    )+
+  }
 }
 
+wide_types!(U256, 4, U512, low_u256; U512, 8, U1024, low_u512; U1024, 16, U2048, low_u1024);
+
 impl U256 {
     /// Returns (result of removing all `f`s, number of `f`s removed)
     pub fn remove_factor(self, f: Self) -> (Self, u64) {
@@ -485,26 +609,6 @@ impl U256 {
     }
 }
 
-/// It turns out to be faster to provide multiplication as U256 * U256 -> U512, because it lets us
-/// use `mpn_mul_n` instead of `mpn_mul`.
-impl ops::Mul<&Self> for U256 {
-    type Output = U512;
-    fn mul(self, x: &Self) -> U512 {
-        let mut y = U512::zero();
-        unsafe { gmp::mpn_mul_n(y.data(), self.data(), x.data(), 4) };
-        y.normalize_size();
-        y
-    }
-}
-
-impl ops::Mul for U256 {
-    type Output = U512;
-    fn mul(self, x: Self) -> U512 {
-        #![allow(clippy::op_ref)]
-        self * &x
-    }
-}
-
 #[allow(unused_mut)]
 fn mut_ptr<T>(mut t: &T) -> *mut T {
     t as *const T as *mut T
@@ -524,6 +628,20 @@ where
     U512::from(t)
 }
 
+pub fn u1024<T>(t: T) -> U1024
+where
+    U1024: From<T>,
+{
+    U1024::from(t)
+}
+
+pub fn u2048<T>(t: T) -> U2048
+where
+    U2048: From<T>,
+{
+    U2048::from(t)
+}
+
 fn i32_to_mpz(i: i32, data: &mut u64) -> mpz_t {
     *data = i.abs() as u64;
     mpz_t {
@@ -596,4 +714,46 @@ mod tests {
         assert!(u512(6) % u256(3) == u256(0));
         assert!(u512([1, 0, 1, 0, 0, 0, 0, 0]) % u256([0, 1, 0, 0]) == u256(1));
     }
+
+    #[test]
+    fn test_wide() {
+        let x = u2048(1) << 2000;
+        let y = u2048(3) << 1000;
+        assert!(x.bits() == 2001 && y.bits() == 1002);
+        assert!(x > y && (x + y) - y == x);
+        assert!(x / y == (u2048(1) << 1000) / u2048(3));
+        assert!((x % y) >> 1000 == u2048(1));
+        assert!(u256(1) / u256([0, 1, 0, 0]) == u256(0));
+        assert!(u256(0).checked_sub(u256(1)).is_none());
+        assert!(y.checked_mul(&y) == Some(u2048(9) << 2000));
+        assert!(x.checked_mul(&y).is_none());
+        assert!(U1024::from(u512(5)) * U1024::from(u512(7)) == u2048(35));
+        assert!((u2048(6) << 1500) % (u1024(7) << 900) == u1024(6) << 900);
+        assert!((u1024(9) << 64) >> 64 == u1024(9));
+        assert!(u1024(9) << 1024 == u1024(0));
+        let mut z = u512(10);
+        z *= u512(10);
+        z /= u512(3);
+        assert!(z == u512(33));
+    }
+
+    #[test]
+    fn test_mpz() {
+        let x = u1024([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+        let m = Mpz::from(x);
+        assert!(m.bit_length() == 965);
+        assert!(U1024::try_from(&m) == Ok(x));
+        assert!(U2048::try_from(&m) == Ok(U2048::from(x)));
+        assert!(U512::try_from(&m) == Err(TryFromMpzError));
+        assert!(U256::try_from(&Mpz::zero()) == Ok(u256(0)));
+        assert!(Mpz::from(u256(0)) == Mpz::zero());
+        let mut neg = Mpz::default();
+        neg.set_si(-1);
+        assert!(U256::try_from(&neg) == Err(TryFromMpzError));
+        let max = Mpz::from(u256([u64::MAX; 4]));
+        assert!(U256::try_from(&max) == Ok(u256([u64::MAX; 4])));
+        let mut over = max.clone();
+        over.add_ui_mut(1);
+        assert!(U256::try_from(&over).is_err());
+    }
 }
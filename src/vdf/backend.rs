@@ -0,0 +1,146 @@
+//! A common interface to the delay functions in this crate.
+//!
+//! The class group implementation is the one to deploy; others exist to
+//! benchmark and cross-check it through the same interface. Any group of
+//! unknown order becomes a `Vdf` with a Wesolowski proof by implementing
+//! `WesolowskiGroup`.
+
+use super::{wesolowski, ChallengePolicy};
+use crate::group::{ClassElem, ClassGroup, UnknownOrderGroup};
+use rug::Integer;
+
+/// A delay function `x -> y` of `iterations` sequential steps, with a proof
+/// that is checked faster than `y` is recomputed.
+pub trait Vdf {
+    type Elem: Clone + std::fmt::Debug + Eq;
+    type Proof: Clone + std::fmt::Debug + Eq;
+
+    fn eval(x: &Self::Elem, iterations: u64) -> Self::Elem;
+
+    fn prove(x: &Self::Elem, y: &Self::Elem, iterations: u64) -> Self::Proof;
+
+    fn verify(x: &Self::Elem, y: &Self::Elem, iterations: u64, proof: &Self::Proof) -> bool;
+}
+
+/// A group in which `y = x^(2^T)` is a `Vdf` with the Wesolowski proof
+/// `pi = x^floor(2^T / l)`.
+pub trait WesolowskiGroup: UnknownOrderGroup {
+    /// Derives the challenge prime for the statement `y = x^(2^iterations)`.
+    fn challenge(x: &Self::Elem, y: &Self::Elem, iterations: u64) -> Integer;
+
+    /// Whether a proof element received from a prover is a group element.
+    fn validate(_pi: &Self::Elem) -> bool {
+        true
+    }
+}
+
+impl<G: WesolowskiGroup> Vdf for G {
+    type Elem = G::Elem;
+    type Proof = G::Elem;
+
+    fn eval(x: &G::Elem, iterations: u64) -> G::Elem {
+        let mut y = x.clone();
+        for _ in 0..iterations {
+            G::square(&mut y);
+        }
+        y
+    }
+
+    fn prove(x: &G::Elem, y: &G::Elem, iterations: u64) -> G::Elem {
+        wesolowski::prove_in::<G>(x, &G::challenge(x, y, iterations), iterations)
+    }
+
+    fn verify(x: &G::Elem, y: &G::Elem, iterations: u64, pi: &G::Elem) -> bool {
+        G::validate(pi)
+            && wesolowski::verify_in::<G>(x, y, pi, &G::challenge(x, y, iterations), iterations)
+    }
+}
+
+/// Agrees with `wesolowski::prove` and `wesolowski::verify` in the current
+/// group.
+impl WesolowskiGroup for ClassGroup {
+    fn challenge(x: &ClassElem, y: &ClassElem, iterations: u64) -> Integer {
+        ChallengePolicy::default().challenge(x, y, iterations)
+    }
+
+    fn validate(pi: &ClassElem) -> bool {
+        ClassGroup::validate(&pi.a, &pi.b, &pi.c)
+    }
+}
+
+#[cfg(feature = "rsa")]
+mod rsa {
+    use super::WesolowskiGroup;
+    use crate::group::{Rsa2048, RsaElem, UnknownOrderGroup};
+    use crate::transcript::TranscriptProtocol;
+    use bacteria::Transcript;
+    use rug::Integer;
+
+    impl WesolowskiGroup for Rsa2048 {
+        fn challenge(x: &RsaElem, y: &RsaElem, iterations: u64) -> Integer {
+            let mut transcript = Transcript::new(b"Classygroup.wesolowski.rsa");
+            Rsa2048::append_params(&mut transcript);
+            transcript.append_u64(b"iterations", iterations);
+            transcript.append_elem::<Rsa2048>(b"x", x);
+            transcript.append_elem::<Rsa2048>(b"y", y);
+            transcript.challenge_prime(b"l")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vdf::{Sloth, WesolowskiProof};
+
+    /// Checks `V` on the input `x`, with `tamper` producing a wrong proof if
+    /// proofs can be wrong.
+    fn check_vdf<V, F>(x: V::Elem, other: V::Elem, tamper: F)
+    where
+        V: Vdf,
+        F: Fn(&V::Proof) -> Option<V::Proof>,
+    {
+        for &t in &[1, 64, 300] {
+            let y = V::eval(&x, t);
+            assert_ne!(y, x);
+            let proof = V::prove(&x, &y, t);
+            assert!(V::verify(&x, &y, t, &proof));
+            assert!(!V::verify(&x, &other, t, &proof));
+            assert!(!V::verify(&x, &y, t + 1, &proof));
+            if let Some(bad) = tamper(&proof).filter(|_| t > 256) {
+                assert!(!V::verify(&x, &y, t, &bad));
+            }
+        }
+    }
+
+    #[cfg(feature = "prover")]
+    #[test]
+    fn test_class_group_vdf() {
+        let x = ClassGroup::unknown_order_elem();
+        check_vdf::<ClassGroup, _>(x.clone(), ClassGroup::id(), |pi| {
+            Some(ClassGroup::op(pi, &x))
+        });
+
+        let y = <ClassGroup as Vdf>::eval(&x, 300);
+        let mut expected = x.clone();
+        ClassGroup::repeated_square(&mut expected, 300);
+        assert_eq!(y, expected);
+        let pi = <ClassGroup as Vdf>::prove(&x, &y, 300);
+        assert_eq!(pi, wesolowski::prove(&x, &y, 300).pi);
+        assert!(wesolowski::verify(&x, &y, 300, &WesolowskiProof { pi }));
+    }
+
+    #[cfg(feature = "rsa")]
+    #[test]
+    fn test_rsa_vdf() {
+        use crate::group::{Group, Rsa2048};
+
+        let x = Rsa2048::unknown_order_elem();
+        check_vdf::<Rsa2048, _>(x.clone(), Rsa2048::id(), |pi| Some(Rsa2048::op(pi, &x)));
+    }
+
+    #[test]
+    fn test_sloth_vdf() {
+        check_vdf::<Sloth, _>(Integer::from(12345), Integer::from(1), |_| None);
+    }
+}
@@ -0,0 +1,45 @@
+//! Measuring the squaring rate, to turn a wall-clock delay into an
+//! iteration count.
+
+use crate::group::ClassGroup;
+use crate::num::Mpz;
+use std::time::{Duration, Instant};
+
+/// Squarings done between clock reads.
+const BATCH: u64 = 256;
+
+/// Squares the generator of the class group of `discriminant` for roughly
+/// `duration` and returns the observed number of squarings per second.
+///
+/// The rate depends on the discriminant size and on the machine, so deployments
+/// should calibrate with the discriminant they will evaluate in. The calling
+/// thread's class group context is restored before returning.
+pub fn calibrate(discriminant: &Mpz, duration: Duration) -> u64 {
+    ClassGroup::with_discriminant(discriminant, || {
+        let mut x = ClassGroup::unknown_order_elem_disc(discriminant);
+        let mut done = 0_u64;
+        let start = Instant::now();
+        loop {
+            ClassGroup::repeated_square(&mut x, BATCH);
+            done += BATCH;
+            let elapsed = start.elapsed();
+            if elapsed >= duration {
+                return (done as f64 / elapsed.as_secs_f64()) as u64;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::create_discriminant;
+
+    #[test]
+    fn test_calibrate() {
+        let disc = create_discriminant(b"calibrate", 512);
+        let rate = calibrate(&disc, Duration::from_millis(50));
+        assert!(rate > 0);
+        assert_ne!(ClassGroup::context_discriminant(), disc);
+    }
+}
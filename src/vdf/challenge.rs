@@ -0,0 +1,173 @@
+//! Fiat-Shamir derivation of the Wesolowski challenge prime.
+//!
+//! Prover and verifier must agree on every field of the `ChallengePolicy`;
+//! proofs made under one policy do not verify under another. In particular a
+//! proof made with a `watermark` only verifies with that same watermark, which
+//! attributes it to the prover that chose it.
+
+use crate::group::{ClassElem, ClassGroup, UnknownOrderGroup};
+use crate::hash::primality::is_prob_prime;
+use crate::transcript::TranscriptProtocol;
+use crate::uint::u256;
+use bacteria::Transcript;
+use mohan::hash::blake256;
+use rug::integer::IsPrime;
+use rug::Integer;
+
+/// The function that expands the statement transcript into prime candidates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChallengeHash {
+    /// Blake2b over a transcript seed and a counter, as in `hash_to_prime`.
+    Blake2b,
+    /// Candidates are squeezed straight out of the Strobe transcript.
+    Strobe,
+}
+
+/// Bit length of the challenge prime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PrimeBits {
+    Bits128,
+    Bits256,
+}
+
+impl PrimeBits {
+    fn bytes(self) -> usize {
+        match self {
+            PrimeBits::Bits128 => 16,
+            PrimeBits::Bits256 => 32,
+        }
+    }
+}
+
+/// How the challenge prime `l` is derived from a statement `y = x^(2^T)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChallengePolicy<'a> {
+    /// Domain separation tag, used as the transcript label.
+    pub domain: &'static [u8],
+    pub hash: ChallengeHash,
+    pub prime_bits: PrimeBits,
+    /// Miller-Rabin rounds a candidate must pass on top of Baillie-PSW.
+    pub mr_rounds: u32,
+    /// Prover identity, e.g. a public key or reward address, bound into the
+    /// transcript.
+    pub watermark: Option<&'a [u8]>,
+}
+
+impl Default for ChallengePolicy<'_> {
+    fn default() -> Self {
+        Self {
+            domain: b"Classygroup.wesolowski",
+            hash: ChallengeHash::Blake2b,
+            prime_bits: PrimeBits::Bits256,
+            mr_rounds: 0,
+            watermark: None,
+        }
+    }
+}
+
+impl<'a> ChallengePolicy<'a> {
+    /// The default policy with proofs bound to `prover`.
+    pub fn watermarked(prover: &'a [u8]) -> Self {
+        Self {
+            watermark: Some(prover),
+            ..Self::default()
+        }
+    }
+
+    /// Derives `l` for the statement `y = x^(2^iterations)` in the group of the
+    /// thread's current discriminant.
+    pub fn challenge(&self, x: &ClassElem, y: &ClassElem, iterations: u64) -> Integer {
+        let mut transcript = Transcript::new(self.domain);
+        ClassGroup::append_params(&mut transcript);
+        transcript.append_u64(b"iterations", iterations);
+        transcript.append_elem::<ClassGroup>(b"x", x);
+        transcript.append_elem::<ClassGroup>(b"y", y);
+        if let Some(prover) = self.watermark {
+            transcript.append_message(b"watermark", prover);
+        }
+
+        let mut seed = [0u8; 32];
+        transcript.challenge_bytes(b"l", &mut seed);
+
+        let len = self.prime_bits.bytes();
+        let mut counter = 0_u64;
+        loop {
+            // Candidates are read little-endian and made odd, like `hash_to_prime`.
+            let mut candidate = [0u8; 32];
+            match self.hash {
+                ChallengeHash::Blake2b => {
+                    let mut buf = seed.to_vec();
+                    buf.extend_from_slice(&counter.to_le_bytes());
+                    candidate[..len].copy_from_slice(&blake256(&buf).to_bytes()[..len]);
+                }
+                ChallengeHash::Strobe => {
+                    transcript.challenge_bytes(b"candidate", &mut candidate[..len]);
+                }
+            }
+            candidate[0] |= 1;
+            let candidate = u256(candidate);
+            if is_prob_prime(&candidate) {
+                let candidate = Integer::from(candidate);
+                if self.mr_rounds == 0 || candidate.is_probably_prime(self.mr_rounds) != IsPrime::No
+                {
+                    return candidate;
+                }
+            }
+            counter += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::hash_to_prime;
+
+    #[test]
+    fn test_policies_differ() {
+        let x = ClassGroup::unknown_order_elem();
+        let y = ClassGroup::op(&x, &x);
+
+        let default = ChallengePolicy::default();
+        let short = ChallengePolicy {
+            prime_bits: PrimeBits::Bits128,
+            ..default
+        };
+        let strobe = ChallengePolicy {
+            hash: ChallengeHash::Strobe,
+            ..default
+        };
+        let tagged = ChallengePolicy {
+            domain: b"another deployment",
+            ..default
+        };
+        let alice = ChallengePolicy::watermarked(b"alice");
+        let bob = ChallengePolicy::watermarked(b"bob");
+
+        let l = default.challenge(&x, &y, 1);
+        assert_eq!(l, default.challenge(&x, &y, 1));
+        assert!(l.significant_bits() <= 256);
+        assert!(short.challenge(&x, &y, 1).significant_bits() <= 128);
+        for policy in &[short, strobe, tagged, alice] {
+            assert_ne!(policy.challenge(&x, &y, 1), l);
+        }
+        assert_ne!(alice.challenge(&x, &y, 1), bob.challenge(&x, &y, 1));
+    }
+
+    #[test]
+    fn test_default_matches_hash_to_prime() {
+        let x = ClassGroup::unknown_order_elem();
+        let mut transcript = Transcript::new(b"Classygroup.wesolowski");
+        ClassGroup::append_params(&mut transcript);
+        transcript.append_u64(b"iterations", 0);
+        transcript.append_elem::<ClassGroup>(b"x", &x);
+        transcript.append_elem::<ClassGroup>(b"y", &x);
+        let mut seed = [0u8; 32];
+        transcript.challenge_bytes(b"l", &mut seed);
+
+        assert_eq!(
+            ChallengePolicy::default().challenge(&x, &x, 0),
+            hash_to_prime(&seed)
+        );
+    }
+}
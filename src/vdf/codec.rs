@@ -0,0 +1,254 @@
+//! Byte encoding of integers and elements shared by the crate's wire formats.
+//!
+//! An integer is a sign byte (1 for negative), a big-endian `u32` length and
+//! the big-endian magnitude. An element is its `a`, `b` and `c` in turn.
+//!
+//! A framed proof starts with a 4-byte magic naming the proof type, a
+//! version byte, the iteration count as a big-endian `u64` and the number of
+//! elements that follow as a big-endian `u32`.
+
+use crate::encoding::{json_decimal, json_object};
+use crate::group::{ClassElem, ClassGroup};
+use crate::num::Mpz;
+use anyhow::{ensure, Result};
+use rug::integer::Order;
+use rug::Integer;
+use std::convert::TryInto;
+use std::io::{Read, Write};
+
+/// The only framed proof version so far.
+const PROOF_VERSION: u8 = 1;
+
+/// Parses all of `bytes` with `read`, rejecting trailing bytes.
+pub(crate) fn read_all<T, F: FnOnce(&mut &[u8]) -> Result<T>>(bytes: &[u8], read: F) -> Result<T> {
+    let mut rest = bytes;
+    let x = read(&mut rest)?;
+    ensure!(rest.is_empty(), "trailing bytes after encoding");
+    Ok(x)
+}
+
+pub(crate) fn write_mpz(buf: &mut Vec<u8>, x: &Mpz) {
+    let bytes = x.to_bytes();
+    buf.push(x.is_neg() as u8);
+    buf.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    buf.extend_from_slice(&bytes);
+}
+
+pub(crate) fn read_mpz<R: Read>(r: &mut R) -> Result<Mpz> {
+    read_mpz_with(r, false)
+}
+
+/// Reads an integer, which with `strict` must be encoded exactly as
+/// `write_mpz` would: no leading zero bytes and no negative zero.
+pub(crate) fn read_mpz_with<R: Read>(r: &mut R, strict: bool) -> Result<Mpz> {
+    let mut head = [0u8; 5];
+    r.read_exact(&mut head)?;
+    ensure!(head[0] <= 1, "invalid sign byte {}", head[0]);
+    let mut len = [0u8; 4];
+    len.copy_from_slice(&head[1..]);
+    let len = u32::from_be_bytes(len) as u64;
+
+    // Grow the buffer as bytes arrive rather than trusting the length up front.
+    let mut bytes = Vec::new();
+    r.take(len).read_to_end(&mut bytes)?;
+    ensure!(bytes.len() as u64 == len, "integer is truncated");
+
+    let mut x = Mpz::from_bytes(&bytes);
+    if head[0] == 1 {
+        x.neg_mut();
+    }
+    if strict {
+        let mut canonical = Vec::new();
+        write_mpz(&mut canonical, &x);
+        ensure!(
+            canonical[0] == head[0] && canonical[5..] == bytes[..],
+            "integer is not canonically encoded"
+        );
+    }
+    Ok(x)
+}
+
+pub(crate) fn write_integer(buf: &mut Vec<u8>, x: &Integer) {
+    let bytes = x.to_digits::<u8>(Order::Msf);
+    buf.push((*x < 0) as u8);
+    buf.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    buf.extend_from_slice(&bytes);
+}
+
+/// Reads an integer in the same encoding as `read_mpz`.
+pub(crate) fn read_integer<R: Read>(r: &mut R) -> Result<Integer> {
+    Ok(Integer::from(read_mpz(r)?))
+}
+
+pub(crate) fn write_elem(buf: &mut Vec<u8>, x: &ClassElem) {
+    write_mpz(buf, &x.a);
+    write_mpz(buf, &x.b);
+    write_mpz(buf, &x.c);
+}
+
+/// Reads an element without checking that it belongs to any group.
+pub(crate) fn read_elem<R: Read>(r: &mut R) -> Result<ClassElem> {
+    let a = read_mpz(r)?;
+    let b = read_mpz(r)?;
+    let c = read_mpz(r)?;
+    Ok(ClassElem { a, b, c })
+}
+
+/// Reads an element, or `None` if `r` ends before its first byte. Ending
+/// anywhere else is an error.
+pub(crate) fn read_elem_or_eof<R: Read>(r: &mut R) -> Result<Option<ClassElem>> {
+    let mut first = [0u8; 1];
+    if r.read(&mut first)? == 0 {
+        return Ok(None);
+    }
+    read_elem(&mut (&first[..]).chain(r)).map(Some)
+}
+
+impl ClassElem {
+    /// Writes the element in the wire format of the crate's proofs and
+    /// checkpoints.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        let mut buf = Vec::new();
+        write_elem(&mut buf, self);
+        w.write_all(&buf)?;
+        Ok(())
+    }
+
+    /// Reads an element from `write_to`, reading no further than its end.
+    /// Group membership is not checked.
+    pub fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        read_elem(r)
+    }
+
+    /// The coefficients and the discriminant they give, as a JSON object of
+    /// decimal strings, for logs and comparing against other
+    /// implementations.
+    pub fn to_json_debug(&self) -> String {
+        json_object(&[
+            ("a", json_decimal(&self.a)),
+            ("b", json_decimal(&self.b)),
+            ("c", json_decimal(&self.c)),
+            (
+                "discriminant",
+                json_decimal(&ClassGroup::discriminant(&self.a, &self.b, &self.c)),
+            ),
+        ])
+    }
+
+    /// Like `read_from`, but with `strict` only accepts the bytes `write_to`
+    /// gives for a reduced element of the current group, so that no element
+    /// has two encodings.
+    pub fn read_checked<R: Read>(r: &mut R, strict: bool) -> Result<Self> {
+        if !strict {
+            return read_elem(r);
+        }
+        let a = read_mpz_with(r, true)?;
+        let b = read_mpz_with(r, true)?;
+        let c = read_mpz_with(r, true)?;
+        ensure!(
+            a.sgn() > 0 && ClassGroup::validate(&a, &b, &c),
+            "not an element of the current group"
+        );
+        let x = ClassElem { a, b, c };
+        ensure!(x.is_reduced(), "element is not reduced");
+        Ok(x)
+    }
+}
+
+/// Writes the header of a framed proof of `segments` elements.
+pub(crate) fn write_proof_header(
+    buf: &mut Vec<u8>,
+    magic: &[u8; 4],
+    iterations: u64,
+    segments: usize,
+) {
+    buf.extend_from_slice(magic);
+    buf.push(PROOF_VERSION);
+    buf.extend_from_slice(&iterations.to_be_bytes());
+    buf.extend_from_slice(&(segments as u32).to_be_bytes());
+}
+
+/// Reads the header of a framed proof and returns its iteration count and
+/// element count.
+pub(crate) fn read_proof_header<R: Read>(r: &mut R, magic: &[u8; 4]) -> Result<(u64, u32)> {
+    let mut head = [0u8; 17];
+    r.read_exact(&mut head)?;
+    ensure!(head[..4] == magic[..], "not a framed proof of this type");
+    ensure!(
+        head[4] == PROOF_VERSION,
+        "unsupported proof version {}",
+        head[4]
+    );
+    let iterations = u64::from_be_bytes(head[5..13].try_into().unwrap());
+    let segments = u32::from_be_bytes(head[13..].try_into().unwrap());
+    Ok((iterations, segments))
+}
+
+crate::encoding::impl_hex!(
+    ClassElem,
+    |x| {
+        let mut buf = Vec::new();
+        write_elem(&mut buf, x);
+        buf
+    },
+    |bytes| read_all(bytes, |r| read_elem(r))
+);
+
+#[cfg(feature = "serde")]
+crate::serialize::impl_serde_bytes!(
+    Mpz,
+    |x| {
+        let mut buf = Vec::new();
+        write_mpz(&mut buf, x);
+        buf
+    },
+    |bytes| read_all(bytes, |r| read_mpz(r))
+);
+
+#[cfg(feature = "serde")]
+crate::serialize::impl_serde_bytes!(
+    ClassElem,
+    |x| {
+        let mut buf = Vec::new();
+        write_elem(&mut buf, x);
+        buf
+    },
+    |bytes| read_all(bytes, |r| read_elem(r))
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(x: &ClassElem) -> Vec<u8> {
+        let mut buf = Vec::new();
+        x.write_to(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_read_checked() {
+        let g = ClassGroup::unknown_order_elem();
+        let bytes = encode(&g);
+        assert_eq!(ClassElem::read_checked(&mut &bytes[..], true).unwrap(), g);
+
+        // A leading zero byte in `a` decodes to the same element.
+        let mut padded = vec![0, 0, 0, 0, bytes[4] + 1, 0];
+        padded.extend_from_slice(&bytes[5..]);
+        assert_eq!(ClassElem::read_checked(&mut &padded[..], false).unwrap(), g);
+        assert!(ClassElem::read_checked(&mut &padded[..], true).is_err());
+        assert!(read_mpz_with(&mut &[1, 0, 0, 0, 0][..], true).is_err());
+
+        // `(c, -b, a)` is the same class but not reduced.
+        let mut neg_b = g.b.clone();
+        neg_b.neg_mut();
+        let swapped = ClassElem {
+            a: g.c.clone(),
+            b: neg_b,
+            c: g.a.clone(),
+        };
+        let bytes = encode(&swapped);
+        assert!(ClassElem::read_checked(&mut &bytes[..], false).is_ok());
+        assert!(ClassElem::read_checked(&mut &bytes[..], true).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+//! Choosing iteration counts that track a wall-clock target.
+
+use std::time::Duration;
+
+/// Recommends iteration counts so that evaluations take about `target`.
+///
+/// Each recommendation moves only `smoothing` of the way from the current
+/// count towards the ideal one, is at most a factor `max_factor` away from the
+/// current count and is kept within `[min_iterations, max_iterations]`. If
+/// the bounds cross, `max_iterations` wins.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DifficultyAdjuster {
+    pub target: Duration,
+    pub min_iterations: u64,
+    pub max_iterations: u64,
+    pub max_factor: f64,
+    /// Weight of the new estimate, in `(0, 1]`; 1 jumps straight to it.
+    pub smoothing: f64,
+}
+
+impl DifficultyAdjuster {
+    /// An adjuster for `target` that moves at most 2x per step and halfway
+    /// towards each new estimate.
+    pub fn new(target: Duration) -> Self {
+        Self {
+            target,
+            min_iterations: 1,
+            max_iterations: u64::MAX,
+            max_factor: 2.0,
+            smoothing: 0.5,
+        }
+    }
+
+    /// Recommends the next iteration count given the `current` one and recent
+    /// `(iterations, proving time)` observations.
+    ///
+    /// Returns `current` (clamped) if there are no usable observations.
+    pub fn next_iterations(&self, current: u64, observed: &[(u64, Duration)]) -> u64 {
+        let iterations: f64 = observed.iter().map(|&(n, _)| n as f64).sum();
+        let seconds: f64 = observed.iter().map(|(_, t)| t.as_secs_f64()).sum();
+        let current_f = current as f64;
+
+        let next = if iterations > 0.0 && seconds > 0.0 {
+            let ideal = iterations / seconds * self.target.as_secs_f64();
+            let smoothed = current_f + self.smoothing * (ideal - current_f);
+            smoothed
+                .max(current_f / self.max_factor)
+                .min(current_f * self.max_factor)
+        } else {
+            current_f
+        };
+
+        // `as` saturates, so huge estimates end up at `u64::MAX`.
+        (next.round() as u64)
+            .max(self.min_iterations)
+            .min(self.max_iterations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_iterations() {
+        let secs = Duration::from_secs;
+        let mut adj = DifficultyAdjuster::new(secs(10));
+
+        // Right on target.
+        assert_eq!(adj.next_iterations(1000, &[(1000, secs(10))]), 1000);
+        // Twice as fast as needed: ideal is 2000, half way there is 1500.
+        assert_eq!(adj.next_iterations(1000, &[(1000, secs(5))]), 1500);
+        // Rates are pooled across observations.
+        assert_eq!(
+            adj.next_iterations(1000, &[(1000, secs(2)), (3000, secs(14))]),
+            1750
+        );
+        // Far too fast: capped at a factor of two.
+        assert_eq!(adj.next_iterations(1000, &[(1000, secs(1))]), 2000);
+        assert_eq!(adj.next_iterations(1000, &[]), 1000);
+
+        adj.smoothing = 1.0;
+        adj.max_factor = 100.0;
+        assert_eq!(adj.next_iterations(1000, &[(1000, secs(1))]), 10_000);
+        adj.max_iterations = 5000;
+        assert_eq!(adj.next_iterations(1000, &[(1000, secs(1))]), 5000);
+        adj.min_iterations = 2000;
+        assert_eq!(adj.next_iterations(1000, &[]), 2000);
+        adj.min_iterations = 8000;
+        assert_eq!(adj.next_iterations(1000, &[]), 5000);
+    }
+}
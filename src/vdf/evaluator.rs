@@ -0,0 +1,209 @@
+//! Background evaluation with cancellation and progress.
+//!
+//! `Evaluator::spawn` moves an `EvalState` onto its own thread, which squares
+//! in chunks and checks for cancellation between them. The returned handle
+//! reports progress and delivers the outcome over a channel, so it can be
+//! polled from an event loop as well as waited on.
+
+use super::{CpuBackend, EvalState, SquaringBackend, WesolowskiProof};
+use crate::group::ClassElem;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// How a background evaluation ended.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EvalOutcome {
+    Finished(ClassElem, WesolowskiProof),
+    /// Cancelled before the last squaring; the state can be resumed or
+    /// snapshotted.
+    Cancelled(EvalState),
+}
+
+/// Runs evaluations on dedicated threads.
+#[derive(Clone)]
+pub struct Evaluator {
+    chunk: u64,
+    backend: Arc<dyn SquaringBackend>,
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self {
+            chunk: 1 << 16,
+            backend: Arc::new(CpuBackend),
+        }
+    }
+}
+
+impl fmt::Debug for Evaluator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Evaluator")
+            .field("chunk", &self.chunk)
+            .finish()
+    }
+}
+
+impl Evaluator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many squarings run between cancellation checks and progress
+    /// updates.
+    pub fn chunk(mut self, iterations: u64) -> Self {
+        self.chunk = std::cmp::max(iterations, 1);
+        self
+    }
+
+    /// Sets the squaring implementation, `CpuBackend` by default.
+    pub fn backend(mut self, backend: Arc<dyn SquaringBackend>) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Starts finishing `state` on a new thread.
+    pub fn spawn(&self, mut state: EvalState) -> EvalHandle {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let done = Arc::new(AtomicU64::new(state.iterations_done()));
+        let (tx, rx) = mpsc::channel();
+        let total = state.iterations();
+        let chunk = self.chunk;
+        let backend = self.backend.clone();
+
+        let (thread_cancelled, thread_done) = (cancelled.clone(), done.clone());
+        thread::spawn(move || {
+            while !state.is_finished() {
+                if thread_cancelled.load(Ordering::Relaxed) {
+                    let _ = tx.send(EvalOutcome::Cancelled(state));
+                    return;
+                }
+                state.advance_with(&*backend, chunk);
+                thread_done.store(state.iterations_done(), Ordering::Relaxed);
+            }
+            let (y, proof) = state.finish_with(&*backend);
+            // The handle may have been dropped; nobody is left to tell.
+            let _ = tx.send(EvalOutcome::Finished(y, proof));
+        });
+
+        EvalHandle {
+            cancelled,
+            done,
+            total,
+            outcome: rx,
+        }
+    }
+}
+
+/// A running evaluation. Dropping the handle lets the evaluation run to
+/// completion unobserved; call `cancel` to stop it.
+#[derive(Debug)]
+pub struct EvalHandle {
+    cancelled: Arc<AtomicBool>,
+    done: Arc<AtomicU64>,
+    total: u64,
+    outcome: Receiver<EvalOutcome>,
+}
+
+impl EvalHandle {
+    /// Asks the evaluation to stop at the next chunk boundary.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `(iterations done, total iterations)`, updated once per chunk.
+    pub fn progress(&self) -> (u64, u64) {
+        (self.done.load(Ordering::Relaxed), self.total)
+    }
+
+    /// Returns the outcome if the evaluation has ended, without blocking.
+    pub fn try_wait(&self) -> Option<EvalOutcome> {
+        match self.outcome.try_recv() {
+            Ok(outcome) => Some(outcome),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => panic!("evaluation thread panicked"),
+        }
+    }
+
+    /// Waits up to `timeout` for the outcome.
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<EvalOutcome> {
+        match self.outcome.recv_timeout(timeout) {
+            Ok(outcome) => Some(outcome),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => panic!("evaluation thread panicked"),
+        }
+    }
+
+    /// Blocks until the evaluation ends.
+    pub fn wait(self) -> EvalOutcome {
+        self.outcome.recv().expect("evaluation thread panicked")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vdf;
+
+    #[test]
+    fn test_spawn_finishes() {
+        let handle =
+            Evaluator::new()
+                .chunk(100)
+                .spawn(EvalState::from_seed(b"evaluator", 256, 1000));
+        match handle.wait() {
+            EvalOutcome::Finished(y, proof) => {
+                assert_eq!((y, proof), vdf::solve(b"evaluator", 256, 1000))
+            }
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn test_custom_backend() {
+        struct Counting(AtomicU64);
+
+        impl SquaringBackend for Counting {
+            fn repeated_square(&self, x: &mut ClassElem, iterations: u64) {
+                self.0.fetch_add(iterations, Ordering::Relaxed);
+                CpuBackend.repeated_square(x, iterations)
+            }
+        }
+
+        let backend = Arc::new(Counting(AtomicU64::new(0)));
+        let handle = Evaluator::new()
+            .chunk(64)
+            .backend(backend.clone())
+            .spawn(EvalState::from_seed(b"evaluator", 256, 500));
+        match handle.wait() {
+            EvalOutcome::Finished(y, proof) => {
+                assert_eq!((y, proof), vdf::solve(b"evaluator", 256, 500))
+            }
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+        assert_eq!(backend.0.load(Ordering::Relaxed), 500);
+    }
+
+    #[test]
+    fn test_cancel_and_resume() {
+        let handle =
+            Evaluator::new()
+                .chunk(10)
+                .spawn(EvalState::from_seed(b"evaluator", 256, u64::MAX));
+        while handle.progress().0 == 0 {
+            thread::yield_now();
+        }
+        handle.cancel();
+
+        let state = match handle.wait() {
+            EvalOutcome::Cancelled(state) => state,
+            outcome => panic!("unexpected {:?}", outcome),
+        };
+        assert!(state.iterations_done() > 0);
+        assert_eq!(state.iterations_done() % 10, 0);
+        assert!(EvalState::restore(&state.snapshot()).is_ok());
+    }
+}
@@ -0,0 +1,196 @@
+//! Verifiable delay function over the class group of a discriminant derived
+//! from a seed, as in the Chia VDF competition.
+//!
+//! Without the `prover` feature only verification against a known
+//! discriminant is compiled in.
+
+#[cfg(feature = "prover")]
+use crate::group::create_discriminant;
+use crate::group::{ClassElem, ClassGroup};
+use crate::num::Mpz;
+use anyhow::{ensure, Result};
+
+mod backend;
+pub use backend::{Vdf, WesolowskiGroup};
+
+#[cfg(feature = "prover")]
+mod calibrate;
+#[cfg(feature = "prover")]
+pub use calibrate::calibrate;
+
+mod challenge;
+pub use challenge::{ChallengeHash, ChallengePolicy, PrimeBits};
+
+pub(crate) mod codec;
+
+mod difficulty;
+pub use difficulty::DifficultyAdjuster;
+
+#[cfg(feature = "prover")]
+mod evaluator;
+#[cfg(feature = "prover")]
+pub use evaluator::{EvalHandle, EvalOutcome, Evaluator};
+
+mod sloth;
+pub use sloth::Sloth;
+
+#[cfg(feature = "prover")]
+mod squaring;
+#[cfg(feature = "prover")]
+pub use squaring::{CpuBackend, SquaringBackend};
+
+#[cfg(feature = "prover")]
+mod state;
+#[cfg(feature = "prover")]
+pub use state::EvalState;
+
+pub mod nweso;
+pub use nweso::NWesolowskiProof;
+
+mod params;
+pub use params::ProofParams;
+
+pub mod pietrzak;
+pub use pietrzak::PietrzakProof;
+
+pub mod poa;
+
+#[cfg(feature = "prover")]
+mod trapdoor;
+#[cfg(feature = "prover")]
+pub use trapdoor::{eval_with_trapdoor, prove_with_trapdoor, prove_with_trapdoor_with};
+
+pub mod wesolowski;
+pub use wesolowski::WesolowskiProof;
+
+/// Evaluates the VDF in one call.
+///
+/// Derives a `length`-bit discriminant from `seed`, squares the generator of
+/// its class group `iterations` times and proves the result. Returns the
+/// output together with its Wesolowski proof.
+///
+/// The calling thread's class group context is restored before returning.
+#[cfg(feature = "prover")]
+pub fn solve(seed: &[u8], length: u64, iterations: u64) -> (ClassElem, WesolowskiProof) {
+    let disc = create_discriminant(seed, length);
+    ClassGroup::with_discriminant(&disc, || {
+        let x = ClassGroup::unknown_order_elem_disc(&disc);
+        let mut y = x.clone();
+        ClassGroup::repeated_square(&mut y, iterations);
+        let proof = wesolowski::prove(&x, &y, iterations);
+        (y, proof)
+    })
+}
+
+/// Like `solve`, but spreads proof generation over `threads` threads.
+///
+/// The output and proof are identical to those of `solve`.
+#[cfg(feature = "prover")]
+pub fn solve_parallel(
+    seed: &[u8],
+    length: u64,
+    iterations: u64,
+    threads: usize,
+) -> (ClassElem, WesolowskiProof) {
+    let disc = create_discriminant(seed, length);
+    ClassGroup::with_discriminant(&disc, || {
+        let x = ClassGroup::unknown_order_elem_disc(&disc);
+        let (y, powers) = wesolowski::eval_with_powers(&x, iterations, threads);
+        let proof = wesolowski::prove_parallel(&x, &y, iterations, &powers);
+        (y, proof)
+    })
+}
+
+/// Checks an output and proof returned by `solve` for the same parameters.
+#[cfg(feature = "prover")]
+pub fn verify(
+    seed: &[u8],
+    length: u64,
+    iterations: u64,
+    y: &ClassElem,
+    proof: &WesolowskiProof,
+) -> bool {
+    verify_with_discriminant(&create_discriminant(seed, length), iterations, y, proof)
+}
+
+/// Like `verify`, but takes the discriminant instead of deriving it from a
+/// seed.
+pub fn verify_with_discriminant(
+    disc: &Mpz,
+    iterations: u64,
+    y: &ClassElem,
+    proof: &WesolowskiProof,
+) -> bool {
+    ClassGroup::with_discriminant(disc, || {
+        let x = ClassGroup::unknown_order_elem_disc(disc);
+        wesolowski::verify(&x, y, iterations, proof)
+    })
+}
+
+/// Encodes an output and its proof, as printed by `classy prove`.
+pub fn output_to_bytes(y: &ClassElem, proof: &WesolowskiProof) -> Vec<u8> {
+    let mut buf = Vec::new();
+    codec::write_elem(&mut buf, y);
+    codec::write_elem(&mut buf, &proof.pi);
+    buf
+}
+
+/// Parses the encoding of `output_to_bytes`. Group membership is left to
+/// `verify`.
+pub fn output_from_bytes(bytes: &[u8]) -> Result<(ClassElem, WesolowskiProof)> {
+    let mut rest = bytes;
+    let y = codec::read_elem(&mut rest)?;
+    let pi = codec::read_elem(&mut rest)?;
+    ensure!(rest.is_empty(), "trailing bytes after proof");
+    Ok((y, WesolowskiProof { pi }))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::group::CLASS_GROUP_DISCRIMINANT;
+    use rug::Integer;
+
+    #[test]
+    fn test_solve_verify() {
+        let (y, proof) = solve(b"\xaa", 512, 1000);
+        assert_eq!(
+            ClassGroup::context_discriminant(),
+            *CLASS_GROUP_DISCRIMINANT
+        );
+
+        assert!(verify(b"\xaa", 512, 1000, &y, &proof));
+        assert!(!verify(b"\xaa", 512, 999, &y, &proof));
+        assert!(!verify(b"\xab", 512, 1000, &y, &proof));
+
+        let disc = create_discriminant(b"\xaa", 512);
+        assert!(verify_with_discriminant(&disc, 1000, &y, &proof));
+    }
+
+    #[test]
+    fn test_output_bytes() {
+        let (y, proof) = solve(b"\xaa", 512, 100);
+        let bytes = output_to_bytes(&y, &proof);
+        assert_eq!(output_from_bytes(&bytes).unwrap(), (y, proof));
+        assert!(output_from_bytes(&bytes[1..]).is_err());
+        assert!(output_from_bytes(&[bytes.clone(), vec![0]].concat()).is_err());
+    }
+
+    #[test]
+    fn test_solve_parallel() {
+        assert_eq!(
+            solve_parallel(b"\xaa", 512, 300, 4),
+            solve(b"\xaa", 512, 300)
+        );
+    }
+
+    #[test]
+    fn test_solve_matches_pow() {
+        let (y, _) = solve(b"seed", 256, 64);
+        let disc = create_discriminant(b"seed", 256);
+        ClassGroup::with_discriminant(&disc, || {
+            let g = ClassGroup::unknown_order_elem_disc(&disc);
+            assert_eq!(y, ClassGroup::pow(&g, &(Integer::from(1) << 64)));
+        });
+    }
+}
@@ -0,0 +1,172 @@
+//! n-Wesolowski proofs: a long evaluation split into segments, each proved
+//! separately, as shipped by Chia timelords.
+//!
+//! Segment `i` claims `y_i = y_(i-1)^(2^T_i)` with `y_0 = x`. The verifier
+//! checks every segment's Wesolowski proof, that each segment starts where the
+//! previous one ended, and that the lengths add up to the whole evaluation.
+
+use super::wesolowski::{self, WesolowskiProof};
+use super::{ChallengePolicy, ProofParams};
+use crate::group::ClassElem;
+
+/// One link in the chain.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Segment {
+    pub iterations: u64,
+    /// The segment's output, which is the next segment's input.
+    pub y: ClassElem,
+    pub proof: WesolowskiProof,
+}
+
+/// A chain of segment proofs for `y = x^(2^T)`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NWesolowskiProof {
+    pub segments: Vec<Segment>,
+}
+
+/// Evaluates `x` through segments of the given lengths and proves each one.
+/// Returns the final output with the chained proof.
+#[cfg(feature = "prover")]
+pub fn prove(x: &ClassElem, lengths: &[u64]) -> (ClassElem, NWesolowskiProof) {
+    prove_with(&ChallengePolicy::default(), x, lengths)
+}
+
+/// Evaluates and proves `x^(2^iterations)` in `params.segments()` segments
+/// under `params.challenge_policy()`.
+#[cfg(feature = "prover")]
+pub fn prove_with_params(
+    params: &ProofParams,
+    x: &ClassElem,
+    iterations: u64,
+) -> (ClassElem, NWesolowskiProof) {
+    let lengths = params.segment_lengths(iterations);
+    prove_with(&params.challenge_policy(), x, &lengths)
+}
+
+/// `prove` with an explicit challenge policy.
+#[cfg(feature = "prover")]
+pub fn prove_with(
+    policy: &ChallengePolicy,
+    x: &ClassElem,
+    lengths: &[u64],
+) -> (ClassElem, NWesolowskiProof) {
+    use crate::group::ClassGroup;
+
+    let mut y = x.clone();
+    let mut segments = Vec::with_capacity(lengths.len());
+    for &iterations in lengths {
+        let start = y.clone();
+        ClassGroup::repeated_square(&mut y, iterations);
+        segments.push(Segment {
+            iterations,
+            y: y.clone(),
+            proof: wesolowski::prove_with(policy, &start, &y, iterations),
+        });
+    }
+    (y, NWesolowskiProof { segments })
+}
+
+/// Checks a chained proof that `y = x^(2^iterations)`.
+pub fn verify(x: &ClassElem, y: &ClassElem, iterations: u64, proof: &NWesolowskiProof) -> bool {
+    verify_with(&ChallengePolicy::default(), x, y, iterations, proof)
+}
+
+/// Checks a proof from `prove_with_params` with the same `params`, including
+/// its segment count.
+pub fn verify_with_params(
+    params: &ProofParams,
+    x: &ClassElem,
+    y: &ClassElem,
+    iterations: u64,
+    proof: &NWesolowskiProof,
+) -> bool {
+    proof.segments.len() == params.segments()
+        && verify_with(&params.challenge_policy(), x, y, iterations, proof)
+}
+
+/// `verify` with an explicit challenge policy.
+pub fn verify_with(
+    policy: &ChallengePolicy,
+    x: &ClassElem,
+    y: &ClassElem,
+    iterations: u64,
+    proof: &NWesolowskiProof,
+) -> bool {
+    let mut start = x;
+    let mut total = 0_u64;
+    for segment in &proof.segments {
+        total = match total.checked_add(segment.iterations) {
+            Some(total) => total,
+            None => return false,
+        };
+        if !wesolowski::verify_with(
+            policy,
+            start,
+            &segment.y,
+            segment.iterations,
+            &segment.proof,
+        ) {
+            return false;
+        }
+        start = &segment.y;
+    }
+    total == iterations && start == y
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::group::ClassGroup;
+
+    #[test]
+    fn test_prove_verify() {
+        let x = ClassGroup::unknown_order_elem();
+        let (y, proof) = prove(&x, &[300, 200, 500]);
+        assert_eq!(proof.segments.len(), 3);
+
+        let mut expected = x.clone();
+        ClassGroup::repeated_square(&mut expected, 1000);
+        assert_eq!(y, expected);
+
+        assert!(verify(&x, &y, 1000, &proof));
+        assert!(!verify(&x, &y, 999, &proof));
+        assert!(!verify(&x, &proof.segments[1].y, 1000, &proof));
+
+        let (empty_y, empty) = prove(&x, &[]);
+        assert_eq!(empty_y, x);
+        assert!(verify(&x, &x, 0, &empty));
+    }
+
+    #[test]
+    fn test_params() {
+        let x = ClassGroup::unknown_order_elem();
+        let params = ProofParams::new(10, 128, 4).unwrap();
+        let (y, proof) = prove_with_params(&params, &x, 1000);
+        assert_eq!(proof.segments.len(), 4);
+        assert!(verify_with_params(&params, &x, &y, 1000, &proof));
+        assert!(!verify(&x, &y, 1000, &proof));
+
+        let other = ProofParams::new(10, 128, 5).unwrap();
+        assert!(!verify_with_params(&other, &x, &y, 1000, &proof));
+    }
+
+    #[test]
+    fn test_rejects_broken_chain() {
+        let x = ClassGroup::unknown_order_elem();
+        let (y, proof) = prove(&x, &[300, 300]);
+
+        let mut reordered = proof.clone();
+        reordered.segments.swap(0, 1);
+        assert!(!verify(&x, &y, 600, &reordered));
+
+        // A valid segment that starts from `x` does not link up.
+        let mut spliced = proof.clone();
+        spliced.segments[1] = proof.segments[0].clone();
+        assert!(!verify(&x, &y, 600, &spliced));
+
+        let mut relabelled = proof;
+        relabelled.segments[0].iterations = 299;
+        relabelled.segments[1].iterations = 301;
+        assert!(!verify(&x, &y, 600, &relabelled));
+    }
+}
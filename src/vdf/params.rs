@@ -0,0 +1,115 @@
+//! Security parameters for proof generation, checked once up front.
+
+use super::{ChallengePolicy, PrimeBits};
+use anyhow::{bail, ensure, Result};
+
+/// Upper bound on `mr_rounds`; more rounds than this buy nothing.
+const MAX_MR_ROUNDS: u32 = 64;
+
+/// The knobs that trade proof cost for security.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ProofParams {
+    mr_rounds: u32,
+    challenge_bits: PrimeBits,
+    segments: usize,
+}
+
+impl Default for ProofParams {
+    /// A 256-bit Baillie-PSW challenge and a single segment, as used by
+    /// `vdf::solve`.
+    fn default() -> Self {
+        Self {
+            mr_rounds: 0,
+            challenge_bits: PrimeBits::Bits256,
+            segments: 1,
+        }
+    }
+}
+
+impl ProofParams {
+    /// Checks and bundles the parameters.
+    ///
+    /// `mr_rounds` Miller-Rabin rounds are run on challenge primes on top of
+    /// Baillie-PSW, `challenge_bits` must be 128 or 256, and evaluations are
+    /// split into `segments` n-Wesolowski segments.
+    pub fn new(mr_rounds: u32, challenge_bits: u32, segments: usize) -> Result<Self> {
+        ensure!(
+            mr_rounds <= MAX_MR_ROUNDS,
+            "at most {} Miller-Rabin rounds are supported, got {}",
+            MAX_MR_ROUNDS,
+            mr_rounds
+        );
+        let challenge_bits = match challenge_bits {
+            128 => PrimeBits::Bits128,
+            256 => PrimeBits::Bits256,
+            n => bail!("challenge primes must have 128 or 256 bits, got {}", n),
+        };
+        ensure!(segments > 0, "at least one segment is needed");
+        Ok(Self {
+            mr_rounds,
+            challenge_bits,
+            segments,
+        })
+    }
+
+    pub fn mr_rounds(&self) -> u32 {
+        self.mr_rounds
+    }
+
+    pub fn challenge_bits(&self) -> PrimeBits {
+        self.challenge_bits
+    }
+
+    pub fn segments(&self) -> usize {
+        self.segments
+    }
+
+    /// The default challenge policy with these parameters applied.
+    pub fn challenge_policy(&self) -> ChallengePolicy<'static> {
+        ChallengePolicy {
+            prime_bits: self.challenge_bits,
+            mr_rounds: self.mr_rounds,
+            ..ChallengePolicy::default()
+        }
+    }
+
+    /// Splits `iterations` into `segments` lengths, for `nweso::prove`. The
+    /// lengths differ by at most one, longest first.
+    pub fn segment_lengths(&self, iterations: u64) -> Vec<u64> {
+        let n = self.segments as u64;
+        (0..n)
+            .map(|i| iterations / n + (i < iterations % n) as u64)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::ClassGroup;
+
+    #[test]
+    fn test_validation() {
+        assert_eq!(ProofParams::new(0, 256, 1).unwrap(), ProofParams::default());
+        assert!(ProofParams::new(MAX_MR_ROUNDS + 1, 256, 1).is_err());
+        assert!(ProofParams::new(0, 192, 1).is_err());
+        assert!(ProofParams::new(0, 128, 0).is_err());
+
+        let params = ProofParams::new(20, 128, 3).unwrap();
+        assert_eq!(params.mr_rounds(), 20);
+        assert_eq!(params.challenge_bits(), PrimeBits::Bits128);
+        assert_eq!(params.segment_lengths(10), vec![4, 3, 3]);
+        assert_eq!(params.segment_lengths(2), vec![1, 1, 0]);
+    }
+
+    #[test]
+    fn test_challenge_policy() {
+        let x = ClassGroup::unknown_order_elem();
+        let y = ClassGroup::op(&x, &x);
+        let plain = ProofParams::default().challenge_policy();
+        let careful = ProofParams::new(30, 256, 1).unwrap().challenge_policy();
+        assert_eq!(plain, ChallengePolicy::default());
+        // Extra rounds only reject composites that slipped through.
+        assert_eq!(plain.challenge(&x, &y, 1), careful.challenge(&x, &y, 1));
+    }
+}
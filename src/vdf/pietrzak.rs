@@ -0,0 +1,294 @@
+//! Pietrzak's proof of exponentiation, "Simple verifiable delay functions".
+//! https://eprint.iacr.org/2018/627.pdf
+//!
+//! The statement `y = x^(2^T)` is halved repeatedly: the prover sends the
+//! midpoint `mu = x^(2^(T/2))` and both sides fold it into the statement
+//! `(x^r * mu)^(2^(T/2)) = mu^r * y` for a challenge `r`. An odd `T` is first
+//! made even by squaring `x`. The proof is the list of midpoints, one per
+//! halving, which stops once `T` is 1.
+
+use super::codec::{
+    read_all, read_elem, read_elem_or_eof, read_proof_header, write_elem, write_proof_header,
+};
+use crate::encoding::json_object;
+use crate::group::{ClassElem, ClassGroup, UnknownOrderGroup};
+use crate::transcript::TranscriptProtocol;
+use anyhow::{anyhow, ensure, Result};
+use bacteria::Transcript;
+use rug::Integer;
+use std::io::{Read, Write};
+
+const FRAME_MAGIC: &[u8; 4] = b"CGPP";
+
+/// A proof that `y = x^(2^T)` for some `x`, `y` and iteration count `T`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PietrzakProof {
+    pub midpoints: Vec<ClassElem>,
+}
+
+impl PietrzakProof {
+    /// Encodes the midpoints back to back, in the order `verify_stream` reads
+    /// them.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for mu in &self.midpoints {
+            write_elem(&mut buf, mu);
+        }
+        buf
+    }
+
+    /// Parses the encoding of `to_bytes`. The midpoints are checked by
+    /// `verify`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::read_from(&mut &bytes[..])
+    }
+
+    /// Writes the encoding of `to_bytes` one midpoint at a time.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.midpoints.iter().try_for_each(|mu| mu.write_to(w))
+    }
+
+    /// Reads midpoints until `r` ends, which must be between two of them.
+    pub fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        let mut midpoints = Vec::new();
+        while let Some(mu) = read_elem_or_eof(r)? {
+            midpoints.push(mu);
+        }
+        Ok(Self { midpoints })
+    }
+
+    /// The proof as JSON with every number in decimal, for debugging.
+    pub fn to_json_debug(&self) -> String {
+        let midpoints: Vec<_> = self.midpoints.iter().map(|mu| mu.to_json_debug()).collect();
+        json_object(&[("midpoints", format!("[{}]", midpoints.join(",")))])
+    }
+
+    /// The number of midpoints in a proof for `iterations`.
+    pub fn midpoint_count(iterations: u64) -> usize {
+        let (mut t, mut count) = (iterations, 0);
+        while t > 1 {
+            t /= 2;
+            count += 1;
+        }
+        count
+    }
+
+    /// Encodes the midpoints behind a header recording `iterations` and
+    /// their number, for sending to peers.
+    pub fn to_framed_bytes(&self, iterations: u64) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_proof_header(&mut buf, FRAME_MAGIC, iterations, self.midpoints.len());
+        buf.extend_from_slice(&self.to_bytes());
+        buf
+    }
+
+    /// Parses the encoding of `to_framed_bytes`, returning the iteration
+    /// count with the proof. The header must announce exactly the number of
+    /// midpoints a proof for its iteration count has, and exactly that many
+    /// elements must follow.
+    pub fn from_framed_bytes(bytes: &[u8]) -> Result<(u64, Self)> {
+        read_all(bytes, |r| {
+            let (iterations, segments) = read_proof_header(r, FRAME_MAGIC)?;
+            ensure!(
+                segments as usize == Self::midpoint_count(iterations),
+                "wrong number of midpoints for {} iterations",
+                iterations
+            );
+            let midpoints = (0..segments).map(|_| read_elem(r)).collect::<Result<_>>()?;
+            Ok((iterations, Self { midpoints }))
+        })
+    }
+}
+
+/// Derives the 128-bit challenge `r` for halving `y = x^(2^t)` at `mu`.
+fn challenge(x: &ClassElem, y: &ClassElem, mu: &ClassElem, t: u64) -> Integer {
+    let mut transcript = Transcript::new(b"Classygroup.pietrzak");
+    ClassGroup::append_params(&mut transcript);
+    transcript.append_u64(b"iterations", t);
+    transcript.append_elem::<ClassGroup>(b"x", x);
+    transcript.append_elem::<ClassGroup>(b"y", y);
+    transcript.append_elem::<ClassGroup>(b"mu", mu);
+
+    transcript.challenge_integer(b"r", 128)
+}
+
+/// Replaces `y = x^(2^t)` with the half-length statement through `mu`.
+/// `t` must be even.
+fn fold(x: &mut ClassElem, y: &mut ClassElem, t: &mut u64, mu: &ClassElem) {
+    let r = challenge(x, y, mu, *t);
+    *x = ClassGroup::op(&ClassGroup::pow(x, &r), mu);
+    *y = ClassGroup::op(&ClassGroup::pow(mu, &r), y);
+    *t /= 2;
+}
+
+/// Proves that `y = x^(2^iterations)`.
+///
+/// Each midpoint is recomputed from the folded base, so this costs about
+/// `iterations` squarings on top of the ones that produced `y`.
+#[cfg(feature = "prover")]
+pub fn prove(x: &ClassElem, y: &ClassElem, iterations: u64) -> PietrzakProof {
+    let (mut x, mut y, mut t) = (x.clone(), y.clone(), iterations);
+    let mut midpoints = Vec::new();
+    while t > 1 {
+        if t % 2 == 1 {
+            ClassGroup::square(&mut x);
+            t -= 1;
+        }
+        let mut mu = x.clone();
+        ClassGroup::repeated_square(&mut mu, t / 2);
+        fold(&mut x, &mut y, &mut t, &mu);
+        midpoints.push(mu);
+    }
+    PietrzakProof { midpoints }
+}
+
+/// Runs the verifier, pulling one midpoint at a time from `next`.
+fn verify_midpoints<F>(x: &ClassElem, y: &ClassElem, iterations: u64, mut next: F) -> Result<bool>
+where
+    F: FnMut() -> Result<ClassElem>,
+{
+    let (mut x, mut y, mut t) = (x.clone(), y.clone(), iterations);
+    while t > 1 {
+        if t % 2 == 1 {
+            ClassGroup::square(&mut x);
+            t -= 1;
+        }
+        let mu = next()?;
+        if mu.a.sgn() <= 0 || !ClassGroup::validate(&mu.a, &mu.b, &mu.c) {
+            return Ok(false);
+        }
+        fold(&mut x, &mut y, &mut t, &mu);
+    }
+    if t == 1 {
+        ClassGroup::square(&mut x);
+    }
+    Ok(x == y)
+}
+
+/// Checks a proof that `y = x^(2^iterations)`.
+pub fn verify(x: &ClassElem, y: &ClassElem, iterations: u64, proof: &PietrzakProof) -> bool {
+    let mut midpoints = proof.midpoints.iter();
+    let valid = verify_midpoints(x, y, iterations, || {
+        midpoints
+            .next()
+            .cloned()
+            .ok_or_else(|| anyhow!("proof is too short"))
+    });
+    valid.unwrap_or(false) && midpoints.next().is_none()
+}
+
+/// Checks a proof that `y = x^(2^iterations)` whose midpoints, encoded as by
+/// `PietrzakProof::to_bytes`, are read from `reader` as they are needed.
+///
+/// Only the current statement and one midpoint are held in memory. Reading
+/// stops after the last midpoint, so the proof may be followed by other data.
+/// I/O and decoding errors are returned as errors; a well-formed proof that
+/// does not check out gives `Ok(false)`.
+pub fn verify_stream<R: Read>(
+    x: &ClassElem,
+    y: &ClassElem,
+    iterations: u64,
+    reader: &mut R,
+) -> Result<bool> {
+    verify_midpoints(x, y, iterations, || read_elem(reader))
+}
+
+crate::encoding::impl_hex!(
+    PietrzakProof,
+    PietrzakProof::to_bytes,
+    PietrzakProof::from_bytes
+);
+
+#[cfg(feature = "serde")]
+crate::serialize::impl_serde_bytes!(
+    PietrzakProof,
+    PietrzakProof::to_bytes,
+    PietrzakProof::from_bytes
+);
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+
+    fn statement(iterations: u64) -> (ClassElem, ClassElem) {
+        let x = ClassGroup::unknown_order_elem();
+        let mut y = x.clone();
+        ClassGroup::repeated_square(&mut y, iterations);
+        (x, y)
+    }
+
+    #[test]
+    fn test_prove_verify() {
+        for &t in &[0, 1, 2, 3, 7, 64, 101] {
+            let (x, y) = statement(t);
+            let proof = prove(&x, &y, t);
+            assert!(verify(&x, &y, t, &proof));
+            assert!(!verify(&x, &x, t + 1, &proof));
+
+            let bytes = proof.to_bytes();
+            assert!(verify_stream(&x, &y, t, &mut &bytes[..]).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_proofs() {
+        let (x, y) = statement(100);
+        let proof = prove(&x, &y, 100);
+
+        let mut swapped = proof.clone();
+        swapped.midpoints.swap(0, 1);
+        assert!(!verify(&x, &y, 100, &swapped));
+
+        let mut short = proof.clone();
+        short.midpoints.pop();
+        assert!(!verify(&x, &y, 100, &short));
+
+        let mut long = proof.clone();
+        long.midpoints.push(x.clone());
+        assert!(!verify(&x, &y, 100, &long));
+
+        let wrong_y = ClassGroup::op(&y, &x);
+        assert!(!verify(&x, &wrong_y, 100, &proof));
+
+        let bytes = proof.to_bytes();
+        assert!(verify_stream(&x, &y, 100, &mut &bytes[..bytes.len() - 1]).is_err());
+        let mut reader = &swapped.to_bytes()[..];
+        assert!(!verify_stream(&x, &y, 100, &mut reader).unwrap());
+    }
+
+    #[test]
+    fn test_write_read() {
+        let (x, y) = statement(64);
+        let proof = prove(&x, &y, 64);
+        let mut file = Vec::new();
+        proof.write_to(&mut file).unwrap();
+        assert_eq!(file, proof.to_bytes());
+        assert_eq!(PietrzakProof::read_from(&mut &file[..]).unwrap(), proof);
+        assert!(PietrzakProof::read_from(&mut &file[..file.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_framed_bytes() {
+        for &t in &[0, 1, 5, 64] {
+            let (x, y) = statement(t);
+            let proof = prove(&x, &y, t);
+            assert_eq!(proof.midpoints.len(), PietrzakProof::midpoint_count(t));
+            let bytes = proof.to_framed_bytes(t);
+            assert_eq!(
+                PietrzakProof::from_framed_bytes(&bytes).unwrap(),
+                (t, proof.clone())
+            );
+        }
+
+        let (x, y) = statement(64);
+        let proof = prove(&x, &y, 64);
+        let bytes = proof.to_framed_bytes(64);
+        assert!(PietrzakProof::from_framed_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(PietrzakProof::from_framed_bytes(&[&bytes[..], &[0]].concat()).is_err());
+        // The header cannot claim fewer iterations than the proof covers.
+        assert!(PietrzakProof::from_framed_bytes(&proof.to_framed_bytes(16)).is_err());
+        let mut wrong_type = bytes.clone();
+        wrong_type[..4].copy_from_slice(b"CGWP");
+        assert!(PietrzakProof::from_framed_bytes(&wrong_type).is_err());
+    }
+}
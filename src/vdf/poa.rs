@@ -0,0 +1,111 @@
+//! The byte formats of POA Network's `vdf` and `classgroup` crates.
+//!
+//! Those crates encode an element as `ClassElem::to_bytes` does, `a` and
+//! `b` in two's complement over `(bits + 16) / 16` bytes each, and a VDF
+//! output as the element `y` followed by its proof: the single element `pi`
+//! for Wesolowski and the midpoints in order for Pietrzak. Only the bytes
+//! carry over. Those crates derive discriminants and challenges with their
+//! own hashes, so a proof made by one crate does not verify under the
+//! other; outputs and discriminants stored by them can be read here, and
+//! ours written out for tools that parse their layout.
+
+use super::{PietrzakProof, WesolowskiProof};
+use crate::group::{ClassElem, ClassElemRef, DeserializeError};
+use crate::num::Mpz;
+
+fn write_elems(elems: &[&ClassElem], discriminant_bits: usize) -> Vec<u8> {
+    let size = ClassElem::serialized_size(discriminant_bits);
+    let mut buf = vec![0u8; elems.len() * size];
+    for (x, out) in elems.iter().zip(buf.chunks_mut(size)) {
+        x.to_bytes_into(discriminant_bits, out);
+    }
+    buf
+}
+
+fn read_elems(bytes: &[u8], discriminant: &Mpz) -> Result<Vec<ClassElem>, DeserializeError> {
+    ClassElemRef::split(bytes, discriminant)?
+        .iter()
+        .map(|x| x.decode(discriminant))
+        .collect()
+}
+
+/// `y` and its Wesolowski proof in the layout of `vdf::WesolowskiVDF`.
+pub fn wesolowski_to_bytes(
+    y: &ClassElem,
+    proof: &WesolowskiProof,
+    discriminant_bits: usize,
+) -> Vec<u8> {
+    write_elems(&[y, &proof.pi], discriminant_bits)
+}
+
+/// Parses the output of `vdf::WesolowskiVDF::solve` for `discriminant`.
+pub fn wesolowski_from_bytes(
+    bytes: &[u8],
+    discriminant: &Mpz,
+) -> Result<(ClassElem, WesolowskiProof), DeserializeError> {
+    match &read_elems(bytes, discriminant)?[..] {
+        [y, pi] => Ok((y.clone(), WesolowskiProof { pi: pi.clone() })),
+        _ => Err(DeserializeError::WrongLength {
+            expected: 2 * ClassElem::serialized_size(discriminant.bit_length()),
+            actual: bytes.len(),
+        }),
+    }
+}
+
+/// `y` and its Pietrzak proof in the layout of `vdf::PietrzakVDF`.
+pub fn pietrzak_to_bytes(
+    y: &ClassElem,
+    proof: &PietrzakProof,
+    discriminant_bits: usize,
+) -> Vec<u8> {
+    let elems: Vec<_> = std::iter::once(y).chain(&proof.midpoints).collect();
+    write_elems(&elems, discriminant_bits)
+}
+
+/// Parses the output of `vdf::PietrzakVDF::solve` for `discriminant`.
+pub fn pietrzak_from_bytes(
+    bytes: &[u8],
+    discriminant: &Mpz,
+) -> Result<(ClassElem, PietrzakProof), DeserializeError> {
+    let mut elems = read_elems(bytes, discriminant)?;
+    if elems.is_empty() {
+        return Err(DeserializeError::WrongLength {
+            expected: ClassElem::serialized_size(discriminant.bit_length()),
+            actual: 0,
+        });
+    }
+    let y = elems.remove(0);
+    Ok((y, PietrzakProof { midpoints: elems }))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::group::{create_discriminant, ClassGroup};
+    use crate::vdf::{pietrzak, solve};
+
+    #[test]
+    fn test_poa_layout() {
+        let disc = create_discriminant(b"\xaa", 512);
+        let bits = disc.bit_length();
+        let size = ClassElem::serialized_size(bits);
+        let (y, proof) = solve(b"\xaa", 512, 100);
+        let bytes = wesolowski_to_bytes(&y, &proof, bits);
+        assert_eq!(bytes.len(), 2 * size);
+        assert_eq!(&bytes[..size], &y.to_bytes(bits)[..]);
+        assert_eq!(
+            wesolowski_from_bytes(&bytes, &disc).unwrap(),
+            (y.clone(), proof)
+        );
+        assert!(wesolowski_from_bytes(&bytes[..size], &disc).is_err());
+
+        ClassGroup::with_discriminant(&disc, || {
+            let x = ClassGroup::unknown_order_elem_disc(&disc);
+            let proof = pietrzak::prove(&x, &y, 100);
+            let bytes = pietrzak_to_bytes(&y, &proof, bits);
+            assert_eq!(bytes.len(), (1 + proof.midpoints.len()) * size);
+            assert_eq!(pietrzak_from_bytes(&bytes, &disc).unwrap(), (y, proof));
+        });
+        assert!(pietrzak_from_bytes(&[], &disc).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+//! Sloth, the modular square root delay function of Lenstra and Wesolowski,
+//! "A random zoo: sloth, unicorn, and trx".
+//! https://eprint.iacr.org/2015/366.pdf
+//!
+//! Each step flips the low bit of `x` and takes a canonical square root of `x`
+//! or `-x` modulo a prime `p = 3 mod 4`, which costs an exponentiation to
+//! `(p + 1) / 4`. Undoing a step is a single squaring, so the verifier simply
+//! runs the chain backwards. There is no proof and no group of unknown order,
+//! which makes this a baseline for when class group arithmetic is unavailable.
+
+use super::Vdf;
+use rug::integer::IsPrime;
+use rug::ops::RemRounding;
+use rug::Integer;
+
+lazy_static! {
+    /// The smallest prime `p = 3 mod 4` above `2^1023`.
+    static ref SLOTH_PRIME: Integer = {
+        let mut p: Integer = (Integer::from(1) << 1023) + 3;
+        while p.is_probably_prime(30) == IsPrime::No {
+            p += 4;
+        }
+        p
+    };
+}
+
+/// Sloth over the integers modulo a fixed 1024-bit prime.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Sloth {}
+
+impl Sloth {
+    pub fn prime() -> &'static Integer {
+        &SLOTH_PRIME
+    }
+
+    /// Swaps `x` with its neighbour that differs in the low bit, leaving
+    /// `p - 1` in place since `p` is out of range.
+    fn flip(x: &mut Integer) {
+        x.toggle_bit(0);
+        if *x >= *SLOTH_PRIME {
+            x.toggle_bit(0);
+        }
+    }
+
+    /// Maps `x` to the even square root of `x` if `x` is a square, and to the
+    /// odd square root of `-x` otherwise. Exactly one of the two is a square.
+    fn root(x: &Integer) -> Integer {
+        let p = &*SLOTH_PRIME;
+        let e = Integer::from(p + 1u32) >> 2;
+        let r = x.clone().pow_mod(&e, p).unwrap();
+        let is_square = Integer::from(r.square_ref()) % p == *x;
+        if r.is_even() == is_square {
+            r
+        } else {
+            p - r
+        }
+    }
+
+    /// Inverts `root`.
+    fn unroot(y: &Integer) -> Integer {
+        let p = &*SLOTH_PRIME;
+        let s = Integer::from(y.square_ref()) % p;
+        if y.is_even() || s == 0 {
+            s
+        } else {
+            p - s
+        }
+    }
+}
+
+impl Vdf for Sloth {
+    type Elem = Integer;
+    type Proof = ();
+
+    /// Runs `iterations` steps from `x` reduced modulo the prime.
+    fn eval(x: &Integer, iterations: u64) -> Integer {
+        let mut x = x.clone().rem_euc(&*SLOTH_PRIME);
+        for _ in 0..iterations {
+            Self::flip(&mut x);
+            x = Self::root(&x);
+        }
+        x
+    }
+
+    fn prove(_x: &Integer, _y: &Integer, _iterations: u64) {}
+
+    /// Runs the chain backwards from `y`, one squaring per step. Both `x` and
+    /// `y` must be reduced.
+    fn verify(x: &Integer, y: &Integer, iterations: u64, _proof: &()) -> bool {
+        let p = &*SLOTH_PRIME;
+        if *x < 0 || *x >= *p || *y < 0 || *y >= *p {
+            return false;
+        }
+        let mut y = y.clone();
+        for _ in 0..iterations {
+            y = Self::unroot(&y);
+            Self::flip(&mut y);
+        }
+        y == *x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prime() {
+        let p = Sloth::prime();
+        assert_eq!(p.significant_bits(), 1024);
+        assert_eq!(p.mod_u(4), 3);
+    }
+
+    #[test]
+    fn test_root_is_a_permutation() {
+        let p = Sloth::prime();
+        for x in [0, 1, 2, 3, 4, 12345].iter().map(|&x| Integer::from(x)) {
+            assert_eq!(Sloth::unroot(&Sloth::root(&x)), x);
+        }
+        let top = Integer::from(p - 1u32);
+        assert_eq!(Sloth::unroot(&Sloth::root(&top)), top);
+        let mut flipped = top.clone();
+        Sloth::flip(&mut flipped);
+        assert_eq!(flipped, top);
+    }
+
+    #[test]
+    fn test_eval_verify() {
+        let x = Integer::from(7);
+        let y = Sloth::eval(&x, 50);
+        assert!(Sloth::verify(&x, &y, 50, &()));
+        assert!(!Sloth::verify(&x, &y, 49, &()));
+        assert!(!Sloth::verify(
+            &x,
+            &Integer::from(Sloth::prime() + &y),
+            50,
+            &()
+        ));
+        assert_eq!(Sloth::eval(&Integer::from(Sloth::prime() + &x), 50), y);
+    }
+}
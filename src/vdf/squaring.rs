@@ -0,0 +1,26 @@
+//! Pluggable implementations of the squaring loop.
+//!
+//! Evaluation spends nearly all of its time in `repeated_square`, so that is
+//! the one operation a hardware squarer has to provide. `EvalState` and
+//! `Evaluator` call it through `SquaringBackend`; `CpuBackend` is the default.
+
+use crate::group::{ClassElem, ClassGroup};
+
+/// Computes `x^(2^iterations)` in place.
+///
+/// Backends are called on the evaluating thread with its class group context
+/// set, so `ClassGroup::context_discriminant()` gives the group to square in.
+/// The result must equal that of `ClassGroup::repeated_square`, reduced.
+pub trait SquaringBackend: Send + Sync {
+    fn repeated_square(&self, x: &mut ClassElem, iterations: u64);
+}
+
+/// Squares on the calling thread with `ClassGroup::repeated_square`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CpuBackend;
+
+impl SquaringBackend for CpuBackend {
+    fn repeated_square(&self, x: &mut ClassElem, iterations: u64) {
+        ClassGroup::repeated_square(x, iterations)
+    }
+}
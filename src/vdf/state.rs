@@ -0,0 +1,203 @@
+//! Resumable VDF evaluation.
+//!
+//! An `EvalState` carries everything a long-running evaluation needs to pick
+//! up where it left off: the group, the current element, how far along it is
+//! and the input element that the final proof is generated from.
+
+use super::codec::{read_elem, read_mpz, write_elem, write_mpz};
+use super::{wesolowski, CpuBackend, SquaringBackend, WesolowskiProof};
+use crate::group::{create_discriminant, ClassElem, ClassGroup};
+use crate::num::Mpz;
+use anyhow::{bail, ensure, Result};
+use std::convert::TryInto;
+
+const SNAPSHOT_MAGIC: &[u8; 4] = b"CGES";
+const SNAPSHOT_VERSION: u8 = 1;
+
+/// Progress of a VDF evaluation `y = x^(2^iterations)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvalState {
+    discriminant: Mpz,
+    x: ClassElem,
+    y: ClassElem,
+    done: u64,
+    iterations: u64,
+}
+
+impl EvalState {
+    /// Starts an evaluation of `x^(2^iterations)` in the group of `discriminant`.
+    pub fn new(discriminant: &Mpz, x: ClassElem, iterations: u64) -> Self {
+        Self {
+            discriminant: discriminant.clone(),
+            y: x.clone(),
+            x,
+            done: 0,
+            iterations,
+        }
+    }
+
+    /// Starts the same evaluation as `vdf::solve(seed, length, iterations)`.
+    pub fn from_seed(seed: &[u8], length: u64, iterations: u64) -> Self {
+        let disc = create_discriminant(seed, length);
+        let x = ClassGroup::with_discriminant(&disc, || ClassGroup::unknown_order_elem_disc(&disc));
+        Self::new(&disc, x, iterations)
+    }
+
+    pub fn discriminant(&self) -> &Mpz {
+        &self.discriminant
+    }
+
+    /// The element `x^(2^iterations_done)`.
+    pub fn current(&self) -> &ClassElem {
+        &self.y
+    }
+
+    pub fn iterations_done(&self) -> u64 {
+        self.done
+    }
+
+    pub fn iterations(&self) -> u64 {
+        self.iterations
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.done == self.iterations
+    }
+
+    /// Performs up to `max` more squarings and returns how many were done.
+    pub fn advance(&mut self, max: u64) -> u64 {
+        self.advance_with(&CpuBackend, max)
+    }
+
+    /// `advance` using `backend` for the squarings.
+    pub fn advance_with(&mut self, backend: &dyn SquaringBackend, max: u64) -> u64 {
+        let n = std::cmp::min(max, self.iterations - self.done);
+        let y = &mut self.y;
+        ClassGroup::with_discriminant(&self.discriminant, || backend.repeated_square(y, n));
+        self.done += n;
+        n
+    }
+
+    /// Runs the remaining squarings and proves the output.
+    pub fn finish(self) -> (ClassElem, WesolowskiProof) {
+        self.finish_with(&CpuBackend)
+    }
+
+    /// `finish` using `backend` for the squarings.
+    pub fn finish_with(mut self, backend: &dyn SquaringBackend) -> (ClassElem, WesolowskiProof) {
+        self.advance_with(backend, self.iterations - self.done);
+        let (x, y, iterations) = (&self.x, &self.y, self.iterations);
+        let proof = ClassGroup::with_discriminant(&self.discriminant, || {
+            wesolowski::prove(x, y, iterations)
+        });
+        (self.y, proof)
+    }
+
+    /// Serializes the state so that it can be resumed with `restore`.
+    pub fn snapshot(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(SNAPSHOT_MAGIC);
+        buf.push(SNAPSHOT_VERSION);
+        buf.extend_from_slice(&self.done.to_be_bytes());
+        buf.extend_from_slice(&self.iterations.to_be_bytes());
+        write_mpz(&mut buf, &self.discriminant);
+        write_elem(&mut buf, &self.x);
+        write_elem(&mut buf, &self.y);
+        buf
+    }
+
+    /// Parses a snapshot, checking that both elements belong to its group.
+    pub fn restore(bytes: &[u8]) -> Result<Self> {
+        let mut rest = bytes;
+        ensure!(
+            take(&mut rest, 4)? == SNAPSHOT_MAGIC,
+            "not an evaluation snapshot"
+        );
+        let version = take(&mut rest, 1)?[0];
+        ensure!(
+            version == SNAPSHOT_VERSION,
+            "unsupported snapshot version {}",
+            version
+        );
+        let done = read_u64(&mut rest)?;
+        let iterations = read_u64(&mut rest)?;
+        ensure!(done <= iterations, "snapshot is past its iteration count");
+
+        let discriminant = read_mpz(&mut rest)?;
+        ensure!(discriminant.is_neg(), "discriminant must be negative");
+        let x = read_elem(&mut rest)?;
+        let y = read_elem(&mut rest)?;
+        ensure!(rest.is_empty(), "trailing bytes after snapshot");
+
+        let valid = ClassGroup::with_discriminant(&discriminant, || {
+            [&x, &y]
+                .iter()
+                .all(|e| e.a.sgn() > 0 && ClassGroup::validate(&e.a, &e.b, &e.c))
+        });
+        ensure!(valid, "snapshot element is not in the snapshot's group");
+
+        Ok(Self {
+            discriminant,
+            x,
+            y,
+            done,
+            iterations,
+        })
+    }
+}
+
+fn take<'a>(rest: &mut &'a [u8], n: usize) -> Result<&'a [u8]> {
+    if rest.len() < n {
+        bail!("snapshot is truncated");
+    }
+    let (head, tail) = rest.split_at(n);
+    *rest = tail;
+    Ok(head)
+}
+
+fn read_u64(rest: &mut &[u8]) -> Result<u64> {
+    Ok(u64::from_be_bytes(take(rest, 8)?.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vdf;
+
+    #[test]
+    fn test_snapshot_restore_resumes() {
+        let mut state = EvalState::from_seed(b"snapshot", 256, 500);
+        assert_eq!(state.advance(200), 200);
+
+        let restored = EvalState::restore(&state.snapshot()).unwrap();
+        assert_eq!(restored, state);
+        assert_eq!(restored.iterations_done(), 200);
+
+        let (y, proof) = restored.finish();
+        assert_eq!((y, proof), vdf::solve(b"snapshot", 256, 500));
+
+        assert_eq!(state.advance(1000), 300);
+        assert!(state.is_finished());
+    }
+
+    #[test]
+    fn test_restore_rejects_bad_snapshots() {
+        let state = EvalState::from_seed(b"snapshot", 256, 10);
+        let bytes = state.snapshot();
+
+        assert!(EvalState::restore(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(EvalState::restore(&trailing).is_err());
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 2;
+        assert!(EvalState::restore(&bad_version).is_err());
+
+        // Flip a bit in the last coefficient so `y` leaves the group.
+        let mut corrupt = bytes;
+        *corrupt.last_mut().unwrap() ^= 1;
+        assert!(EvalState::restore(&corrupt).is_err());
+    }
+}
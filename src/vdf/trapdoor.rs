@@ -0,0 +1,114 @@
+//! Shortcuts for groups whose order is known.
+//!
+//! With the order `h` of `x` (or any multiple of it, such as the class
+//! number), `x^(2^T) = x^(2^T mod h)` and the Wesolowski quotient can be
+//! reduced the same way, so evaluation and proving take a few exponentiations
+//! instead of `T` squarings. This is only sound for test groups: for a real
+//! deployment the order must be unknown.
+
+use super::{ChallengePolicy, WesolowskiProof};
+use crate::group::{ClassElem, ClassGroup};
+use rug::Integer;
+
+/// Computes `x^(2^iterations)` given a multiple `order` of the order of `x`.
+pub fn eval_with_trapdoor(x: &ClassElem, iterations: u64, order: &Integer) -> ClassElem {
+    let e = Integer::from(2)
+        .pow_mod(&Integer::from(iterations), order)
+        .expect("order is positive");
+    ClassGroup::pow(x, &e)
+}
+
+/// Produces the same proof as `wesolowski::prove` given a multiple `order` of
+/// the order of `x`.
+pub fn prove_with_trapdoor(
+    x: &ClassElem,
+    y: &ClassElem,
+    iterations: u64,
+    order: &Integer,
+) -> WesolowskiProof {
+    prove_with_trapdoor_with(&ChallengePolicy::default(), x, y, iterations, order)
+}
+
+/// `prove_with_trapdoor` with an explicit challenge policy, giving the same
+/// proof as `wesolowski::prove_with`.
+pub fn prove_with_trapdoor_with(
+    policy: &ChallengePolicy,
+    x: &ClassElem,
+    y: &ClassElem,
+    iterations: u64,
+    order: &Integer,
+) -> WesolowskiProof {
+    let l = policy.challenge(x, y, iterations);
+    let t = Integer::from(iterations);
+
+    // `l * (q mod h) = (2^T - r) mod (l * h)` for `q = floor(2^T / l)`.
+    let m = Integer::from(&l * order);
+    let r = Integer::from(2).pow_mod(&t, &l).expect("l is positive");
+    let mut e = Integer::from(2).pow_mod(&t, &m).expect("m is positive") - r;
+    if e < 0 {
+        e += &m;
+    }
+    WesolowskiProof {
+        pi: ClassGroup::pow(x, &(e / l)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::num::Mpz;
+    use crate::vdf::{wesolowski, ProofParams};
+    use std::str::FromStr;
+
+    /// Counts the reduced forms of discriminant `d`.
+    fn class_number(d: i64) -> u64 {
+        let mut h = 0;
+        let mut a = 1;
+        while 3 * a * a <= -d {
+            for b in -a + 1..=a {
+                let n = b * b - d;
+                if n % (4 * a) != 0 {
+                    continue;
+                }
+                let c = n / (4 * a);
+                if c > a || (c == a && b >= 0) {
+                    h += 1;
+                }
+            }
+            a += 1;
+        }
+        h
+    }
+
+    #[test]
+    fn test_trapdoor_matches_squaring() {
+        // -1000039 is prime and 1 mod 8.
+        let d = -1_000_039;
+        let order = Integer::from(class_number(d));
+        let disc = Mpz::from_str(&d.to_string()).unwrap();
+
+        ClassGroup::with_discriminant(&disc, || {
+            let x = ClassGroup::unknown_order_elem_disc(&disc);
+            assert_eq!(ClassGroup::pow(&x, &order), ClassGroup::id());
+
+            let mut y = x.clone();
+            ClassGroup::repeated_square(&mut y, 1000);
+            assert_eq!(eval_with_trapdoor(&x, 1000, &order), y);
+            assert_eq!(
+                prove_with_trapdoor(&x, &y, 1000, &order),
+                wesolowski::prove(&x, &y, 1000)
+            );
+
+            let t = 1 << 40;
+            let y = eval_with_trapdoor(&x, t, &order);
+            let proof = prove_with_trapdoor(&x, &y, t, &order);
+            assert!(wesolowski::verify(&x, &y, t, &proof));
+
+            // Proofs for other parameters verify under the matching policy.
+            let policy = ProofParams::new(2, 128, 1).unwrap().challenge_policy();
+            let proof = prove_with_trapdoor_with(&policy, &x, &y, t, &order);
+            assert!(wesolowski::verify_with(&policy, &x, &y, t, &proof));
+            assert!(!wesolowski::verify(&x, &y, t, &proof));
+        });
+    }
+}
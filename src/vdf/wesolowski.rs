@@ -0,0 +1,357 @@
+//! Wesolowski's proof of exponentiation, "Efficient verifiable delay functions".
+//! https://eprint.iacr.org/2018/623.pdf
+//!
+//! To prove `y = x^(2^T)` the prover derives a challenge prime `l` from the
+//! statement and publishes `pi = x^floor(2^T / l)`. The verifier then checks
+//! `pi^l * x^r = y` with `r = 2^T mod l`, which needs only two small
+//! exponentiations.
+
+use super::codec::{read_all, read_elem, read_proof_header, write_elem, write_proof_header};
+use super::ChallengePolicy;
+use crate::encoding::json_object;
+use crate::group::{ClassElem, ClassGroup, UnknownOrderGroup};
+use anyhow::{ensure, Result};
+use rug::Integer;
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+#[cfg(feature = "prover")]
+use std::thread;
+
+const FRAME_MAGIC: &[u8; 4] = b"CGWP";
+
+/// A proof that `y = x^(2^T)` for some `x`, `y` and iteration count `T`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WesolowskiProof {
+    pub pi: ClassElem,
+}
+
+/// `x * 2^bits`. Shifting by `bits as u32` would wrap for `bits >= 2^32`.
+fn shl(x: Integer, bits: u64) -> Integer {
+    x << usize::try_from(bits).expect("shift exceeds the address space")
+}
+
+/// The proof `x^floor(2^iterations / l)` for the challenge `l`, in any group
+/// of unknown order.
+pub(crate) fn prove_in<G: UnknownOrderGroup>(x: &G::Elem, l: &Integer, iterations: u64) -> G::Elem {
+    G::pow(x, &(shl(Integer::from(1), iterations) / l))
+}
+
+/// Whether `pi^l * x^r = y` for `r = 2^iterations mod l`. Checking that `pi`
+/// is an element is left to the caller.
+pub(crate) fn verify_in<G: UnknownOrderGroup>(
+    x: &G::Elem,
+    y: &G::Elem,
+    pi: &G::Elem,
+    l: &Integer,
+    iterations: u64,
+) -> bool {
+    let r = Integer::from(2)
+        .pow_mod(&Integer::from(iterations), l)
+        .expect("l is a positive prime");
+    G::op(&G::pow(pi, l), &G::pow(x, &r)) == *y
+}
+
+/// Proves that `y = x^(2^iterations)`, where `y` has already been computed by
+/// repeated squaring.
+#[cfg(feature = "prover")]
+pub fn prove(x: &ClassElem, y: &ClassElem, iterations: u64) -> WesolowskiProof {
+    prove_with(&ChallengePolicy::default(), x, y, iterations)
+}
+
+/// `prove` with an explicit challenge policy.
+#[cfg(feature = "prover")]
+pub fn prove_with(
+    policy: &ChallengePolicy,
+    x: &ClassElem,
+    y: &ClassElem,
+    iterations: u64,
+) -> WesolowskiProof {
+    let l = policy.challenge(x, y, iterations);
+    WesolowskiProof {
+        pi: prove_in::<ClassGroup>(x, &l, iterations),
+    }
+}
+
+/// Width in bits of the quotient block handled by each of `threads` workers.
+#[cfg(feature = "prover")]
+fn block_width(iterations: u64, threads: usize) -> u64 {
+    let threads = std::cmp::max(threads, 1) as u64;
+    iterations.div_ceil(threads)
+}
+
+/// Computes `floor(2^e / l) mod 2^w`, one block of long-division digits of
+/// `floor(2^T / l)`, without computing the digits above it.
+#[cfg(feature = "prover")]
+fn quotient_block(l: &Integer, e: u64, w: u64) -> Integer {
+    if e <= w {
+        return shl(Integer::from(1), e) / l;
+    }
+    let r = Integer::from(2)
+        .pow_mod(&Integer::from(e - w), l)
+        .expect("l is a positive prime");
+    shl(r, w) / l
+}
+
+/// Computes `x^(2^iterations)` and also returns the powers `x^(2^(j * w))`
+/// that `prove_parallel` needs to split the proof across `threads` workers.
+#[cfg(feature = "prover")]
+pub fn eval_with_powers(
+    x: &ClassElem,
+    iterations: u64,
+    threads: usize,
+) -> (ClassElem, Vec<ClassElem>) {
+    let block = block_width(iterations, threads);
+    let mut powers = Vec::with_capacity(threads);
+    let mut y = x.clone();
+    let mut done = 0;
+    while done < iterations {
+        powers.push(y.clone());
+        let n = std::cmp::min(block, iterations - done);
+        ClassGroup::repeated_square(&mut y, n);
+        done += n;
+    }
+    (y, powers)
+}
+
+/// Proves that `y = x^(2^iterations)` from `powers`, as returned by
+/// `eval_with_powers`, spread over at most `available_parallelism` threads.
+///
+/// Each `powers[j]` is raised to its own block of the quotient
+/// `floor(2^T / l)`; the partial results are multiplied together at the end.
+/// The proof is identical to the one computed by `prove`.
+#[cfg(feature = "prover")]
+pub fn prove_parallel(
+    x: &ClassElem,
+    y: &ClassElem,
+    iterations: u64,
+    powers: &[ClassElem],
+) -> WesolowskiProof {
+    prove_parallel_with(&ChallengePolicy::default(), x, y, iterations, powers)
+}
+
+/// `prove_parallel` with an explicit challenge policy.
+#[cfg(feature = "prover")]
+pub fn prove_parallel_with(
+    policy: &ChallengePolicy,
+    x: &ClassElem,
+    y: &ClassElem,
+    iterations: u64,
+    powers: &[ClassElem],
+) -> WesolowskiProof {
+    if powers.len() <= 1 {
+        return prove_with(policy, x, y, iterations);
+    }
+
+    let l = policy.challenge(x, y, iterations);
+    let block = block_width(iterations, powers.len());
+    assert_eq!(
+        iterations.div_ceil(block),
+        powers.len() as u64,
+        "powers were not computed by eval_with_powers"
+    );
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    WesolowskiProof {
+        pi: quotient_power(&l, iterations, powers, threads),
+    }
+}
+
+/// `x^floor(2^iterations / l)` from `powers` on `threads` scoped workers,
+/// each taking a run of consecutive blocks and switching to the caller's
+/// discriminant once for all of them.
+#[cfg(feature = "prover")]
+fn quotient_power(l: &Integer, iterations: u64, powers: &[ClassElem], threads: usize) -> ClassElem {
+    let block = block_width(iterations, powers.len());
+    let run = powers.len().div_ceil(std::cmp::max(threads, 1));
+    let disc = ClassGroup::context_discriminant();
+    thread::scope(|s| {
+        let workers: Vec<_> = powers
+            .chunks(run)
+            .enumerate()
+            .map(|(i, bases)| {
+                let disc = &disc;
+                s.spawn(move || {
+                    ClassGroup::with_discriminant(disc, || {
+                        bases
+                            .iter()
+                            .enumerate()
+                            .fold(ClassGroup::id(), |acc, (k, base)| {
+                                let e = iterations - (i * run + k) as u64 * block;
+                                let part = ClassGroup::pow(base, &quotient_block(l, e, block));
+                                ClassGroup::op(&acc, &part)
+                            })
+                    })
+                })
+            })
+            .collect();
+        workers.into_iter().fold(ClassGroup::id(), |acc, worker| {
+            ClassGroup::op(&acc, &worker.join().expect("prover thread panicked"))
+        })
+    })
+}
+
+/// Checks a proof that `y = x^(2^iterations)`.
+pub fn verify(x: &ClassElem, y: &ClassElem, iterations: u64, proof: &WesolowskiProof) -> bool {
+    verify_with(&ChallengePolicy::default(), x, y, iterations, proof)
+}
+
+/// `verify` with an explicit challenge policy.
+pub fn verify_with(
+    policy: &ChallengePolicy,
+    x: &ClassElem,
+    y: &ClassElem,
+    iterations: u64,
+    proof: &WesolowskiProof,
+) -> bool {
+    let pi = &proof.pi;
+    if !ClassGroup::validate(&pi.a, &pi.b, &pi.c) {
+        return false;
+    }
+
+    let l = policy.challenge(x, y, iterations);
+    verify_in::<ClassGroup>(x, y, pi, &l, iterations)
+}
+
+impl WesolowskiProof {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_elem(&mut buf, &self.pi);
+        buf
+    }
+
+    /// Parses the encoding of `to_bytes`. Group membership is left to
+    /// `verify`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        read_all(bytes, |r| Self::read_from(r))
+    }
+
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.pi.write_to(w)
+    }
+
+    /// Reads a proof from `write_to`, reading no further than its end.
+    pub fn read_from<R: Read>(r: &mut R) -> Result<Self> {
+        Ok(Self { pi: read_elem(r)? })
+    }
+
+    /// The proof as JSON with every number in decimal, for debugging.
+    pub fn to_json_debug(&self) -> String {
+        json_object(&[("pi", self.pi.to_json_debug())])
+    }
+
+    /// Encodes the proof behind a header recording `iterations`, for
+    /// sending to peers.
+    pub fn to_framed_bytes(&self, iterations: u64) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_proof_header(&mut buf, FRAME_MAGIC, iterations, 1);
+        write_elem(&mut buf, &self.pi);
+        buf
+    }
+
+    /// Parses the encoding of `to_framed_bytes`, returning the iteration
+    /// count with the proof. Anything but a header for exactly one element
+    /// followed by that element is rejected.
+    pub fn from_framed_bytes(bytes: &[u8]) -> Result<(u64, Self)> {
+        read_all(bytes, |r| {
+            let (iterations, segments) = read_proof_header(r, FRAME_MAGIC)?;
+            ensure!(segments == 1, "a Wesolowski proof has one element");
+            Ok((iterations, Self::read_from(r)?))
+        })
+    }
+}
+
+crate::encoding::impl_hex!(
+    WesolowskiProof,
+    WesolowskiProof::to_bytes,
+    WesolowskiProof::from_bytes
+);
+
+#[cfg(feature = "serde")]
+crate::serialize::impl_serde_bytes!(
+    WesolowskiProof,
+    WesolowskiProof::to_bytes,
+    WesolowskiProof::from_bytes
+);
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prove_verify() {
+        let x = ClassGroup::unknown_order_elem();
+        let mut y = x.clone();
+        ClassGroup::repeated_square(&mut y, 100);
+
+        let proof = prove(&x, &y, 100);
+        assert!(verify(&x, &y, 100, &proof));
+        assert!(!verify(&x, &y, 99, &proof));
+        assert!(!verify(&x, &x, 100, &proof));
+
+        let bad_proof = WesolowskiProof {
+            pi: ClassGroup::op(&proof.pi, &x),
+        };
+        assert!(!verify(&x, &y, 100, &bad_proof));
+    }
+
+    #[test]
+    fn test_policy_must_match() {
+        use crate::vdf::PrimeBits;
+
+        // Past 256 squarings `pi` depends on `l`.
+        let x = ClassGroup::unknown_order_elem();
+        let mut y = x.clone();
+        ClassGroup::repeated_square(&mut y, 300);
+
+        let short = ChallengePolicy {
+            prime_bits: PrimeBits::Bits128,
+            ..ChallengePolicy::default()
+        };
+        let proof = prove_with(&short, &x, &y, 300);
+        assert!(verify_with(&short, &x, &y, 300, &proof));
+        assert!(!verify(&x, &y, 300, &proof));
+
+        let alice = ChallengePolicy::watermarked(b"alice");
+        let bob = ChallengePolicy::watermarked(b"bob");
+        let attributed = prove_with(&alice, &x, &y, 300);
+        assert!(verify_with(&alice, &x, &y, 300, &attributed));
+        assert!(!verify_with(&bob, &x, &y, 300, &attributed));
+        assert!(!verify(&x, &y, 300, &attributed));
+
+        let (_, powers) = eval_with_powers(&x, 300, 3);
+        assert_eq!(prove_parallel_with(&short, &x, &y, 300, &powers), proof);
+    }
+
+    #[test]
+    fn test_quotient_blocks() {
+        let l = Integer::from(1_000_003);
+        let q = (Integer::from(1) << 200) / &l;
+        let mut blocks = Integer::new();
+        for j in (0..4).rev() {
+            blocks <<= 50;
+            blocks += quotient_block(&l, 200 - j * 50, 50);
+        }
+        assert_eq!(blocks, q);
+    }
+
+    #[test]
+    fn test_prove_parallel() {
+        let x = ClassGroup::unknown_order_elem();
+        for &(iterations, threads) in &[(100, 4), (97, 3), (10, 16), (5, 1)] {
+            let (y, powers) = eval_with_powers(&x, iterations, threads);
+            let mut y_seq = x.clone();
+            ClassGroup::repeated_square(&mut y_seq, iterations);
+            assert_eq!(y, y_seq);
+
+            let proof = prove_parallel(&x, &y, iterations, &powers);
+            assert_eq!(proof, prove(&x, &y, iterations));
+            assert!(verify(&x, &y, iterations, &proof));
+
+            // However the blocks are shared out between workers.
+            let l = ChallengePolicy::default().challenge(&x, &y, iterations);
+            for workers in 1..=powers.len() + 1 {
+                assert_eq!(quotient_power(&l, iterations, &powers, workers), proof.pi);
+            }
+        }
+    }
+}
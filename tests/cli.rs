@@ -0,0 +1,48 @@
+//! Runs the `classy` binary end to end.
+
+#![cfg(feature = "cli")]
+
+use std::process::{Command, Output};
+
+fn classy(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_classy"))
+        .args(args)
+        .output()
+        .expect("failed to run classy")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_prove_verify() {
+    let proved = classy(&["prove", "aa", "512", "100"]);
+    assert_eq!(proved.status.code(), Some(0));
+    let proof = stdout(&proved).trim().to_string();
+
+    let valid = classy(&["verify", "aa", "512", "100", &proof]);
+    assert_eq!(valid.status.code(), Some(0));
+    assert_eq!(stdout(&valid).trim(), "Proof is valid");
+
+    let invalid = classy(&["verify", "aa", "512", "101", &proof]);
+    assert_eq!(invalid.status.code(), Some(1));
+    assert_eq!(stdout(&invalid).trim(), "Proof is invalid");
+}
+
+#[test]
+fn test_errors() {
+    for args in &[
+        &[][..],
+        &["prove", "aa", "512"],
+        &["prove", "zz", "512", "100"],
+        &["prove", "aa", "63", "100"],
+        &["prove", "aa", "16385", "100"],
+        &["discriminant", "aa", "0"],
+        &["verify", "aa", "512", "100", "00"],
+    ] {
+        let output = classy(args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(output.stdout.is_empty(), "{:?}", args);
+    }
+}